@@ -17,9 +17,9 @@ break
     
 end
 
-* = $FFFA
+* = $FFFC
 .dsb (*-end), 0
-* = $FFFA
+* = $FFFC
 
-.word break
-.word reset
\ No newline at end of file
+.word reset
+.word break
\ No newline at end of file
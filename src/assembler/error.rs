@@ -0,0 +1,57 @@
+use crate::cpu::instruction::InstructionType;
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub enum ErrorKind {
+    Syntax(String),
+    UnknownMnemonic(String),
+    UnknownDirective(String),
+    UndefinedSymbol(String),
+    DuplicateSymbol(String),
+    /// the instruction has no opcode for the addressing of its operand
+    InvalidAddressing(InstructionType, String),
+    ValueOutOfRange(i64),
+    BranchOutOfRange(i64),
+    DivisionByZero,
+    ProgramTooLarge,
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Syntax(message) => write!(f, "Syntax error: {}", message),
+            Self::UnknownMnemonic(mnemonic) => write!(f, "Unknown mnemonic {}", mnemonic),
+            Self::UnknownDirective(directive) => write!(f, "Unknown directive {}", directive),
+            Self::UndefinedSymbol(symbol) => write!(f, "Undefined symbol {}", symbol),
+            Self::DuplicateSymbol(symbol) => write!(f, "Symbol {} is already defined", symbol),
+            Self::InvalidAddressing(instruction_type, operand) => {
+                write!(
+                    f,
+                    "{} can't be used with operand `{}`",
+                    instruction_type, operand
+                )
+            }
+            Self::ValueOutOfRange(value) => write!(f, "Value {} is out of range", value),
+            Self::BranchOutOfRange(distance) => {
+                write!(f, "Branch target is {} bytes away, out of range", distance)
+            }
+            Self::DivisionByZero => write!(f, "Division by zero"),
+            Self::ProgramTooLarge => write!(f, "Program doesn't fit in 64K"),
+        }
+    }
+}
+
+/// An error along with the source line it was found at, starting from 1
+#[derive(Debug)]
+pub struct AssemblerError {
+    pub line: usize,
+    pub kind: ErrorKind,
+}
+
+impl Display for AssemblerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+impl std::error::Error for AssemblerError {}
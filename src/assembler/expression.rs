@@ -0,0 +1,274 @@
+use super::error::ErrorKind;
+use std::{collections::HashMap, iter::Peekable, vec::IntoIter};
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Number(i64),
+    Symbol(String),
+    /// `*`, the address of the current line
+    ProgramCounter,
+    Unary(Unary, Box<Expr>),
+    Binary(Binary, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Unary {
+    Negate,
+    Not,
+    LowByte,
+    HighByte,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Binary {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    And,
+    Or,
+    Xor,
+    ShiftLeft,
+    ShiftRight,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(i64),
+    Symbol(String),
+    Punct(&'static str),
+}
+
+const PUNCTS: [&str; 14] = [
+    "<<", ">>", "+", "-", "*", "/", "&", "|", "^", "~", "<", ">", "(", ")",
+];
+
+/// What symbols and `*` evaluate to
+pub struct Context<'a> {
+    pub symbols: &'a HashMap<String, i64>,
+    pub program_counter: i64,
+}
+
+impl Expr {
+    /// Parses xa style expressions: `$` hex, `%` binary and decimal numbers, quoted characters,
+    /// symbols, `*`, the usual arithmetic and bitwise operators and parentheses.
+    /// A leading `<` or `>` takes the low or high byte of everything after it
+    pub fn parse(text: &str) -> Result<Expr, ErrorKind> {
+        let mut tokens = tokenize(text)?.into_iter().peekable();
+        let expr = parse_expression(&mut tokens)?;
+
+        match tokens.next() {
+            None => Ok(expr),
+            Some(token) => Err(unexpected(Some(token))),
+        }
+    }
+
+    pub fn eval(&self, context: &Context) -> Result<i64, ErrorKind> {
+        Ok(match self {
+            Expr::Number(x) => *x,
+            Expr::Symbol(name) => *context
+                .symbols
+                .get(name)
+                .ok_or_else(|| ErrorKind::UndefinedSymbol(name.clone()))?,
+            Expr::ProgramCounter => context.program_counter,
+
+            Expr::Unary(op, x) => {
+                let x = x.eval(context)?;
+
+                match op {
+                    Unary::Negate => x.wrapping_neg(),
+                    Unary::Not => !x,
+                    Unary::LowByte => x & 0xFF,
+                    Unary::HighByte => (x >> 8) & 0xFF,
+                }
+            }
+
+            Expr::Binary(op, x, y) => {
+                let (x, y) = (x.eval(context)?, y.eval(context)?);
+
+                match op {
+                    Binary::Add => x.wrapping_add(y),
+                    Binary::Subtract => x.wrapping_sub(y),
+                    Binary::Multiply => x.wrapping_mul(y),
+                    Binary::Divide if y == 0 => return Err(ErrorKind::DivisionByZero),
+                    Binary::Divide => x.wrapping_div(y),
+                    Binary::And => x & y,
+                    Binary::Or => x | y,
+                    Binary::Xor => x ^ y,
+                    Binary::ShiftLeft => x.wrapping_shl(y.clamp(0, 63) as u32),
+                    Binary::ShiftRight => x.wrapping_shr(y.clamp(0, 63) as u32),
+                }
+            }
+        })
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, ErrorKind> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        let token = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+
+            '$' | '%' | '0'..='9' => {
+                let radix = match c {
+                    '$' => 16,
+                    '%' => 2,
+                    _ => 10,
+                };
+
+                if radix != 10 {
+                    chars.next();
+                }
+
+                let mut digits = String::new();
+                while let Some(&d) = chars.peek().filter(|d| d.is_ascii_alphanumeric()) {
+                    digits.push(d);
+                    chars.next();
+                }
+
+                let number = i64::from_str_radix(&digits, radix)
+                    .map_err(|_| ErrorKind::Syntax(format!("invalid number {}{}", c, digits)))?;
+
+                Token::Number(number)
+            }
+
+            '\'' | '"' => {
+                chars.next();
+                let quoted = chars.next();
+
+                match (quoted, chars.next()) {
+                    (Some(x), Some(quote)) if quote == c => Token::Number(x as i64),
+                    _ => return Err(ErrorKind::Syntax("invalid character literal".into())),
+                }
+            }
+
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut name = String::new();
+                while let Some(&x) = chars
+                    .peek()
+                    .filter(|x| x.is_ascii_alphanumeric() || **x == '_')
+                {
+                    name.push(x);
+                    chars.next();
+                }
+
+                Token::Symbol(name)
+            }
+
+            _ => {
+                let rest: String = chars.clone().take(2).collect();
+                let punct = PUNCTS
+                    .iter()
+                    .find(|punct| rest.starts_with(*punct))
+                    .ok_or_else(|| ErrorKind::Syntax(format!("unexpected character {}", c)))?;
+
+                for _ in 0..punct.len() {
+                    chars.next();
+                }
+
+                Token::Punct(punct)
+            }
+        };
+
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+type Tokens = Peekable<IntoIter<Token>>;
+
+fn unexpected(token: Option<Token>) -> ErrorKind {
+    ErrorKind::Syntax(match token {
+        Some(Token::Number(x)) => format!("unexpected number {}", x),
+        Some(Token::Symbol(name)) => format!("unexpected symbol {}", name),
+        Some(Token::Punct(punct)) => format!("unexpected {}", punct),
+        None => "unexpected end of expression".into(),
+    })
+}
+
+fn parse_expression(tokens: &mut Tokens) -> Result<Expr, ErrorKind> {
+    let byte = match tokens.peek() {
+        Some(Token::Punct("<")) => Some(Unary::LowByte),
+        Some(Token::Punct(">")) => Some(Unary::HighByte),
+        _ => None,
+    };
+
+    match byte {
+        Some(op) => {
+            tokens.next();
+            Ok(Expr::Unary(op, Box::new(parse_binary(tokens, 0)?)))
+        }
+        None => parse_binary(tokens, 0),
+    }
+}
+
+/// Binary operators, from the loosest binding to the tightest
+const PRECEDENCE: [&[(&str, Binary)]; 6] = [
+    &[("|", Binary::Or)],
+    &[("^", Binary::Xor)],
+    &[("&", Binary::And)],
+    &[("<<", Binary::ShiftLeft), (">>", Binary::ShiftRight)],
+    &[("+", Binary::Add), ("-", Binary::Subtract)],
+    &[("*", Binary::Multiply), ("/", Binary::Divide)],
+];
+
+fn parse_binary(tokens: &mut Tokens, level: usize) -> Result<Expr, ErrorKind> {
+    if level == PRECEDENCE.len() {
+        return parse_unary(tokens);
+    }
+
+    let mut expr = parse_binary(tokens, level + 1)?;
+
+    loop {
+        let op = match tokens.peek() {
+            Some(Token::Punct(punct)) => PRECEDENCE[level]
+                .iter()
+                .find(|(symbol, _)| symbol == punct)
+                .map(|&(_, op)| op),
+            _ => None,
+        };
+
+        match op {
+            Some(op) => {
+                tokens.next();
+                let rhs = parse_binary(tokens, level + 1)?;
+                expr = Expr::Binary(op, Box::new(expr), Box::new(rhs));
+            }
+            None => return Ok(expr),
+        }
+    }
+}
+
+fn parse_unary(tokens: &mut Tokens) -> Result<Expr, ErrorKind> {
+    let op = match tokens.peek() {
+        Some(Token::Punct("-")) => Unary::Negate,
+        Some(Token::Punct("~")) => Unary::Not,
+        _ => return parse_primary(tokens),
+    };
+
+    tokens.next();
+    Ok(Expr::Unary(op, Box::new(parse_unary(tokens)?)))
+}
+
+fn parse_primary(tokens: &mut Tokens) -> Result<Expr, ErrorKind> {
+    match tokens.next() {
+        Some(Token::Number(x)) => Ok(Expr::Number(x)),
+        Some(Token::Symbol(name)) => Ok(Expr::Symbol(name)),
+        Some(Token::Punct("*")) => Ok(Expr::ProgramCounter),
+        Some(Token::Punct("(")) => {
+            let expr = parse_expression(tokens)?;
+
+            match tokens.next() {
+                Some(Token::Punct(")")) => Ok(expr),
+                token => Err(unexpected(token)),
+            }
+        }
+        token => Err(unexpected(token)),
+    }
+}
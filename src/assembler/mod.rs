@@ -0,0 +1,772 @@
+use self::{
+    error::{AssemblerError, ErrorKind},
+    expression::{Context, Expr},
+};
+use crate::cpu::{
+    addressable_bus::DataBus,
+    instruction::{Addressing, Instruction, InstructionType},
+    variant::Variant,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+pub mod error;
+pub mod expression;
+
+/// The output of the assembler: one contiguous image, like `xa` produces.
+/// `* =` moves the program counter without leaving any gap in the image
+#[derive(Debug, Clone)]
+pub struct Assembly {
+    /// address of the first byte of the image
+    pub origin: u16,
+    pub image: Vec<u8>,
+    /// labels and `name = value` definitions
+    pub symbols: BTreeMap<String, u16>,
+    /// which of `symbols` are labels, the rest being definitions
+    pub labels: BTreeSet<String>,
+    /// address of each instruction, by source line number starting from 1
+    pub lines: BTreeMap<usize, u16>,
+}
+
+impl Assembly {
+    /// Copies the image into `bus` at its origin, wrapping around 64K
+    pub fn load_into(&self, bus: &mut impl DataBus) {
+        for (i, &byte) in self.image.iter().enumerate() {
+            bus.set(self.origin.wrapping_add(i as u16), byte);
+        }
+    }
+}
+
+/// Assembles xa style `source` for the documented NMOS instruction set
+pub fn assemble(source: &str) -> Result<Assembly, AssemblerError> {
+    assemble_variant(source, Variant::Nmos6502, false)
+}
+
+/// Like [`assemble`], accepting the instructions `variant` understands
+pub fn assemble_variant(
+    source: &str,
+    variant: Variant,
+    undocumented: bool,
+) -> Result<Assembly, AssemblerError> {
+    let mut assembler = Assembler::new(variant, undocumented);
+    let lines = assembler.parse(source)?;
+
+    assembler.first_pass(&lines)?;
+    assembler.second_pass(&lines)
+}
+
+/// Syntax of an instruction operand, before it's known
+/// whether it fits in page zero
+#[derive(Debug, Clone)]
+enum Operand {
+    None,
+    Accumulator,
+    Immediate(Expr),
+    /// the flag forces absolute addressing, as in `LDA !$10`
+    Direct(Expr, bool),
+    IndexedX(Expr, bool),
+    IndexedY(Expr, bool),
+    Indirect(Expr),
+    IndirectX(Expr),
+    IndirectY(Expr),
+    /// zeropage address and branch target of BBR and BBS
+    BitBranch(Expr, Expr),
+}
+
+#[derive(Debug, Clone)]
+enum Data {
+    Bytes(Vec<u8>),
+    Expr(Expr),
+}
+
+#[derive(Debug, Clone)]
+enum Statement {
+    Empty,
+    Origin(Expr),
+    Define(String, Expr),
+    Instruction(InstructionType, Operand, String),
+    Bytes(Vec<Data>),
+    Words(Vec<Expr>),
+    Fill(Expr, Option<Expr>),
+}
+
+struct Line {
+    number: usize,
+    label: Option<String>,
+    statement: Statement,
+}
+
+struct Assembler {
+    variant: Variant,
+    undocumented: bool,
+    mnemonics: HashMap<String, InstructionType>,
+    symbols: HashMap<String, i64>,
+    /// addressing chosen by the first pass for each line,
+    /// so that both passes agree on instruction sizes
+    addressing: Vec<Option<Addressing>>,
+}
+
+impl Assembler {
+    fn new(variant: Variant, undocumented: bool) -> Self {
+        // every mnemonic the decoder knows about for this variant
+        let mnemonics = (0..=0xFF)
+            .filter_map(|opcode| Instruction::read_variant(variant, undocumented, opcode, || 0))
+            .map(|instr| (instr.instruction_type.to_string(), instr.instruction_type))
+            .collect();
+
+        Self {
+            variant,
+            undocumented,
+            mnemonics,
+            symbols: HashMap::new(),
+            addressing: Vec::new(),
+        }
+    }
+
+    fn parse(&self, source: &str) -> Result<Vec<Line>, AssemblerError> {
+        source
+            .lines()
+            .enumerate()
+            .map(|(i, text)| {
+                let mut line = self
+                    .parse_line(text)
+                    .map_err(|kind| AssemblerError { line: i + 1, kind })?;
+                line.number = i + 1;
+
+                Ok(line)
+            })
+            .collect()
+    }
+
+    fn parse_line(&self, text: &str) -> Result<Line, ErrorKind> {
+        let text = strip_comment(text).trim();
+
+        let mut line = Line {
+            number: 0,
+            label: None,
+            statement: Statement::Empty,
+        };
+
+        // `* = $8000` and `name = value`
+        if let Some((name, value)) = split_definition(text) {
+            let value = Expr::parse(value)?;
+
+            line.statement = match name {
+                "*" => Statement::Origin(value),
+                name if is_symbol(name) => Statement::Define(name.to_string(), value),
+                name => return Err(ErrorKind::Syntax(format!("invalid symbol {}", name))),
+            };
+
+            return Ok(line);
+        }
+
+        let (word, rest) = split_word(text);
+
+        // labels may or may not be followed by a colon
+        let rest = if let Some(label) = word.strip_suffix(':') {
+            line.label = Some(label.to_string());
+            rest
+        } else if is_symbol(word) && !self.mnemonics.contains_key(&word.to_uppercase()) {
+            // unless what follows isn't a statement, in which case it was a typo
+            let (next, _) = split_word(rest);
+            if !next.is_empty()
+                && !next.starts_with('.')
+                && !self.mnemonics.contains_key(&next.to_uppercase())
+            {
+                return Err(ErrorKind::UnknownMnemonic(word.to_string()));
+            }
+
+            line.label = Some(word.to_string());
+            rest
+        } else {
+            text
+        };
+
+        if let Some(label) = &line.label {
+            if !is_symbol(label) {
+                return Err(ErrorKind::Syntax(format!("invalid label {}", label)));
+            }
+        }
+
+        line.statement = self.parse_statement(rest)?;
+
+        Ok(line)
+    }
+
+    fn parse_statement(&self, text: &str) -> Result<Statement, ErrorKind> {
+        let (word, operand) = split_word(text);
+
+        if word.is_empty() {
+            return Ok(Statement::Empty);
+        }
+
+        if word.starts_with('.') {
+            let args = split_arguments(operand);
+
+            return match word.to_lowercase().as_str() {
+                ".byt" | ".byte" | ".asc" => args
+                    .iter()
+                    .map(|arg| match parse_string(arg) {
+                        Some(bytes) => Ok(Data::Bytes(bytes)),
+                        None => Ok(Data::Expr(Expr::parse(arg)?)),
+                    })
+                    .collect::<Result<_, _>>()
+                    .map(Statement::Bytes),
+                ".word" => args
+                    .iter()
+                    .map(|arg| Expr::parse(arg))
+                    .collect::<Result<_, _>>()
+                    .map(Statement::Words),
+                ".dsb" => match args.as_slice() {
+                    [count] => Ok(Statement::Fill(Expr::parse(count)?, None)),
+                    [count, fill] => Ok(Statement::Fill(
+                        Expr::parse(count)?,
+                        Some(Expr::parse(fill)?),
+                    )),
+                    _ => Err(ErrorKind::Syntax(
+                        ".dsb takes a count and a fill byte".into(),
+                    )),
+                },
+                _ => Err(ErrorKind::UnknownDirective(word.to_string())),
+            };
+        }
+
+        let instruction_type = *self
+            .mnemonics
+            .get(&word.to_uppercase())
+            .ok_or_else(|| ErrorKind::UnknownMnemonic(word.to_string()))?;
+
+        let bit_branch = Instruction::encode_variant(
+            self.variant,
+            self.undocumented,
+            instruction_type,
+            Addressing::ZeropageRelative(0, 0),
+        )
+        .is_ok();
+
+        Ok(Statement::Instruction(
+            instruction_type,
+            parse_operand(operand, bit_branch)?,
+            operand.to_string(),
+        ))
+    }
+
+    fn define(&mut self, name: &str, value: i64) -> Result<(), ErrorKind> {
+        match self.symbols.insert(name.to_string(), value) {
+            Some(_) => Err(ErrorKind::DuplicateSymbol(name.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    fn eval(&self, expr: &Expr, program_counter: i64) -> Result<i64, ErrorKind> {
+        expr.eval(&Context {
+            symbols: &self.symbols,
+            program_counter,
+        })
+    }
+
+    /// Evaluates `expr`, unless it refers to symbols which aren't defined yet
+    fn eval_known(&self, expr: &Expr, program_counter: i64) -> Result<Option<i64>, ErrorKind> {
+        match self.eval(expr, program_counter) {
+            Ok(x) => Ok(Some(x)),
+            Err(ErrorKind::UndefinedSymbol(_)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Defines every label and picks the addressing of every instruction
+    fn first_pass(&mut self, lines: &[Line]) -> Result<(), AssemblerError> {
+        let mut program_counter = 0;
+
+        for line in lines {
+            let addressing = self
+                .first_pass_line(line, &mut program_counter)
+                .map_err(|kind| AssemblerError {
+                    line: line.number,
+                    kind,
+                })?;
+
+            self.addressing.push(addressing);
+        }
+
+        Ok(())
+    }
+
+    fn first_pass_line(
+        &mut self,
+        line: &Line,
+        program_counter: &mut i64,
+    ) -> Result<Option<Addressing>, ErrorKind> {
+        if let Some(label) = &line.label {
+            self.define(label, *program_counter)?;
+        }
+
+        let size = match &line.statement {
+            Statement::Empty => 0,
+            Statement::Origin(expr) => {
+                *program_counter = self.eval(expr, *program_counter)?;
+                0
+            }
+            Statement::Define(name, expr) => {
+                // definitions referring to later symbols are resolved by the second pass
+                if let Some(value) = self.eval_known(expr, *program_counter)? {
+                    self.define(name, value)?;
+                }
+                0
+            }
+            Statement::Instruction(instruction_type, operand, text) => {
+                let addressing =
+                    self.choose_addressing(*instruction_type, operand, text, *program_counter)?;
+                *program_counter += 1 + addressing.operand_len() as i64;
+
+                return Ok(Some(addressing));
+            }
+            Statement::Bytes(data) => data
+                .iter()
+                .map(|data| match data {
+                    Data::Bytes(bytes) => bytes.len() as i64,
+                    Data::Expr(_) => 1,
+                })
+                .sum(),
+            Statement::Words(words) => 2 * words.len() as i64,
+            // nothing bigger fits in the address space, so don't try allocating it
+            Statement::Fill(count, _) => match self.eval(count, *program_counter)? {
+                count if !(0..=0x10000).contains(&count) => {
+                    return Err(ErrorKind::ValueOutOfRange(count))
+                }
+                count => count,
+            },
+        };
+
+        *program_counter += size;
+
+        Ok(None)
+    }
+
+    /// Picks the addressing of an instruction. Operands which aren't known yet
+    /// are assumed not to fit in page zero
+    fn choose_addressing(
+        &self,
+        instruction_type: InstructionType,
+        operand: &Operand,
+        text: &str,
+        program_counter: i64,
+    ) -> Result<Addressing, ErrorKind> {
+        let zeropage = |expr: &Expr, absolute: bool| -> Result<bool, ErrorKind> {
+            Ok(!absolute && matches!(self.eval_known(expr, program_counter)?, Some(0..=0xFF)))
+        };
+
+        let candidates = match operand {
+            Operand::None => vec![Addressing::Implied, Addressing::Accumulator],
+            Operand::Accumulator => vec![Addressing::Accumulator],
+            Operand::Immediate(_) => vec![Addressing::Immediate(0)],
+            Operand::Direct(expr, absolute) => match zeropage(expr, *absolute)? {
+                true => vec![
+                    Addressing::Relative(0),
+                    Addressing::Zeropage(0),
+                    Addressing::Absolute(0),
+                ],
+                false => vec![
+                    Addressing::Relative(0),
+                    Addressing::Absolute(0),
+                    Addressing::Zeropage(0),
+                ],
+            },
+            Operand::IndexedX(expr, absolute) => match zeropage(expr, *absolute)? {
+                true => vec![Addressing::ZeropageX(0), Addressing::AbsoluteX(0)],
+                false => vec![Addressing::AbsoluteX(0), Addressing::ZeropageX(0)],
+            },
+            Operand::IndexedY(expr, absolute) => match zeropage(expr, *absolute)? {
+                true => vec![Addressing::ZeropageY(0), Addressing::AbsoluteY(0)],
+                false => vec![Addressing::AbsoluteY(0), Addressing::ZeropageY(0)],
+            },
+            Operand::Indirect(_) => vec![Addressing::Indirect(0), Addressing::ZeropageIndirect(0)],
+            Operand::IndirectX(_) => {
+                vec![Addressing::IndirectX(0), Addressing::AbsoluteIndirectX(0)]
+            }
+            Operand::IndirectY(_) => vec![Addressing::IndirectY(0)],
+            Operand::BitBranch(..) => vec![Addressing::ZeropageRelative(0, 0)],
+        };
+
+        candidates
+            .into_iter()
+            .find(|&addressing| {
+                Instruction::encode_variant(
+                    self.variant,
+                    self.undocumented,
+                    instruction_type,
+                    addressing,
+                )
+                .is_ok()
+            })
+            .ok_or_else(|| ErrorKind::InvalidAddressing(instruction_type, text.to_string()))
+    }
+
+    /// Emits the image, now that every symbol is known
+    fn second_pass(&mut self, lines: &[Line]) -> Result<Assembly, AssemblerError> {
+        let mut program_counter = 0;
+        let mut origin = None;
+        let mut image = Vec::new();
+        let mut addresses = BTreeMap::new();
+
+        for (i, line) in lines.iter().enumerate() {
+            let addressing = self.addressing[i];
+            let bytes = self
+                .second_pass_line(line, addressing, &mut program_counter)
+                .map_err(|kind| AssemblerError {
+                    line: line.number,
+                    kind,
+                })?;
+
+            if origin.is_none() && !bytes.is_empty() {
+                origin = Some(program_counter);
+            }
+
+            if let Statement::Instruction(..) = line.statement {
+                addresses.insert(line.number, program_counter as u16);
+            }
+
+            program_counter += bytes.len() as i64;
+            image.extend(bytes);
+
+            if image.len() > 0x10000 {
+                return Err(AssemblerError {
+                    line: line.number,
+                    kind: ErrorKind::ProgramTooLarge,
+                });
+            }
+        }
+
+        let origin = origin.unwrap_or(program_counter);
+
+        Ok(Assembly {
+            origin: word(origin).map_err(|kind| AssemblerError { line: 0, kind })?,
+            image,
+            symbols: self
+                .symbols
+                .iter()
+                .map(|(name, &value)| (name.clone(), value as u16))
+                .collect(),
+            labels: lines.iter().filter_map(|line| line.label.clone()).collect(),
+            lines: addresses,
+        })
+    }
+
+    fn second_pass_line(
+        &mut self,
+        line: &Line,
+        addressing: Option<Addressing>,
+        program_counter: &mut i64,
+    ) -> Result<Vec<u8>, ErrorKind> {
+        let pc = *program_counter;
+
+        Ok(match &line.statement {
+            Statement::Empty => vec![],
+            Statement::Origin(expr) => {
+                *program_counter = self.eval(expr, pc)?;
+                vec![]
+            }
+            Statement::Define(name, expr) => {
+                if !self.symbols.contains_key(name) {
+                    let value = self.eval(expr, pc)?;
+                    self.define(name, value)?;
+                }
+                vec![]
+            }
+            Statement::Instruction(instruction_type, operand, _) => {
+                let addressing =
+                    self.resolve(addressing.unwrap_or(Addressing::Implied), operand, pc)?;
+
+                Instruction::encode_variant(
+                    self.variant,
+                    self.undocumented,
+                    *instruction_type,
+                    addressing,
+                )
+                .expect("the first pass only picks valid addressings")
+            }
+            Statement::Bytes(data) => {
+                let mut bytes = vec![];
+
+                for data in data {
+                    match data {
+                        Data::Bytes(string) => bytes.extend(string),
+                        Data::Expr(expr) => bytes.push(byte(self.eval(expr, pc)?)?),
+                    }
+                }
+
+                bytes
+            }
+            Statement::Words(words) => {
+                let mut bytes = vec![];
+
+                for expr in words {
+                    bytes.extend(word(self.eval(expr, pc)?)?.to_le_bytes());
+                }
+
+                bytes
+            }
+            Statement::Fill(count, fill) => {
+                let fill = match fill {
+                    Some(fill) => byte(self.eval(fill, pc)?)?,
+                    None => 0,
+                };
+
+                vec![fill; self.eval(count, pc)? as usize]
+            }
+        })
+    }
+
+    /// Fills the addressing picked by the first pass with the value of the operand
+    fn resolve(
+        &self,
+        addressing: Addressing,
+        operand: &Operand,
+        program_counter: i64,
+    ) -> Result<Addressing, ErrorKind> {
+        let expr = match operand {
+            Operand::None | Operand::Accumulator => return Ok(addressing),
+            Operand::BitBranch(zeropage, target) => {
+                let zeropage = byte(self.eval(zeropage, program_counter)?)?;
+                let offset = self.branch(target, program_counter, 3)?;
+
+                return Ok(Addressing::ZeropageRelative(zeropage, offset));
+            }
+
+            Operand::Immediate(expr)
+            | Operand::Direct(expr, _)
+            | Operand::IndexedX(expr, _)
+            | Operand::IndexedY(expr, _)
+            | Operand::Indirect(expr)
+            | Operand::IndirectX(expr)
+            | Operand::IndirectY(expr) => expr,
+        };
+
+        if let Addressing::Relative(_) = addressing {
+            return Ok(Addressing::Relative(self.branch(
+                expr,
+                program_counter,
+                2,
+            )?));
+        }
+
+        let value = self.eval(expr, program_counter)?;
+
+        Ok(match addressing {
+            Addressing::Immediate(_) => Addressing::Immediate(byte(value)?),
+            Addressing::Zeropage(_) => Addressing::Zeropage(zeropage(value)?),
+            Addressing::ZeropageX(_) => Addressing::ZeropageX(zeropage(value)?),
+            Addressing::ZeropageY(_) => Addressing::ZeropageY(zeropage(value)?),
+            Addressing::IndirectX(_) => Addressing::IndirectX(zeropage(value)?),
+            Addressing::IndirectY(_) => Addressing::IndirectY(zeropage(value)?),
+            Addressing::ZeropageIndirect(_) => Addressing::ZeropageIndirect(zeropage(value)?),
+            Addressing::Absolute(_) => Addressing::Absolute(word(value)?),
+            Addressing::AbsoluteX(_) => Addressing::AbsoluteX(word(value)?),
+            Addressing::AbsoluteY(_) => Addressing::AbsoluteY(word(value)?),
+            Addressing::Indirect(_) => Addressing::Indirect(word(value)?),
+            Addressing::AbsoluteIndirectX(_) => Addressing::AbsoluteIndirectX(word(value)?),
+            addressing => addressing,
+        })
+    }
+
+    /// Offset from the end of a branch instruction `size` bytes long to `target`
+    fn branch(&self, target: &Expr, program_counter: i64, size: i64) -> Result<u8, ErrorKind> {
+        let distance = self.eval(target, program_counter)? - (program_counter + size);
+
+        match distance {
+            -128..=127 => Ok(distance as u8),
+            _ => Err(ErrorKind::BranchOutOfRange(distance)),
+        }
+    }
+}
+
+/// Immediate and data bytes may also be negative
+fn byte(value: i64) -> Result<u8, ErrorKind> {
+    match value {
+        -128..=0xFF => Ok(value as u8),
+        _ => Err(ErrorKind::ValueOutOfRange(value)),
+    }
+}
+
+fn zeropage(value: i64) -> Result<u8, ErrorKind> {
+    match value {
+        0..=0xFF => Ok(value as u8),
+        _ => Err(ErrorKind::ValueOutOfRange(value)),
+    }
+}
+
+fn word(value: i64) -> Result<u16, ErrorKind> {
+    match value {
+        0..=0xFFFF => Ok(value as u16),
+        _ => Err(ErrorKind::ValueOutOfRange(value)),
+    }
+}
+
+fn is_symbol(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Everything before a `;` which isn't quoted
+fn strip_comment(text: &str) -> &str {
+    let mut quote = None;
+
+    for (i, c) in text.char_indices() {
+        match (c, quote) {
+            ('"', None) | ('\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            (';', None) => return &text[..i],
+            _ => (),
+        }
+    }
+
+    text
+}
+
+/// Splits `name = value`, as long as `name` is a single word
+fn split_definition(text: &str) -> Option<(&str, &str)> {
+    let (name, value) = text.split_once('=')?;
+    let name = name.trim();
+
+    if name.is_empty() || name.contains(char::is_whitespace) || name.contains(['"', '\'']) {
+        return None;
+    }
+
+    Some((name, value.trim()))
+}
+
+fn split_word(text: &str) -> (&str, &str) {
+    match text.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim()),
+        None => (text, ""),
+    }
+}
+
+/// Splits on the commas which are neither quoted nor within parentheses
+fn split_arguments(text: &str) -> Vec<&str> {
+    let mut args = Vec::new();
+    let (mut depth, mut quote, mut start) = (0, None, 0);
+
+    for (i, c) in text.char_indices() {
+        match (c, quote) {
+            ('"', None) | ('\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('(', None) => depth += 1,
+            (')', None) => depth -= 1,
+            (',', None) if depth == 0 => {
+                args.push(text[start..i].trim());
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+
+    if !text.trim().is_empty() {
+        args.push(text[start..].trim());
+    }
+
+    args
+}
+
+/// The bytes of a double quoted string
+fn parse_string(text: &str) -> Option<Vec<u8>> {
+    let string = text.strip_prefix('"')?.strip_suffix('"')?;
+
+    Some(string.bytes().collect())
+}
+
+fn parse_operand(text: &str, bit_branch: bool) -> Result<Operand, ErrorKind> {
+    if text.is_empty() {
+        return Ok(Operand::None);
+    }
+
+    if text.eq_ignore_ascii_case("a") {
+        return Ok(Operand::Accumulator);
+    }
+
+    if let Some(value) = text.strip_prefix('#') {
+        return Ok(Operand::Immediate(Expr::parse(value)?));
+    }
+
+    let args = split_arguments(text);
+
+    if bit_branch {
+        return match args.as_slice() {
+            [zeropage, target] => Ok(Operand::BitBranch(
+                Expr::parse(zeropage)?,
+                Expr::parse(target)?,
+            )),
+            _ => Err(ErrorKind::Syntax(
+                "expected a zeropage address and a branch target".into(),
+            )),
+        };
+    }
+
+    // `($10),Y`, `($10,X)` and `($1234)`, but not `(1+2)*3`
+    if let Some(inner) = text.strip_prefix('(') {
+        let close = matching_parenthesis(inner);
+
+        if let Some(close) = close {
+            let (inner, after) = (&inner[..close], inner[close + 1..].trim());
+            let inner_args = split_arguments(inner);
+
+            match (inner_args.as_slice(), after) {
+                ([pointer], "") => return Ok(Operand::Indirect(Expr::parse(pointer)?)),
+                ([pointer, x], "") if x.eq_ignore_ascii_case("x") => {
+                    return Ok(Operand::IndirectX(Expr::parse(pointer)?))
+                }
+                ([pointer], after) if is_index(after, "y") => {
+                    return Ok(Operand::IndirectY(Expr::parse(pointer)?))
+                }
+                _ => (),
+            }
+        }
+    }
+
+    let (value, index) = match args.as_slice() {
+        [value] => (*value, None),
+        [value, index] => (*value, Some(index.to_lowercase())),
+        _ => return Err(ErrorKind::Syntax(format!("invalid operand {}", text))),
+    };
+
+    let (value, absolute) = match value.strip_prefix('!') {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+    let value = Expr::parse(value)?;
+
+    match index.as_deref() {
+        None => Ok(Operand::Direct(value, absolute)),
+        Some("x") => Ok(Operand::IndexedX(value, absolute)),
+        Some("y") => Ok(Operand::IndexedY(value, absolute)),
+        Some(index) => Err(ErrorKind::Syntax(format!(
+            "invalid index register {}",
+            index
+        ))),
+    }
+}
+
+/// Index of the `)` closing a parenthesis which was opened right before `text`
+fn matching_parenthesis(text: &str) -> Option<usize> {
+    let mut depth = 0;
+
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(i),
+            ')' => depth -= 1,
+            _ => (),
+        }
+    }
+
+    None
+}
+
+/// Whether `text` is `,` followed by the `register` name
+fn is_index(text: &str, register: &str) -> bool {
+    match text.strip_prefix(',') {
+        Some(name) => name.trim().eq_ignore_ascii_case(register),
+        None => false,
+    }
+}
@@ -0,0 +1,132 @@
+use crate::savestate::{error::SaveStateError, SaveState};
+use std::{cell::RefCell, rc::Rc};
+
+/// Something attached to a [`Bus`](super::Bus), such as memory or an I/O chip.
+/// Devices are addressed relatively to the start of the range they're mapped to
+pub trait Device {
+    /// Reads without any side effect
+    fn peek(&self, offset: u16) -> u8;
+    fn set(&mut self, offset: u16, x: u8);
+
+    /// Reads on behalf of the cpu, which the device may react to
+    fn read(&mut self, offset: u16) -> u8 {
+        self.peek(offset)
+    }
+
+    /// Adds the state of the device to a save state, in the section called `name`.
+    /// Devices needing more than one section name them `name.` followed by anything.
+    /// Nothing is kept by default, which suits stateless devices such as ROMs
+    fn save(&self, _name: &str, _state: &mut SaveState) {}
+
+    /// Checks that the state kept by [`Device::save`] can be restored, without changing
+    /// anything. A bus checks every device before restoring any of them
+    fn check_restore(&self, _name: &str, _state: &SaveState) -> Result<(), SaveStateError> {
+        Ok(())
+    }
+
+    /// Restores the state kept by [`Device::save`], changing nothing on errors
+    fn restore(&mut self, _name: &str, _state: &SaveState) -> Result<(), SaveStateError> {
+        Ok(())
+    }
+}
+
+/// Shared devices can be mapped while keeping a handle to them,
+/// so that the rest of the machine can still reach them
+impl<D: Device> Device for Rc<RefCell<D>> {
+    fn peek(&self, offset: u16) -> u8 {
+        self.borrow().peek(offset)
+    }
+
+    fn set(&mut self, offset: u16, x: u8) {
+        self.borrow_mut().set(offset, x)
+    }
+
+    fn read(&mut self, offset: u16) -> u8 {
+        self.borrow_mut().read(offset)
+    }
+
+    fn save(&self, name: &str, state: &mut SaveState) {
+        self.borrow().save(name, state)
+    }
+
+    fn check_restore(&self, name: &str, state: &SaveState) -> Result<(), SaveStateError> {
+        self.borrow().check_restore(name, state)
+    }
+
+    fn restore(&mut self, name: &str, state: &SaveState) -> Result<(), SaveStateError> {
+        self.borrow_mut().restore(name, state)
+    }
+}
+
+/// Read/write memory. Offsets past its size wrap around
+pub struct Ram {
+    memory: Vec<u8>,
+}
+
+impl Ram {
+    /// Zeroed memory of `size` bytes, which can't be 0
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0, "ram needs at least one byte");
+
+        Self {
+            memory: vec![0; size],
+        }
+    }
+
+    pub fn load_data(&mut self, offset: u16, data: &[u8]) {
+        self.memory
+            .iter_mut()
+            .skip(offset as usize)
+            .zip(data)
+            .for_each(|(mem, data)| *mem = *data);
+    }
+}
+
+impl Device for Ram {
+    fn peek(&self, offset: u16) -> u8 {
+        self.memory[offset as usize % self.memory.len()]
+    }
+
+    fn set(&mut self, offset: u16, x: u8) {
+        let len = self.memory.len();
+        self.memory[offset as usize % len] = x;
+    }
+
+    fn save(&self, name: &str, state: &mut SaveState) {
+        state.insert(name, self.memory.clone());
+    }
+
+    fn check_restore(&self, name: &str, state: &SaveState) -> Result<(), SaveStateError> {
+        state.require(name, self.memory.len()).map(|_| ())
+    }
+
+    fn restore(&mut self, name: &str, state: &SaveState) -> Result<(), SaveStateError> {
+        let data = state.require(name, self.memory.len())?;
+        self.memory.copy_from_slice(data);
+
+        Ok(())
+    }
+}
+
+/// Read-only memory, writes to it are ignored. Offsets past its size wrap around
+pub struct Rom {
+    memory: Vec<u8>,
+}
+
+impl Rom {
+    /// Memory holding `data`, which can't be empty
+    pub fn new(data: impl Into<Vec<u8>>) -> Self {
+        let memory = data.into();
+        assert!(!memory.is_empty(), "rom needs at least one byte");
+
+        Self { memory }
+    }
+}
+
+impl Device for Rom {
+    fn peek(&self, offset: u16) -> u8 {
+        self.memory[offset as usize % self.memory.len()]
+    }
+
+    fn set(&mut self, _offset: u16, _x: u8) {}
+}
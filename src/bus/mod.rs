@@ -0,0 +1,150 @@
+use self::device::Device;
+use crate::{
+    cpu::addressable_bus::DataBus,
+    savestate::{error::SaveStateError, SaveState, Snapshot},
+};
+use std::ops::RangeInclusive;
+
+pub mod device;
+pub mod recorder;
+
+/// Value read from addresses no device is mapped to, unless set otherwise
+pub const DEFAULT_OPEN_BUS: u8 = 0xFF;
+
+struct Mapping {
+    range: RangeInclusive<u16>,
+    mask: u16,
+    device: Box<dyn Device>,
+}
+
+impl Mapping {
+    fn offset(&self, addr: u16) -> u16 {
+        (addr - self.range.start()) & self.mask
+    }
+}
+
+/// A [`DataBus`] dispatching address ranges to the devices attached to it.
+///
+/// When ranges overlap, the device mapped last wins, so that I/O devices
+/// can be laid over a wider memory mapping
+pub struct Bus {
+    mappings: Vec<Mapping>,
+    open_bus: u8,
+}
+
+impl Default for Bus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bus {
+    pub fn new() -> Self {
+        Self::with_open_bus(DEFAULT_OPEN_BUS)
+    }
+
+    /// Creates an empty bus where unmapped reads return `open_bus`
+    pub fn with_open_bus(open_bus: u8) -> Self {
+        Self {
+            mappings: Vec::new(),
+            open_bus,
+        }
+    }
+
+    /// Maps `device` to `range`, handing it offsets from the start of the range
+    pub fn map(&mut self, range: RangeInclusive<u16>, device: impl Device + 'static) {
+        self.map_mirrored(range, 0xFFFF, device)
+    }
+
+    /// Maps `device` to `range`, masking the offsets handed to it with `mask`.
+    /// A 2K RAM mapped to `0x0000..=0x1FFF` with mask `0x07FF` shows up four times
+    pub fn map_mirrored(
+        &mut self,
+        range: RangeInclusive<u16>,
+        mask: u16,
+        device: impl Device + 'static,
+    ) {
+        self.mappings.push(Mapping {
+            range,
+            mask,
+            device: Box::new(device),
+        });
+    }
+
+    /// Name of the save state section of the device mapped `index`-th
+    fn section(index: usize) -> String {
+        format!("bus.{}", index)
+    }
+
+    fn mapping(&self, addr: u16) -> Option<&Mapping> {
+        self.mappings.iter().rev().find(|m| m.range.contains(&addr))
+    }
+
+    fn mapping_mut(&mut self, addr: u16) -> Option<&mut Mapping> {
+        self.mappings
+            .iter_mut()
+            .rev()
+            .find(|m| m.range.contains(&addr))
+    }
+}
+
+impl DataBus for Bus {
+    fn peek(&self, addr: u16) -> u8 {
+        match self.mapping(addr) {
+            Some(mapping) => mapping.device.peek(mapping.offset(addr)),
+            None => self.open_bus,
+        }
+    }
+
+    fn read(&mut self, addr: u16) -> u8 {
+        match self.mapping_mut(addr) {
+            Some(mapping) => {
+                let offset = mapping.offset(addr);
+                mapping.device.read(offset)
+            }
+            None => self.open_bus,
+        }
+    }
+
+    fn set(&mut self, addr: u16, x: u8) {
+        if let Some(mapping) = self.mapping_mut(addr) {
+            let offset = mapping.offset(addr);
+            mapping.device.set(offset, x);
+        }
+    }
+}
+
+/// Every device keeps its state in the section named after the order it was
+/// mapped in: `bus.0` for the first one, `bus.1` for the second and so on.
+/// A state can only be restored into a bus with the same devices, mapped in the same order
+impl Snapshot for Bus {
+    fn save(&self, state: &mut SaveState) {
+        for (i, mapping) in self.mappings.iter().enumerate() {
+            mapping.device.save(&Self::section(i), state);
+        }
+    }
+
+    fn restore(&mut self, state: &SaveState) -> Result<(), SaveStateError> {
+        // check that every section is there before any device changes
+        let mut expected = SaveState::new();
+        self.save(&mut expected);
+
+        if let Some(missing) = expected
+            .sections()
+            .find(|name| state.section(name).is_none())
+        {
+            return Err(SaveStateError::MissingSection(missing.to_string()));
+        }
+
+        // then that every device accepts its section
+        for (i, mapping) in self.mappings.iter().enumerate() {
+            mapping.device.check_restore(&Self::section(i), state)?;
+        }
+
+        for (i, mapping) in self.mappings.iter_mut().enumerate() {
+            mapping.device.restore(&Self::section(i), state)?;
+        }
+
+        Ok(())
+    }
+}
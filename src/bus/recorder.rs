@@ -0,0 +1,180 @@
+use crate::{
+    cpu::addressable_bus::DataBus,
+    savestate::{error::SaveStateError, SaveState, Snapshot},
+};
+use std::{
+    collections::VecDeque,
+    fmt::{self, Display, Formatter},
+    io::{self, Write},
+    ops::Range,
+};
+
+/// A read or write of the cpu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event {
+    pub cycle: u64,
+    pub address: u16,
+    pub value: u8,
+    pub write: bool,
+}
+
+/// Formats as the cycle, address, value and direction of the access:
+///
+/// ```text
+/// 7  8000  A9  read
+/// ```
+impl Display for Event {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let direction = if self.write { "write" } else { "read" };
+
+        write!(
+            f,
+            "{}  {:04X}  {:02X}  {}",
+            self.cycle, self.address, self.value, direction
+        )
+    }
+}
+
+/// A [`DataBus`] wrapper recording the reads and writes of the cpu, keeping the
+/// latest `capacity` of them and optionally writing each of them to a file.
+///
+/// Bus accesses happen all at once when an instruction is executed, but the cpu
+/// makes one per cycle in the order of the hardware, dummy ones included, so an
+/// access is stamped with the cycle its instruction started at plus the number
+/// of accesses before it. Peeks aren't recorded
+pub struct Recorder<T: DataBus> {
+    pub inner: T,
+    events: VecDeque<Event>,
+    capacity: usize,
+    output: Option<Box<dyn Write>>,
+    /// the first error writing to `output`, after which it's dropped
+    error: Option<io::Error>,
+    cycle: u64,
+}
+
+impl<T: DataBus> Recorder<T> {
+    pub fn new(inner: T, capacity: usize) -> Self {
+        Self {
+            inner,
+            events: VecDeque::with_capacity(capacity),
+            capacity,
+            output: None,
+            error: None,
+            cycle: 0,
+        }
+    }
+
+    /// Also writes every event to `output`, a line each
+    pub fn with_output(mut self, output: impl Write + 'static) -> Self {
+        self.output = Some(Box::new(output));
+        self
+    }
+
+    /// The recorded events, oldest first
+    pub fn events(&self) -> impl DoubleEndedIterator<Item = &Event> + '_ {
+        self.events.iter()
+    }
+
+    /// The latest `count` events, oldest first
+    pub fn last(&self, count: usize) -> impl Iterator<Item = &Event> + '_ {
+        self.events
+            .iter()
+            .skip(self.events.len().saturating_sub(count))
+    }
+
+    /// The events which happened within `cycles`
+    pub fn between(&self, cycles: Range<u64>) -> impl Iterator<Item = &Event> + '_ {
+        self.events
+            .iter()
+            .filter(move |event| cycles.contains(&event.cycle))
+    }
+
+    /// The events which accessed `address`
+    pub fn at(&self, address: u16) -> impl Iterator<Item = &Event> + '_ {
+        self.events
+            .iter()
+            .filter(move |event| event.address == address)
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// Flushes the output, reporting the first error writing to it, if any
+    pub fn flush(&mut self) -> io::Result<()> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+
+        match self.output.as_mut() {
+            Some(output) => output.flush(),
+            None => Ok(()),
+        }
+    }
+
+    fn record(&mut self, address: u16, value: u8, write: bool) {
+        let event = Event {
+            cycle: self.cycle,
+            address,
+            value,
+            write,
+        };
+        self.cycle += 1;
+
+        if let Some(output) = self.output.as_mut() {
+            if let Err(err) = writeln!(output, "{}", event) {
+                self.error = Some(err);
+                self.output = None;
+            }
+        }
+
+        if self.capacity == 0 {
+            return;
+        }
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+}
+
+impl<T: DataBus> DataBus for Recorder<T> {
+    fn peek(&self, addr: u16) -> u8 {
+        self.inner.peek(addr)
+    }
+
+    fn set(&mut self, addr: u16, x: u8) {
+        self.inner.set(addr, x);
+        self.record(addr, x, true);
+    }
+
+    fn read(&mut self, addr: u16) -> u8 {
+        let x = self.inner.read(addr);
+        self.record(addr, x, false);
+
+        x
+    }
+
+    fn sync(&mut self, cycles: u64) {
+        self.cycle = cycles;
+        self.inner.sync(cycles);
+    }
+}
+
+impl<T: DataBus + Snapshot> Snapshot for Recorder<T> {
+    fn save(&self, state: &mut SaveState) {
+        self.inner.save(state);
+    }
+
+    fn restore(&mut self, state: &SaveState) -> Result<(), SaveStateError> {
+        self.inner.restore(state)
+    }
+}
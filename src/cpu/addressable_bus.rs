@@ -1,15 +1,33 @@
 pub trait DataBus {
-    fn get(&self, addr: u16) -> u8;
+    /// Reads without any side effect, for debuggers, disassemblers and the like
+    fn peek(&self, addr: u16) -> u8;
     fn set(&mut self, addr: u16, x: u8);
 
+    /// Reads as the cpu does. Devices may react to it, for example
+    /// by clearing a status flag or popping a FIFO
+    fn read(&mut self, addr: u16) -> u8 {
+        self.peek(addr)
+    }
+
+    /// Called by the cpu with its cycle count before executing an instruction,
+    /// for buses that keep time. Does nothing by default
+    fn sync(&mut self, _cycles: u64) {}
+
     fn set_word(&mut self, offset: u16, x: u16) {
         self.set(offset, x as u8);
-        self.set(offset + 1, (x >> 8) as u8);
+        self.set(offset.wrapping_add(1), (x >> 8) as u8);
+    }
+
+    fn peek_word(&self, offset: u16) -> u16 {
+        let ll = self.peek(offset);
+        let hh = self.peek(offset.wrapping_add(1));
+
+        ((hh as u16) << 8) + ll as u16
     }
 
-    fn get_word(&self, offset: u16) -> u16 {
-        let ll = self.get(offset);
-        let hh = self.get(offset + 1);
+    fn read_word(&mut self, offset: u16) -> u16 {
+        let ll = self.read(offset);
+        let hh = self.read(offset.wrapping_add(1));
 
         ((hh as u16) << 8) + ll as u16
     }
@@ -1,37 +1,185 @@
-use super::{addressable_bus::DataBus, instruction::Addressing, Cpu};
+use super::{
+    addressable_bus::DataBus,
+    instruction::{Addressing, Instruction, InstructionType},
+    Cpu,
+};
 
 impl<T: DataBus> Cpu<T> {
-    pub fn address_addressing(&self, addressing: Addressing) -> u16 {
+    /// Computes the effective address of `addressing`. Zeropage indexing and
+    /// zeropage pointers wrap around within page zero, everything else wraps
+    /// around the whole 64K address space. Pointers are read through the bus
+    pub fn address_addressing(&mut self, addressing: Addressing) -> u16 {
         match addressing {
             Addressing::Relative(offset) => {
                 let offset = offset as i8;
-                self.program_counter + (offset as u16)
+                self.program_counter.wrapping_add(offset as u16)
             }
-            Addressing::Zeropage(addr) => addr as u16, // TODO: these operations are all with carry
-            Addressing::ZeropageX(addr) => (addr + self.x_register) as u16,
-            Addressing::ZeropageY(addr) => (addr + self.y_register) as u16,
+            Addressing::Zeropage(addr) => addr as u16,
+            Addressing::ZeropageX(addr) => addr.wrapping_add(self.x_register) as u16,
+            Addressing::ZeropageY(addr) => addr.wrapping_add(self.y_register) as u16,
             Addressing::Absolute(addr) => addr,
-            Addressing::AbsoluteX(addr) => addr + self.x_register as u16,
-            Addressing::AbsoluteY(addr) => addr + self.y_register as u16,
-            Addressing::Indirect(addr) => self.bus.get_word(addr),
-            Addressing::IndirectX(addr) => self.bus.get_word((addr + self.x_register) as u16),
-            Addressing::IndirectY(addr) => self.bus.get_word(addr as u16) + self.y_register as u16,
+            Addressing::AbsoluteX(addr) => addr.wrapping_add(self.x_register as u16),
+            Addressing::AbsoluteY(addr) => addr.wrapping_add(self.y_register as u16),
+            Addressing::Indirect(addr) if !self.variant.is_cmos() => {
+                // NMOS parts never carry into the high byte of the pointer,
+                // so JMP ($xxFF) fetches its high byte from $xx00
+                let hh = (addr & 0xFF00) | (addr.wrapping_add(1) & 0x00FF);
+                let ll = self.bus.read(addr);
+                ((self.bus.read(hh) as u16) << 8) + ll as u16
+            }
+            Addressing::Indirect(addr) => self.bus.read_word(addr),
+            Addressing::IndirectX(addr) => self.zeropage_word(addr.wrapping_add(self.x_register)),
+            Addressing::IndirectY(addr) => self
+                .zeropage_word(addr)
+                .wrapping_add(self.y_register as u16),
+            Addressing::ZeropageIndirect(addr) => self.zeropage_word(addr),
+            Addressing::AbsoluteIndirectX(addr) => self
+                .bus
+                .read_word(addr.wrapping_add(self.x_register as u16)),
+            Addressing::ZeropageRelative(addr, _) => addr as u16,
             _ => 0,
         }
     }
 
-    pub fn load_addressing(&self, addressing: Addressing) -> u8 {
+    /// Reads a pointer out of page zero, wrapping from $FF to $00
+    fn zeropage_word(&mut self, addr: u8) -> u16 {
+        let ll = self.bus.read(addr as u16);
+        let hh = self.bus.read(addr.wrapping_add(1) as u16);
+
+        ((hh as u16) << 8) + ll as u16
+    }
+
+    /// Resolves the memory operand of `addressing` to an absolute address.
+    /// Pointers get read once here, so that instructions accessing their operand
+    /// more than once, such as read-modify-write ones, don't read them again
+    pub fn resolve_addressing(&mut self, addressing: Addressing) -> Addressing {
+        match addressing {
+            Addressing::Accumulator
+            | Addressing::Implied
+            | Addressing::Immediate(_)
+            | Addressing::Relative(_)
+            | Addressing::ZeropageRelative(..) => addressing,
+            addr => Addressing::Absolute(self.address_addressing(addr)),
+        }
+    }
+
+    /// Fetches the operand bytes of `instruction`, then resolves its memory operand
+    /// like [`Cpu::resolve_addressing`], making the same bus accesses on the same
+    /// cycles as the hardware, dummy ones included. JSR, BBR and BBS fetch their
+    /// last operand byte later on, when they get executed
+    pub fn fetch_operand(&mut self, instruction: &Instruction) -> Addressing {
+        let addressing = instruction.addressing;
+
+        let deferred = matches!(instruction.instruction_type, InstructionType::JSR)
+            || matches!(addressing, Addressing::ZeropageRelative(..));
+        for _ in deferred as u16..addressing.operand_len() {
+            self.read_byte();
+        }
+
+        match addressing {
+            // the one cycle NOPs of the 65C02 don't get to read anything
+            Addressing::Implied | Addressing::Accumulator if instruction.cycles > 1 => {
+                self.bus.read(self.program_counter);
+            }
+            Addressing::ZeropageX(base)
+            | Addressing::ZeropageY(base)
+            | Addressing::IndirectX(base) => self.dummy_read(base as u16),
+            Addressing::Indirect(addr) | Addressing::AbsoluteIndirectX(addr)
+                if self.variant.is_cmos() =>
+            {
+                self.dummy_read(addr)
+            }
+            _ => (),
+        }
+
+        let resolved = self.resolve_addressing(addressing);
+
+        let index = match addressing {
+            Addressing::AbsoluteX(_) => self.x_register,
+            Addressing::AbsoluteY(_) | Addressing::IndirectY(_) => self.y_register,
+            _ => return resolved,
+        };
+
+        // indexing adds to the low byte first, and reads from that address while
+        // carrying into the high one. Only reads that don't cross a page skip it
+        if let Addressing::Absolute(addr) = resolved {
+            let base = addr.wrapping_sub(index as u16);
+            let crossed = (base & 0xFF00) != (addr & 0xFF00);
+
+            if crossed || !instruction.page_penalty {
+                self.dummy_read((base & 0xFF00) | (addr & 0x00FF));
+            }
+        }
+
+        resolved
+    }
+
+    /// A read made while the cpu is busy computing an address. NMOS parts read
+    /// from whatever address is on the bus; the 65C02 reads the last byte of the
+    /// instruction again instead, which is how its datasheet describes it
+    pub(crate) fn dummy_read(&mut self, addr: u16) {
+        match self.variant.is_cmos() {
+            true => self.bus.read(self.program_counter.wrapping_sub(1)),
+            false => self.bus.read(addr),
+        };
+    }
+
+    /// Whether the indexed effective address of `addressing` lies
+    /// on a different page than its base address. Pointers are only peeked at
+    pub fn page_crossed(&self, addressing: Addressing) -> bool {
+        let (base, index) = match addressing {
+            Addressing::AbsoluteX(addr) => (addr, self.x_register),
+            Addressing::AbsoluteY(addr) => (addr, self.y_register),
+            Addressing::IndirectY(addr) => {
+                let ll = self.bus.peek(addr as u16);
+                let hh = self.bus.peek(addr.wrapping_add(1) as u16);
+
+                (((hh as u16) << 8) + ll as u16, self.y_register)
+            }
+            _ => return false,
+        };
+
+        (base & 0xFF00) != (base.wrapping_add(index as u16) & 0xFF00)
+    }
+
+    pub fn load_addressing(&mut self, addressing: Addressing) -> u8 {
         match addressing {
             Addressing::Immediate(x) => x,
             Addressing::Accumulator => self.accumulator,
-            addr => self.bus.get(self.address_addressing(addr)),
+            addr => {
+                let addr = self.address_addressing(addr);
+                self.bus.read(addr)
+            }
+        }
+    }
+
+    /// Loads the operand of a read-modify-write instruction. NMOS parts write the
+    /// value back unchanged while modifying it, the 65C02 reads it once more
+    pub fn load_modify_addressing(&mut self, addressing: Addressing) -> u8 {
+        match addressing {
+            Addressing::Immediate(x) => x,
+            Addressing::Accumulator => self.accumulator,
+            addr => {
+                let addr = self.address_addressing(addr);
+                let x = self.bus.read(addr);
+
+                if self.variant.is_cmos() {
+                    self.bus.read(addr);
+                } else {
+                    self.bus.set(addr, x);
+                }
+                x
+            }
         }
     }
 
     pub fn write_addressing(&mut self, addressing: Addressing, x: u8) {
         match addressing {
             Addressing::Accumulator => self.accumulator = x,
-            addr => self.bus.set(self.address_addressing(addr), x),
+            addr => {
+                let addr = self.address_addressing(addr);
+                self.bus.set(addr, x)
+            }
         }
     }
 }
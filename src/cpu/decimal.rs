@@ -0,0 +1,108 @@
+use super::{addressable_bus::DataBus, status::StatusFlag, Cpu};
+
+// Decimal mode arithmetic, following the behaviour documented in Bruce Clark's
+// "Decimal Mode" tutorial: on NMOS parts the accumulator and the carry hold the
+// BCD result, while the other flags don't always reflect it. CMOS parts
+// compute N and Z from the BCD result instead
+
+impl<T: DataBus> Cpu<T> {
+    /// Whether ADC and SBC should currently operate on BCD values
+    pub(crate) fn decimal_mode(&self) -> bool {
+        self.variant.has_decimal_mode() && self.processor_status.get_flag(StatusFlag::Decimal)
+    }
+
+    /// CMOS parts take one more cycle for ADC and SBC in decimal mode,
+    /// reading the last byte of the instruction again
+    pub(crate) fn cmos_decimal_penalty(&mut self) -> u8 {
+        if !self.variant.is_cmos() || !self.decimal_mode() {
+            return 0;
+        }
+
+        self.bus.read(self.program_counter.wrapping_sub(1));
+        1
+    }
+
+    pub(crate) fn decimal_add(&mut self, x: u8, y: u8) -> u8 {
+        let carry = self.processor_status.get_flag(StatusFlag::Carry) as u16;
+        let (x, y) = (x as u16, y as u16);
+
+        let mut low = (x & 0x0F) + (y & 0x0F) + carry;
+        if low >= 0x0A {
+            low = ((low + 0x06) & 0x0F) + 0x10;
+        }
+
+        let mut res = (x & 0xF0) + (y & 0xF0) + low;
+
+        // N and V are taken from the result before the high digit gets adjusted
+        let signed = (x & 0xF0) as u8 as i8 as i16 + (y & 0xF0) as u8 as i8 as i16 + low as i16;
+        self.processor_status
+            .set_flag(StatusFlag::Negative, (res & 0x80) != 0);
+        self.processor_status
+            .set_flag(StatusFlag::Overflow, !(-128..=127).contains(&signed));
+
+        if res >= 0xA0 {
+            res += 0x60;
+        }
+
+        // while Z comes from the plain binary sum
+        let binary = (x + y + carry) as u8;
+        self.processor_status
+            .set_flag(StatusFlag::Zero, binary == 0);
+        self.processor_status
+            .set_flag(StatusFlag::Carry, res >= 0x100);
+
+        if self.variant.is_cmos() {
+            self.flag_value(res as u8);
+        }
+
+        res as u8
+    }
+
+    pub(crate) fn decimal_sub(&mut self, x: u8, y: u8) -> u8 {
+        let borrow = !self.processor_status.get_flag(StatusFlag::Carry) as i16;
+        let (dx, dy) = (x as i16, y as i16);
+
+        let mut low = (dx & 0x0F) - (dy & 0x0F) - borrow;
+        if low < 0 {
+            low = ((low - 0x06) & 0x0F) - 0x10;
+        }
+
+        let res = if self.variant.is_cmos() {
+            let mut res = dx - dy - borrow;
+            if res < 0 {
+                res -= 0x60;
+            }
+            if low < 0 {
+                res -= 0x06;
+            }
+
+            res
+        } else {
+            let mut res = (dx & 0xF0) - (dy & 0xF0) + low;
+            if res < 0 {
+                res -= 0x60;
+            }
+
+            res
+        };
+
+        // the flags are the ones of the binary subtraction,
+        // apart from N and Z on CMOS parts
+        let binary = dx - dy - borrow;
+        let binary_res = binary as u8;
+
+        self.processor_status
+            .set_flag(StatusFlag::Carry, binary >= 0);
+        self.processor_status.set_flag(
+            StatusFlag::Overflow,
+            ((x ^ y) & (x ^ binary_res) & 0x80) != 0,
+        );
+        self.flag_value(binary_res);
+
+        if self.variant.is_cmos() {
+            self.flag_value(res as u8);
+        }
+
+        res as u8
+    }
+}
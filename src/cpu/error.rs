@@ -1,16 +1,46 @@
+use crate::cpu::instruction::{Addressing, InstructionType};
 use std::fmt::Display;
 
 #[derive(Debug)]
 pub enum CpuError {
     UnknownOpcode(u8),
+    Jammed(u8),
+    Stopped,
 }
 
 impl Display for CpuError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::UnknownOpcode(opcode) => write!(f, "Unknown opcode 0x{:02X}", opcode),
+            Self::Jammed(opcode) => write!(f, "Cpu jammed by opcode 0x{:02X}", opcode),
+            Self::Stopped => write!(f, "Cpu stopped by STP"),
         }
     }
 }
 
 impl std::error::Error for CpuError {}
+
+/// No opcode implements the instruction with the requested addressing
+#[derive(Debug)]
+pub enum EncodeError {
+    UnsupportedAddressing(InstructionType, Addressing),
+}
+
+impl Display for EncodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnsupportedAddressing(instruction_type, Addressing::Implied) => {
+                write!(f, "{} needs an operand", instruction_type)
+            }
+            Self::UnsupportedAddressing(instruction_type, addressing) => {
+                write!(
+                    f,
+                    "{} {} is not a valid instruction",
+                    instruction_type, addressing
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for EncodeError {}
@@ -0,0 +1,140 @@
+use super::{Addressing, Instruction, InstructionType};
+use crate::symbols::Symbols;
+use std::fmt::{self, Display, Formatter};
+
+impl Display for InstructionType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Operands use the usual assembly syntax. Branch targets are relative
+/// to the start of the instruction, as in `BNE *-3`
+impl Display for Addressing {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_operand(f, None, None)
+    }
+}
+
+impl Addressing {
+    /// Formats the operand, resolving branch targets when the
+    /// `address` of the instruction is known, and naming addresses
+    /// after `symbols` when given
+    fn fmt_operand(
+        &self,
+        f: &mut Formatter<'_>,
+        address: Option<u16>,
+        symbols: Option<&Symbols>,
+    ) -> fmt::Result {
+        let named = |x: u16| symbols.and_then(|symbols| symbols.describe(x));
+        let zeropage = |x: u8| named(x as u16).unwrap_or_else(|| format!("${:02X}", x));
+        let absolute = |x: u16| named(x).unwrap_or_else(|| format!("${:04X}", x));
+
+        match *self {
+            Addressing::Accumulator => write!(f, "A"),
+            Addressing::Implied => Ok(()),
+
+            Addressing::Relative(_) => self.fmt_target(f, address, symbols),
+
+            Addressing::Immediate(x) => write!(f, "#${:02X}", x),
+
+            Addressing::Zeropage(x) => write!(f, "{}", zeropage(x)),
+            Addressing::ZeropageX(x) => write!(f, "{},X", zeropage(x)),
+            Addressing::ZeropageY(x) => write!(f, "{},Y", zeropage(x)),
+
+            Addressing::Absolute(x) => write!(f, "{}", absolute(x)),
+            Addressing::AbsoluteX(x) => write!(f, "{},X", absolute(x)),
+            Addressing::AbsoluteY(x) => write!(f, "{},Y", absolute(x)),
+
+            Addressing::Indirect(x) => write!(f, "({})", absolute(x)),
+            Addressing::IndirectX(x) => write!(f, "({},X)", zeropage(x)),
+            Addressing::IndirectY(x) => write!(f, "({}),Y", zeropage(x)),
+
+            Addressing::ZeropageIndirect(x) => write!(f, "({})", zeropage(x)),
+            Addressing::AbsoluteIndirectX(x) => write!(f, "({},X)", absolute(x)),
+            Addressing::ZeropageRelative(x, _) => {
+                write!(f, "{},", zeropage(x))?;
+                self.fmt_target(f, address, symbols)
+            }
+        }
+    }
+
+    fn fmt_target(
+        &self,
+        f: &mut Formatter<'_>,
+        address: Option<u16>,
+        symbols: Option<&Symbols>,
+    ) -> fmt::Result {
+        match address {
+            Some(address) => {
+                let target = self.branch_target(address).unwrap_or(0);
+
+                match symbols.and_then(|symbols| symbols.describe(target)) {
+                    Some(name) => write!(f, "{}", name),
+                    None => write!(f, "${:04X}", target),
+                }
+            }
+            None => {
+                // distance from the start of the instruction
+                let target = self.branch_target(0).unwrap_or(0) as i16;
+                write!(f, "*{:+}", target)
+            }
+        }
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_at(f, None, None)
+    }
+}
+
+impl Instruction {
+    /// Displays the instruction as if it was located at `address`,
+    /// so that branch targets get resolved, as in `BNE $8002`
+    pub fn display_at(&self, address: u16) -> impl Display + '_ {
+        DisplayAt {
+            instruction: self,
+            address,
+            symbols: None,
+        }
+    }
+
+    /// Like [`Instruction::display_at`], naming operands after `symbols`, as in `BNE loop`
+    pub fn display_with<'a>(&'a self, address: u16, symbols: &'a Symbols) -> impl Display + 'a {
+        DisplayAt {
+            instruction: self,
+            address,
+            symbols: Some(symbols),
+        }
+    }
+
+    fn fmt_at(
+        &self,
+        f: &mut Formatter<'_>,
+        address: Option<u16>,
+        symbols: Option<&Symbols>,
+    ) -> fmt::Result {
+        write!(f, "{}", self.instruction_type)?;
+
+        match self.addressing {
+            Addressing::Implied => Ok(()),
+            addressing => {
+                write!(f, " ")?;
+                addressing.fmt_operand(f, address, symbols)
+            }
+        }
+    }
+}
+
+struct DisplayAt<'a> {
+    instruction: &'a Instruction,
+    address: u16,
+    symbols: Option<&'a Symbols>,
+}
+
+impl Display for DisplayAt<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.instruction.fmt_at(f, Some(self.address), self.symbols)
+    }
+}
@@ -1,268 +1,783 @@
 // use super::Instruction;
 use super::{Addressing, Instruction, InstructionType};
+use crate::cpu::{error::EncodeError, variant::Variant};
 
-macro_rules! opcode_match {
-    ($opcode: expr
+// a `*` after the cycle count marks the opcodes that take
+// one extra cycle when the indexed address crosses a page boundary
+macro_rules! page_penalty {
+    () => {
+        false
+    };
+    (*) => {
+        true
+    };
+}
+
+// bit branches carry both a zeropage address and a branch offset
+macro_rules! operand_addressing {
+    (ZeropageRelative) => {
+        Addressing::ZeropageRelative(0, 0)
+    };
+    ($addressing:tt) => {
+        Addressing::$addressing(0)
+    };
+}
+
+// generates both the decoder and the encoder of an opcode table,
+// so that the two can't disagree
+macro_rules! opcode_table {
+    ($(#[$meta:meta])* $decode:ident, $encode:ident
         $(, $type:tt => {
-            $(($matchcode:expr, $addressing:tt)),*
-            $(; $(($optcode:expr, $optaddr:tt)),*)? })*
-        $(; $($impltype:tt => $implcode:expr),*)?) =>
+            $(($matchcode:expr, $addressing:tt, $cycles:literal $($page:tt)?)),*
+            $(; $(($optcode:expr, $optaddr:tt, $optcycles:literal)),*)? })*
+        $(; $($impltype:tt => ($implcode:expr, $implcycles:literal)),*)?) =>
     {
-        match $opcode {
-            $(
+        $(#[$meta])*
+        fn $decode(opcode: u8) -> Option<Decoded> {
+            match opcode {
                 $(
-                    $matchcode => Some((InstructionType::$type, Addressing::$addressing(0))),
-
+                    $(
+                        $matchcode => Some((
+                            InstructionType::$type,
+                            operand_addressing!($addressing),
+                            $cycles,
+                            page_penalty!($($page)?),
+                        )),
+                    )*
+                    $(
+                        $(
+                            $optcode => Some((InstructionType::$type, Addressing::$optaddr, $optcycles, false)),
+                        )*
+                    )?
                 )*
                 $(
                     $(
-                        $optcode => Some((InstructionType::$type, Addressing::$optaddr)),
+                        $implcode => Some((InstructionType::$impltype, Addressing::Implied, $implcycles, false)),
                     )*
                 )?
-            )*
-            $(
+
+                _ => None,
+            }
+        }
+
+        // opcodes sharing the same instruction and addressing encode to the first of them
+        #[allow(unreachable_patterns)]
+        fn $encode(instruction_type: InstructionType, addressing: Addressing) -> Option<u8> {
+            match (instruction_type, addressing) {
                 $(
-                    $implcode => Some((InstructionType::$impltype, Addressing::Implied)),
+                    $(
+                        (InstructionType::$type, Addressing::$addressing(..)) => Some($matchcode),
+                    )*
+                    $(
+                        $(
+                            (InstructionType::$type, Addressing::$optaddr) => Some($optcode),
+                        )*
+                    )?
                 )*
-            )?
+                $(
+                    $(
+                        (InstructionType::$impltype, Addressing::Implied) => Some($implcode),
+                    )*
+                )?
 
-            _ => None,
+                _ => None,
+            }
         }
     };
+}
+
+/// Instruction type, addressing mode, base cycles and page crossing penalty of an opcode
+type Decoded = (InstructionType, Addressing, u8, bool);
+
+opcode_table! {
+    legal, legal_opcode,
+
+    ADC => {
+        (0x69, Immediate, 2),
+        (0x65, Zeropage, 3),
+        (0x75, ZeropageX, 4),
+        (0x6D, Absolute, 4),
+        (0x7D, AbsoluteX, 4*),
+        (0x79, AbsoluteY, 4*),
+        (0x61, IndirectX, 6),
+        (0x71, IndirectY, 5*)
+    },
+    AND => {
+        (0x29, Immediate, 2),
+        (0x25, Zeropage, 3),
+        (0x35, ZeropageX, 4),
+        (0x2D, Absolute, 4),
+        (0x3D, AbsoluteX, 4*),
+        (0x39, AbsoluteY, 4*),
+        (0x21, IndirectX, 6),
+        (0x31, IndirectY, 5*)
+    },
+    ASL => {
+        (0x06, Zeropage, 5),
+        (0x16, ZeropageX, 6),
+        (0x0E, Absolute, 6),
+        (0x1E, AbsoluteX, 7);
+        (0x0A, Accumulator, 2)
+    },
+    BCC => {
+        (0x90, Relative, 2)
+    },
+    BCS => {
+        (0xB0, Relative, 2)
+    },
+    BEQ => {
+        (0xF0, Relative, 2)
+    },
+    BIT => {
+        (0x24, Zeropage, 3),
+        (0x2C, Absolute, 4)
+    },
+    BMI => {
+        (0x30, Relative, 2)
+    },
+    BNE => {
+        (0xD0, Relative, 2)
+    },
+    BPL => {
+        (0x10, Relative, 2)
+    },
+    BVC => {
+        (0x50, Relative, 2)
+    },
+    BVS => {
+        (0x70, Relative, 2)
+    },
+    CMP => {
+        (0xC9, Immediate, 2),
+        (0xc5, Zeropage, 3),
+        (0xd5, ZeropageX, 4),
+        (0xcd, Absolute, 4),
+        (0xdd, AbsoluteX, 4*),
+        (0xd9, AbsoluteY, 4*),
+        (0xc1, IndirectX, 6),
+        (0xd1, IndirectY, 5*)
+    },
+    CPX => {
+        (0xe0, Immediate, 2),
+        (0xe4, Zeropage, 3),
+        (0xec, Absolute, 4)
+    },
+    CPY => {
+        (0xc0, Immediate, 2),
+        (0xc4, Zeropage, 3),
+        (0xcc, Absolute, 4)
+    },
+    DEC => {
+        (0xc6, Zeropage, 5),
+        (0xd6, ZeropageX, 6),
+        (0xce, Absolute, 6),
+        (0xde, AbsoluteX, 7)
+    },
+    EOR => {
+        (0x49, Immediate, 2),
+        (0x45, Zeropage, 3),
+        (0x55, ZeropageX, 4),
+        (0x4d, Absolute, 4),
+        (0x5d, AbsoluteX, 4*),
+        (0x59, AbsoluteY, 4*),
+        (0x41, IndirectX, 6),
+        (0x51, IndirectY, 5*)
+    },
+    INC => {
+        (0xe6, Zeropage, 5),
+        (0xf6, ZeropageX, 6),
+        (0xee, Absolute, 6),
+        (0xfe, AbsoluteX, 7)
+    },
+    JMP => {
+        (0x4C, Absolute, 3),
+        (0x6C, Indirect, 5)
+    },
+    JSR => {
+        (0x20, Absolute, 6)
+    },
+    LDA => {
+        (0xa9, Immediate, 2),
+        (0xa5, Zeropage, 3),
+        (0xb5, ZeropageX, 4),
+        (0xad, Absolute, 4),
+        (0xbd, AbsoluteX, 4*),
+        (0xb9, AbsoluteY, 4*),
+        (0xa1, IndirectX, 6),
+        (0xb1, IndirectY, 5*)
+    },
+    LDX => {
+        (0xa2, Immediate, 2),
+        (0xa6, Zeropage, 3),
+        (0xb6, ZeropageY, 4),
+        (0xae, Absolute, 4),
+        (0xbe, AbsoluteY, 4*)
+    },
+    LDY => {
+        (0xa0, Immediate, 2),
+        (0xa4, Zeropage, 3),
+        (0xb4, ZeropageX, 4),
+        (0xac, Absolute, 4),
+        (0xbc, AbsoluteX, 4*)
+    },
+    LSR => {
+        (0x46, Zeropage, 5),
+        (0x56, ZeropageX, 6),
+        (0x4E, Absolute, 6),
+        (0x5e, AbsoluteX, 7);
+        (0x4a, Accumulator, 2)
+    },
+    ORA => {
+        (0x09, Immediate, 2),
+        (0x05, Zeropage, 3),
+        (0x15, ZeropageX, 4),
+        (0x0d, Absolute, 4),
+        (0x1d, AbsoluteX, 4*),
+        (0x19, AbsoluteY, 4*),
+        (0x01, IndirectX, 6),
+        (0x11, IndirectY, 5*)
+    },
+    ROL => {
+        (0x26, Zeropage, 5),
+        (0x36, ZeropageX, 6),
+        (0x2e, Absolute, 6),
+        (0x3e, AbsoluteX, 7);
+        (0x2a, Accumulator, 2)
+    },
+    ROR => {
+        (0x66, Zeropage, 5),
+        (0x76, ZeropageX, 6),
+        (0x6e, Absolute, 6),
+        (0x7e, AbsoluteX, 7);
+        (0x6a, Accumulator, 2)
+    },
+    SBC => {
+        (0xe9, Immediate, 2),
+        (0xe5, Zeropage, 3),
+        (0xf5, ZeropageX, 4),
+        (0xed, Absolute, 4),
+        (0xfd, AbsoluteX, 4*),
+        (0xf9, AbsoluteY, 4*),
+        (0xe1, IndirectX, 6),
+        (0xf1, IndirectY, 5*)
+    },
+    STA => {
+        (0x85, Zeropage, 3),
+        (0x95, ZeropageX, 4),
+        (0x8d, Absolute, 4),
+        (0x9d, AbsoluteX, 5),
+        (0x99, AbsoluteY, 5),
+        (0x81, IndirectX, 6),
+        (0x91, IndirectY, 6)
+    },
+    STX => {
+        (0x86, Zeropage, 3),
+        (0x96, ZeropageY, 4),
+        (0x8e, Absolute, 4)
+    },
+    STY => {
+        (0x84, Zeropage, 3),
+        (0x94, ZeropageX, 4),
+        (0x8c, Absolute, 4)
+    }
+    ;
+
+    NOP => (0xea, 2),
+    PLA => (0x68, 4),
+    BRK => (0x00, 7),
+    CLC => (0x18, 2),
+    CLD => (0xD8, 2),
+    CLI => (0x58, 2),
+    CLV => (0xB8, 2),
+    DEX => (0xCA, 2),
+    DEY => (0x88, 2),
+    INX => (0xE8, 2),
+    INY => (0xC8, 2),
+    PHA => (0x48, 3),
+    PHP => (0x08, 3),
+    PLP => (0x28, 4),
+    RTI => (0x40, 6),
+    RTS => (0x60, 6),
+    SEC => (0x38, 2),
+    SED => (0xF8, 2),
+    SEI => (0x78, 2),
+    TAX => (0xAA, 2),
+    TAY => (0xA8, 2),
+    TSX => (0xBA, 2),
+    TXA => (0x8A, 2),
+    TXS => (0x9A, 2),
+    TYA => (0x98, 2)
+}
+
+opcode_table! {
+    undocumented, undocumented_opcode,
+
+    ALR => {
+        (0x4B, Immediate, 2)
+    },
+    ANC => {
+        (0x0B, Immediate, 2),
+        (0x2B, Immediate, 2)
+    },
+    ARR => {
+        (0x6B, Immediate, 2)
+    },
+    AXS => {
+        (0xCB, Immediate, 2)
+    },
+    DCP => {
+        (0xC7, Zeropage, 5),
+        (0xD7, ZeropageX, 6),
+        (0xCF, Absolute, 6),
+        (0xDF, AbsoluteX, 7),
+        (0xDB, AbsoluteY, 7),
+        (0xC3, IndirectX, 8),
+        (0xD3, IndirectY, 8)
+    },
+    ISC => {
+        (0xE7, Zeropage, 5),
+        (0xF7, ZeropageX, 6),
+        (0xEF, Absolute, 6),
+        (0xFF, AbsoluteX, 7),
+        (0xFB, AbsoluteY, 7),
+        (0xE3, IndirectX, 8),
+        (0xF3, IndirectY, 8)
+    },
+    LAX => {
+        (0xA7, Zeropage, 3),
+        (0xB7, ZeropageY, 4),
+        (0xAF, Absolute, 4),
+        (0xBF, AbsoluteY, 4*),
+        (0xA3, IndirectX, 6),
+        (0xB3, IndirectY, 5*)
+    },
+    NOP => {
+        (0x80, Immediate, 2),
+        (0x82, Immediate, 2),
+        (0x89, Immediate, 2),
+        (0xC2, Immediate, 2),
+        (0xE2, Immediate, 2),
+        (0x04, Zeropage, 3),
+        (0x44, Zeropage, 3),
+        (0x64, Zeropage, 3),
+        (0x14, ZeropageX, 4),
+        (0x34, ZeropageX, 4),
+        (0x54, ZeropageX, 4),
+        (0x74, ZeropageX, 4),
+        (0xD4, ZeropageX, 4),
+        (0xF4, ZeropageX, 4),
+        (0x0C, Absolute, 4),
+        (0x1C, AbsoluteX, 4*),
+        (0x3C, AbsoluteX, 4*),
+        (0x5C, AbsoluteX, 4*),
+        (0x7C, AbsoluteX, 4*),
+        (0xDC, AbsoluteX, 4*),
+        (0xFC, AbsoluteX, 4*);
+        (0x1A, Implied, 2),
+        (0x3A, Implied, 2),
+        (0x5A, Implied, 2),
+        (0x7A, Implied, 2),
+        (0xDA, Implied, 2),
+        (0xFA, Implied, 2)
+    },
+    RLA => {
+        (0x27, Zeropage, 5),
+        (0x37, ZeropageX, 6),
+        (0x2F, Absolute, 6),
+        (0x3F, AbsoluteX, 7),
+        (0x3B, AbsoluteY, 7),
+        (0x23, IndirectX, 8),
+        (0x33, IndirectY, 8)
+    },
+    RRA => {
+        (0x67, Zeropage, 5),
+        (0x77, ZeropageX, 6),
+        (0x6F, Absolute, 6),
+        (0x7F, AbsoluteX, 7),
+        (0x7B, AbsoluteY, 7),
+        (0x63, IndirectX, 8),
+        (0x73, IndirectY, 8)
+    },
+    SAX => {
+        (0x87, Zeropage, 3),
+        (0x97, ZeropageY, 4),
+        (0x8F, Absolute, 4),
+        (0x83, IndirectX, 6)
+    },
+    SBC => {
+        (0xEB, Immediate, 2)
+    },
+    SLO => {
+        (0x07, Zeropage, 5),
+        (0x17, ZeropageX, 6),
+        (0x0F, Absolute, 6),
+        (0x1F, AbsoluteX, 7),
+        (0x1B, AbsoluteY, 7),
+        (0x03, IndirectX, 8),
+        (0x13, IndirectY, 8)
+    },
+    SRE => {
+        (0x47, Zeropage, 5),
+        (0x57, ZeropageX, 6),
+        (0x4F, Absolute, 6),
+        (0x5F, AbsoluteX, 7),
+        (0x5B, AbsoluteY, 7),
+        (0x43, IndirectX, 8),
+        (0x53, IndirectY, 8)
+    }
+    ;
+
+    JAM => (0x02, 2),
+    JAM => (0x12, 2),
+    JAM => (0x22, 2),
+    JAM => (0x32, 2),
+    JAM => (0x42, 2),
+    JAM => (0x52, 2),
+    JAM => (0x62, 2),
+    JAM => (0x72, 2),
+    JAM => (0x92, 2),
+    JAM => (0xB2, 2),
+    JAM => (0xD2, 2),
+    JAM => (0xF2, 2)
+}
 
+opcode_table! {
+    /// Opcodes added or changed by the 65C02, on top of the legal NMOS ones
+    cmos, cmos_opcode,
+
+    ADC => {
+        (0x72, ZeropageIndirect, 5)
+    },
+    AND => {
+        (0x32, ZeropageIndirect, 5)
+    },
+    ASL => {
+        (0x1E, AbsoluteX, 6*)
+    },
+    BBR0 => {
+        (0x0F, ZeropageRelative, 5)
+    },
+    BBR1 => {
+        (0x1F, ZeropageRelative, 5)
+    },
+    BBR2 => {
+        (0x2F, ZeropageRelative, 5)
+    },
+    BBR3 => {
+        (0x3F, ZeropageRelative, 5)
+    },
+    BBR4 => {
+        (0x4F, ZeropageRelative, 5)
+    },
+    BBR5 => {
+        (0x5F, ZeropageRelative, 5)
+    },
+    BBR6 => {
+        (0x6F, ZeropageRelative, 5)
+    },
+    BBR7 => {
+        (0x7F, ZeropageRelative, 5)
+    },
+    BBS0 => {
+        (0x8F, ZeropageRelative, 5)
+    },
+    BBS1 => {
+        (0x9F, ZeropageRelative, 5)
+    },
+    BBS2 => {
+        (0xAF, ZeropageRelative, 5)
+    },
+    BBS3 => {
+        (0xBF, ZeropageRelative, 5)
+    },
+    BBS4 => {
+        (0xCF, ZeropageRelative, 5)
+    },
+    BBS5 => {
+        (0xDF, ZeropageRelative, 5)
+    },
+    BBS6 => {
+        (0xEF, ZeropageRelative, 5)
+    },
+    BBS7 => {
+        (0xFF, ZeropageRelative, 5)
+    },
+    BIT => {
+        (0x89, Immediate, 2),
+        (0x34, ZeropageX, 4),
+        (0x3C, AbsoluteX, 4*)
+    },
+    BRA => {
+        (0x80, Relative, 2)
+    },
+    CMP => {
+        (0xD2, ZeropageIndirect, 5)
+    },
+    DEC => {
+        ;
+        (0x3A, Accumulator, 2)
+    },
+    EOR => {
+        (0x52, ZeropageIndirect, 5)
+    },
+    INC => {
+        ;
+        (0x1A, Accumulator, 2)
+    },
+    JMP => {
+        (0x6C, Indirect, 6),
+        (0x7C, AbsoluteIndirectX, 6)
+    },
+    LDA => {
+        (0xB2, ZeropageIndirect, 5)
+    },
+    LSR => {
+        (0x5E, AbsoluteX, 6*)
+    },
+    NOP => {
+        (0x02, Immediate, 2),
+        (0x22, Immediate, 2),
+        (0x42, Immediate, 2),
+        (0x62, Immediate, 2),
+        (0x82, Immediate, 2),
+        (0xC2, Immediate, 2),
+        (0xE2, Immediate, 2),
+        (0x44, Zeropage, 3),
+        (0x54, ZeropageX, 4),
+        (0xD4, ZeropageX, 4),
+        (0xF4, ZeropageX, 4),
+        (0x5C, Absolute, 8),
+        (0xDC, Absolute, 4),
+        (0xFC, Absolute, 4);
+        (0x03, Implied, 1),
+        (0x13, Implied, 1),
+        (0x23, Implied, 1),
+        (0x33, Implied, 1),
+        (0x43, Implied, 1),
+        (0x53, Implied, 1),
+        (0x63, Implied, 1),
+        (0x73, Implied, 1),
+        (0x83, Implied, 1),
+        (0x93, Implied, 1),
+        (0xA3, Implied, 1),
+        (0xB3, Implied, 1),
+        (0xC3, Implied, 1),
+        (0xD3, Implied, 1),
+        (0xE3, Implied, 1),
+        (0xF3, Implied, 1),
+        (0x0B, Implied, 1),
+        (0x1B, Implied, 1),
+        (0x2B, Implied, 1),
+        (0x3B, Implied, 1),
+        (0x4B, Implied, 1),
+        (0x5B, Implied, 1),
+        (0x6B, Implied, 1),
+        (0x7B, Implied, 1),
+        (0x8B, Implied, 1),
+        (0x9B, Implied, 1),
+        (0xAB, Implied, 1),
+        (0xBB, Implied, 1),
+        (0xEB, Implied, 1),
+        (0xFB, Implied, 1)
+    },
+    ORA => {
+        (0x12, ZeropageIndirect, 5)
+    },
+    RMB0 => {
+        (0x07, Zeropage, 5)
+    },
+    RMB1 => {
+        (0x17, Zeropage, 5)
+    },
+    RMB2 => {
+        (0x27, Zeropage, 5)
+    },
+    RMB3 => {
+        (0x37, Zeropage, 5)
+    },
+    RMB4 => {
+        (0x47, Zeropage, 5)
+    },
+    RMB5 => {
+        (0x57, Zeropage, 5)
+    },
+    RMB6 => {
+        (0x67, Zeropage, 5)
+    },
+    RMB7 => {
+        (0x77, Zeropage, 5)
+    },
+    ROL => {
+        (0x3E, AbsoluteX, 6*)
+    },
+    ROR => {
+        (0x7E, AbsoluteX, 6*)
+    },
+    SBC => {
+        (0xF2, ZeropageIndirect, 5)
+    },
+    SMB0 => {
+        (0x87, Zeropage, 5)
+    },
+    SMB1 => {
+        (0x97, Zeropage, 5)
+    },
+    SMB2 => {
+        (0xA7, Zeropage, 5)
+    },
+    SMB3 => {
+        (0xB7, Zeropage, 5)
+    },
+    SMB4 => {
+        (0xC7, Zeropage, 5)
+    },
+    SMB5 => {
+        (0xD7, Zeropage, 5)
+    },
+    SMB6 => {
+        (0xE7, Zeropage, 5)
+    },
+    SMB7 => {
+        (0xF7, Zeropage, 5)
+    },
+    STA => {
+        (0x92, ZeropageIndirect, 5)
+    },
+    STZ => {
+        (0x64, Zeropage, 3),
+        (0x74, ZeropageX, 4),
+        (0x9C, Absolute, 4),
+        (0x9E, AbsoluteX, 5)
+    },
+    TRB => {
+        (0x14, Zeropage, 5),
+        (0x1C, Absolute, 6)
+    },
+    TSB => {
+        (0x04, Zeropage, 5),
+        (0x0C, Absolute, 6)
+    }
+    ;
+
+    PHX => (0xDA, 3),
+    PHY => (0x5A, 3),
+    PLX => (0xFA, 4),
+    PLY => (0x7A, 4),
+    STP => (0xDB, 3),
+    WAI => (0xCB, 3)
 }
 
 impl Instruction {
-    pub fn read_instruction(opcode: u8, mut read_byte: impl FnMut() -> u8) -> Option<Instruction> {
-        let mut instr = opcode_match! {
-            opcode,
-
-            ADC => {
-                (0x69, Immediate),
-                (0x65, Zeropage),
-                (0x75, ZeropageX),
-                (0x6D, Absolute),
-                (0x7D, AbsoluteX),
-                (0x79, AbsoluteY),
-                (0x61, IndirectX),
-                (0x71, IndirectY)
-            },
-            AND => {
-                (0x29, Immediate),
-                (0x25, Zeropage),
-                (0x35, ZeropageX),
-                (0x2D, Absolute),
-                (0x3D, AbsoluteX),
-                (0x39, AbsoluteY),
-                (0x21, IndirectX),
-                (0x31, IndirectY)
-            },
-            ASL => {
-                (0x06, Zeropage),
-                (0x16, ZeropageX),
-                (0x0E, Absolute),
-                (0x1E, AbsoluteX);
-                (0x0A, Accumulator)
-            },
-            BCC => {
-                (0x90, Relative)
-            },
-            BCS => {
-                (0xB0, Relative)
-            },
-            BEQ => {
-                (0xF0, Relative)
-            },
-            BIT => {
-                (0x24, Zeropage),
-                (0x2C, Absolute)
-            },
-            BMI => {
-                (0x30, Relative)
-            },
-            BNE => {
-                (0xD0, Relative)
-            },
-            BPL => {
-                (0x10, Relative)
-            },
-            BVC => {
-                (0x50, Relative)
-            },
-            BVS => {
-                (0x70, Relative)
-            },
-            CMP => {
-                (0xC9, Immediate),
-                (0xc5, Zeropage),
-                (0xd5, ZeropageX),
-                (0xcd, Absolute),
-                (0xdd, AbsoluteX),
-                (0xd9, AbsoluteY),
-                (0xc1, IndirectX),
-                (0xd1, IndirectY)
-            },
-            CPX => {
-                (0xe0, Immediate),
-                (0xe4, Zeropage),
-                (0xec, Absolute)
-            },
-            CPY => {
-                (0xc0, Immediate),
-                (0xc4, Zeropage),
-                (0xcc, Absolute)
-            },
-            DEC => {
-                (0xc6, Zeropage),
-                (0xd6, ZeropageX),
-                (0xce, Absolute),
-                (0xde, AbsoluteX)
-            },
-            EOR => {
-                (0x49, Immediate),
-                (0x45, Zeropage),
-                (0x55, ZeropageX),
-                (0x4d, Absolute),
-                (0x5d, AbsoluteX),
-                (0x59, AbsoluteY),
-                (0x41, IndirectX),
-                (0x51, IndirectY)
-            },
-            INC => {
-                (0xe6, Zeropage),
-                (0xf6, ZeropageX),
-                (0xee, Absolute),
-                (0xfe, AbsoluteX)
-            },
-            JMP => {
-                (0x4C, Absolute),
-                (0x6C, Indirect)
-            },
-            JSR => {
-                (0x20, Absolute)
-            },
-            LDA => {
-                (0xa9, Immediate),
-                (0xa5, Zeropage),
-                (0xb5, ZeropageX),
-                (0xad, Absolute),
-                (0xbd, AbsoluteX),
-                (0xb9, AbsoluteY),
-                (0xa1, IndirectX),
-                (0xb1, IndirectY)
-            },
-            LDX => {
-                (0xa2, Immediate),
-                (0xa6, Zeropage),
-                (0xb6, ZeropageY),
-                (0xae, Absolute),
-                (0xbe, AbsoluteY)
-            },
-            LDY => {
-                (0xa0, Immediate),
-                (0xa4, Zeropage),
-                (0xb4, ZeropageX),
-                (0xac, Absolute),
-                (0xbc, AbsoluteX)
-            },
-            LSR => {
-                (0x46, Zeropage),
-                (0x56, ZeropageX),
-                (0x4E, Absolute),
-                (0x5e, AbsoluteX);
-                (0x4a, Accumulator)
-            },
-            ORA => {
-                (0x09, Immediate),
-                (0x05, Zeropage),
-                (0x15, ZeropageX),
-                (0x0d, Absolute),
-                (0x1d, AbsoluteX),
-                (0x19, AbsoluteY),
-                (0x01, IndirectX),
-                (0x11, IndirectY)
-            },
-            ROL => {
-                (0x26, Zeropage),
-                (0x36, ZeropageX),
-                (0x2e, Absolute),
-                (0x3e, AbsoluteX);
-                (0x2a, Accumulator)
-            },
-            ROR => {
-                (0x66, Zeropage),
-                (0x76, ZeropageX),
-                (0x6e, Absolute),
-                (0x7e, AbsoluteX);
-                (0x6a, Accumulator)
-            },
-            SBC => {
-                (0xe9, Immediate),
-                (0xe5, Zeropage),
-                (0xf5, ZeropageX),
-                (0xed, Absolute),
-                (0xfd, AbsoluteX),
-                (0xf9, AbsoluteY),
-                (0xe1, IndirectX),
-                (0xf1, IndirectY)
-            },
-            STA => {
-                (0x85, Zeropage),
-                (0x95, ZeropageX),
-                (0x8d, Absolute),
-                (0x9d, AbsoluteX),
-                (0x99, AbsoluteY),
-                (0x81, IndirectX),
-                (0x91, IndirectY)
-            },
-            STX => {
-                (0x86, Zeropage),
-                (0x96, ZeropageY),
-                (0x8e, Absolute)
-            },
-            STY => {
-                (0x84, Zeropage),
-                (0x94, ZeropageX),
-                (0x8c, Absolute)
-            }
-            ;
-
-            NOP => 0xea,
-            PLA => 0x68,
-            BRK => 0x00,
-            CLC => 0x18,
-            CLD => 0xD8,
-            CLI => 0x58,
-            CLV => 0xB8,
-            DEX => 0xCA,
-            DEY => 0x88,
-            INX => 0xE8,
-            INY => 0xC8,
-            PHA => 0x48,
-            PHP => 0x08,
-            PLP => 0x28,
-            RTI => 0x40,
-            RTS => 0x60,
-            SEC => 0x38,
-            SED => 0xF8,
-            SEI => 0x78,
-            TAX => 0xAA,
-            TAY => 0xA8,
-            TSX => 0xBA,
-            TXA => 0x8A,
-            TXS => 0x9A,
-            TYA => 0x98
-        }?;
+    /// Decodes one of the legal NMOS opcodes, reading its operand through `read_byte`
+    pub fn read_instruction(opcode: u8, read_byte: impl FnMut() -> u8) -> Option<Instruction> {
+        Some(Self::read_operand(legal(opcode)?, read_byte))
+    }
+
+    /// Like [`Instruction::read_instruction`], but also decodes the undocumented NMOS opcodes
+    pub fn read_undocumented(opcode: u8, read_byte: impl FnMut() -> u8) -> Option<Instruction> {
+        let instr = legal(opcode).or_else(|| undocumented(opcode))?;
+
+        Some(Self::read_operand(instr, read_byte))
+    }
+
+    /// Decodes one of the 65C02 opcodes, which leaves no opcode undefined
+    pub fn read_cmos_instruction(opcode: u8, read_byte: impl FnMut() -> u8) -> Option<Instruction> {
+        let instr = cmos(opcode).or_else(|| legal(opcode))?;
+
+        Some(Self::read_operand(instr, read_byte))
+    }
+
+    /// Decodes `opcode` the way `variant` does, with or without the undocumented NMOS opcodes
+    pub fn read_variant(
+        variant: Variant,
+        undocumented: bool,
+        opcode: u8,
+        read_byte: impl FnMut() -> u8,
+    ) -> Option<Instruction> {
+        if variant.is_cmos() {
+            Self::read_cmos_instruction(opcode, read_byte)
+        } else if undocumented {
+            Self::read_undocumented(opcode, read_byte)
+        } else {
+            Self::read_instruction(opcode, read_byte)
+        }
+    }
+
+    /// Encodes one of the legal NMOS instructions into its opcode, followed by its operand
+    pub fn encode(
+        instruction_type: InstructionType,
+        addressing: Addressing,
+    ) -> Result<Vec<u8>, EncodeError> {
+        Self::encode_variant(Variant::Nmos6502, false, instruction_type, addressing)
+    }
+
+    /// Like [`Instruction::encode`], with the opcodes `variant` understands.
+    /// Legal opcodes are preferred whenever more than one would do
+    pub fn encode_variant(
+        variant: Variant,
+        undocumented: bool,
+        instruction_type: InstructionType,
+        addressing: Addressing,
+    ) -> Result<Vec<u8>, EncodeError> {
+        let opcode = legal_opcode(instruction_type, addressing)
+            .or_else(|| {
+                if variant.is_cmos() {
+                    cmos_opcode(instruction_type, addressing)
+                } else if undocumented {
+                    undocumented_opcode(instruction_type, addressing)
+                } else {
+                    None
+                }
+            })
+            .ok_or(EncodeError::UnsupportedAddressing(
+                instruction_type,
+                addressing,
+            ))?;
+
+        let mut bytes = vec![opcode];
 
+        match addressing {
+            Addressing::Accumulator | Addressing::Implied => (),
+
+            Addressing::Relative(x)
+            | Addressing::Immediate(x)
+            | Addressing::Zeropage(x)
+            | Addressing::ZeropageX(x)
+            | Addressing::ZeropageY(x)
+            | Addressing::IndirectX(x)
+            | Addressing::IndirectY(x)
+            | Addressing::ZeropageIndirect(x) => bytes.push(x),
+
+            Addressing::ZeropageRelative(addr, offset) => bytes.extend([addr, offset]),
+
+            Addressing::Indirect(x)
+            | Addressing::AbsoluteIndirectX(x)
+            | Addressing::Absolute(x)
+            | Addressing::AbsoluteX(x)
+            | Addressing::AbsoluteY(x) => bytes.extend(x.to_le_bytes()),
+        }
+
+        Ok(bytes)
+    }
+
+    fn read_operand(mut instr: Decoded, mut read_byte: impl FnMut() -> u8) -> Instruction {
         match &mut instr.1 {
             Addressing::Relative(x)
             | Addressing::Immediate(x)
             | Addressing::Zeropage(x)
             | Addressing::ZeropageX(x)
+            | Addressing::ZeropageY(x)
             | Addressing::IndirectX(x)
-            | Addressing::IndirectY(x) => *x = read_byte(),
+            | Addressing::IndirectY(x)
+            | Addressing::ZeropageIndirect(x) => *x = read_byte(),
+
+            Addressing::ZeropageRelative(addr, offset) => {
+                *addr = read_byte();
+                *offset = read_byte();
+            }
 
             Addressing::Indirect(x)
+            | Addressing::AbsoluteIndirectX(x)
             | Addressing::Absolute(x)
             | Addressing::AbsoluteX(x)
             | Addressing::AbsoluteY(x) => {
@@ -272,9 +787,11 @@ impl Instruction {
             _ => (),
         }
 
-        Some(Instruction {
+        Instruction {
             instruction_type: instr.0,
             addressing: instr.1,
-        })
+            cycles: instr.2,
+            page_penalty: instr.3,
+        }
     }
 }
@@ -1,6 +1,7 @@
+mod display;
 mod mapping;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Addressing {
     Accumulator,
     Implied,
@@ -20,9 +21,15 @@ pub enum Addressing {
     Indirect(u16),
     IndirectX(u8),
     IndirectY(u8),
+
+    // 65C02 only
+    ZeropageIndirect(u8),
+    AbsoluteIndirectX(u16),
+    /// zeropage address and branch offset of BBR and BBS
+    ZeropageRelative(u8, u8),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InstructionType {
     ADC, //     add with carry
     AND, // and (with accumulator)
@@ -80,10 +87,118 @@ pub enum InstructionType {
     TXA, // transfer X to accumulator
     TXS, // transfer X to stack pointer
     TYA, // transfer Y to accumulator
+
+    // undocumented NMOS instructions
+    ALR, // and, then logical shift right the accumulator
+    ANC, // and, then copy the negative flag into carry
+    ARR, // and, then rotate right the accumulator
+    AXS, // X = (A and X) - operand, setting carry like a compare
+    DCP, // decrement, then compare with accumulator
+    ISC, // increment, then subtract with carry
+    JAM, // halt the processor
+    LAX, // load accumulator and X
+    RLA, // rotate left, then and with accumulator
+    RRA, // rotate right, then add with carry
+    SAX, // store accumulator and X
+    SLO, // arithmetic shift left, then or with accumulator
+    SRE, // logical shift right, then exclusive or with accumulator
+
+    // 65C02 instructions
+    BBR0, // branch on bit 0 reset
+    BBR1, // branch on bit 1 reset
+    BBR2, // branch on bit 2 reset
+    BBR3, // branch on bit 3 reset
+    BBR4, // branch on bit 4 reset
+    BBR5, // branch on bit 5 reset
+    BBR6, // branch on bit 6 reset
+    BBR7, // branch on bit 7 reset
+    BBS0, // branch on bit 0 set
+    BBS1, // branch on bit 1 set
+    BBS2, // branch on bit 2 set
+    BBS3, // branch on bit 3 set
+    BBS4, // branch on bit 4 set
+    BBS5, // branch on bit 5 set
+    BBS6, // branch on bit 6 set
+    BBS7, // branch on bit 7 set
+    BRA,  // branch always
+    PHX,  // push X
+    PHY,  // push Y
+    PLX,  // pull X
+    PLY,  // pull Y
+    RMB0, // reset memory bit 0
+    RMB1, // reset memory bit 1
+    RMB2, // reset memory bit 2
+    RMB3, // reset memory bit 3
+    RMB4, // reset memory bit 4
+    RMB5, // reset memory bit 5
+    RMB6, // reset memory bit 6
+    RMB7, // reset memory bit 7
+    SMB0, // set memory bit 0
+    SMB1, // set memory bit 1
+    SMB2, // set memory bit 2
+    SMB3, // set memory bit 3
+    SMB4, // set memory bit 4
+    SMB5, // set memory bit 5
+    SMB6, // set memory bit 6
+    SMB7, // set memory bit 7
+    STP,  // stop the processor
+    STZ,  // store zero
+    TRB,  // test and reset bits
+    TSB,  // test and set bits
+    WAI,  // wait for interrupt
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct Instruction {
     pub instruction_type: InstructionType,
     pub addressing: Addressing,
+
+    /// base number of clock cycles the instruction takes
+    pub cycles: u8,
+    /// whether an indexed access crossing a page boundary costs one more cycle
+    pub page_penalty: bool,
+}
+
+impl Addressing {
+    /// Number of operand bytes following the opcode
+    pub fn operand_len(&self) -> u16 {
+        match self {
+            Addressing::Accumulator | Addressing::Implied => 0,
+
+            Addressing::Relative(_)
+            | Addressing::Immediate(_)
+            | Addressing::Zeropage(_)
+            | Addressing::ZeropageX(_)
+            | Addressing::ZeropageY(_)
+            | Addressing::IndirectX(_)
+            | Addressing::IndirectY(_)
+            | Addressing::ZeropageIndirect(_) => 1,
+
+            Addressing::Absolute(_)
+            | Addressing::AbsoluteX(_)
+            | Addressing::AbsoluteY(_)
+            | Addressing::Indirect(_)
+            | Addressing::AbsoluteIndirectX(_)
+            | Addressing::ZeropageRelative(..) => 2,
+        }
+    }
+
+    /// Where a branch taken by an instruction at `address` lands
+    pub fn branch_target(&self, address: u16) -> Option<u16> {
+        let offset = match *self {
+            Addressing::Relative(offset) | Addressing::ZeropageRelative(_, offset) => offset,
+            _ => return None,
+        };
+
+        let next = address.wrapping_add(1 + self.operand_len());
+
+        Some(next.wrapping_add(offset as i8 as u16))
+    }
+}
+
+impl Instruction {
+    /// Size in bytes of the instruction, opcode included
+    pub fn size(&self) -> u16 {
+        1 + self.addressing.operand_len()
+    }
 }
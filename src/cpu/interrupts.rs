@@ -0,0 +1,88 @@
+use super::{
+    addressable_bus::DataBus, status::StatusFlag, Cpu, VECTOR_IRQ, VECTOR_NMI, VECTOR_RESET,
+};
+
+/// Clock cycles taken by the IRQ, NMI and RESET sequences
+const INTERRUPT_CYCLES: u8 = 7;
+
+impl<T: DataBus> Cpu<T> {
+    /// Drives the IRQ input line. The line is level-triggered: the interrupt is
+    /// serviced at the end of every instruction for as long as the line is
+    /// asserted and the Interrupt flag is clear
+    pub fn set_irq(&mut self, asserted: bool) {
+        self.irq = asserted;
+    }
+
+    /// Drives the NMI input line. The line is edge-triggered: asserting it
+    /// latches a single interrupt, which can't be masked
+    pub fn set_nmi(&mut self, asserted: bool) {
+        if asserted && !self.nmi {
+            self.nmi_pending = true;
+        }
+
+        self.nmi = asserted;
+    }
+
+    /// Runs the RESET sequence: the stack pointer goes down by three without
+    /// anything being written, interrupts get disabled and execution restarts
+    /// from the reset vector. The bus sees the same reads as for an interrupt,
+    /// with the pushes turned into reads
+    pub fn reset(&mut self) {
+        self.bus.read(self.program_counter);
+        self.bus.read(self.program_counter);
+        for _ in 0..3 {
+            self.stack_dummy_read();
+            self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+        }
+        self.processor_status.set_flag(StatusFlag::Interrupt, true);
+        self.program_counter = self.bus.read_word(VECTOR_RESET);
+
+        if self.variant.is_cmos() {
+            self.processor_status.set_flag(StatusFlag::Decimal, false);
+        }
+
+        self.nmi_pending = false;
+        self.stall = 0;
+        self.cycles += INTERRUPT_CYCLES as u64;
+    }
+
+    /// Services a pending interrupt, if any, returning the cycles it took.
+    /// `interrupt_disable` is the Interrupt flag as seen when the lines were polled
+    pub(crate) fn poll_interrupts(&mut self, interrupt_disable: bool) -> u8 {
+        let vector = if self.nmi_pending {
+            self.nmi_pending = false;
+            VECTOR_NMI
+        } else if self.irq && !interrupt_disable {
+            VECTOR_IRQ
+        } else {
+            return 0;
+        };
+
+        // the opcode and the byte after it get fetched, then thrown away
+        self.bus.read(self.program_counter);
+        self.bus.read(self.program_counter);
+        self.interrupt(vector, false);
+
+        INTERRUPT_CYCLES
+    }
+
+    /// Pushes the program counter and the status, then jumps through `vector`.
+    /// The pushed status has the Break flag set only when coming from BRK,
+    /// and CMOS parts also leave decimal mode
+    pub(crate) fn interrupt(&mut self, vector: u16, brk: bool) {
+        let mut ps = self.processor_status.clone();
+
+        ps.set_flag(StatusFlag::Ignored, true);
+        ps.set_flag(StatusFlag::Break, brk);
+
+        self.stack_push_word(self.program_counter);
+        self.stack_push(ps.0);
+
+        self.processor_status.set_flag(StatusFlag::Interrupt, true);
+        self.program_counter = self.bus.read_word(vector);
+
+        if self.variant.is_cmos() {
+            self.processor_status.set_flag(StatusFlag::Decimal, false);
+        }
+    }
+}
@@ -5,28 +5,36 @@ const STACK_OFFSET: u16 = 0x100;
 impl<T: DataBus> Cpu<T> {
     pub fn stack_push(&mut self, x: u8) {
         self.bus.set(STACK_OFFSET + self.stack_pointer as u16, x);
-        self.stack_pointer -= 1;
+        self.stack_pointer = self.stack_pointer.wrapping_sub(1);
     }
 
+    /// Pushes the high byte first, so that the word ends up little endian.
+    /// Like every other stack access, it wraps around within page one
     pub fn stack_push_word(&mut self, x: u16) {
-        self.stack_pointer -= 2;
-        self.bus
-            .set_word(STACK_OFFSET + self.stack_pointer as u16 + 1, x);
+        self.stack_push((x >> 8) as u8);
+        self.stack_push(x as u8);
     }
 
     pub fn stack_pop(&mut self) -> u8 {
-        self.stack_pointer += 1;
-        self.bus.get(STACK_OFFSET + (self.stack_pointer) as u16)
+        self.stack_pointer = self.stack_pointer.wrapping_add(1);
+        self.bus.read(STACK_OFFSET + (self.stack_pointer) as u16)
     }
 
     pub fn stack_pop_word(&mut self) -> u16 {
-        self.stack_pointer += 2;
-        self.bus
-            .get_word(STACK_OFFSET + (self.stack_pointer) as u16 - 1)
+        let ll = self.stack_pop();
+        let hh = self.stack_pop();
+
+        ((hh as u16) << 8) + ll as u16
+    }
+
+    /// Reads the top of the stack without pulling it, as the cpu does on
+    /// the cycle it spends incrementing the stack pointer
+    pub(crate) fn stack_dummy_read(&mut self) {
+        self.bus.read(STACK_OFFSET + self.stack_pointer as u16);
     }
 
     pub fn read_byte(&mut self) -> u8 {
-        self.program_counter += 1;
-        self.bus.get(self.program_counter - 1)
+        self.program_counter = self.program_counter.wrapping_add(1);
+        self.bus.read(self.program_counter.wrapping_sub(1))
     }
 }
@@ -7,20 +7,29 @@ use self::{
     error::CpuError,
     instruction::{Addressing, Instruction},
     status::{ProcessorStatus, StatusFlag},
+    variant::Variant,
 };
+use crate::symbols::Symbols;
 use std::fmt::Display;
 
 pub mod addressable_bus;
 pub mod addressing;
+pub mod decimal;
 pub mod error;
 pub mod instruction;
+pub mod interrupts;
 pub mod memops;
 pub mod shifting;
+pub mod snapshot;
 pub mod status;
 pub mod tick;
+pub mod variant;
 
 pub struct Cpu<T: DataBus> {
     pub bus: T,
+    pub variant: Variant,
+    /// whether the undocumented NMOS opcodes get decoded, CMOS parts ignore it
+    pub undocumented_opcodes: bool,
 
     pub program_counter: u16,
 
@@ -31,21 +40,64 @@ pub struct Cpu<T: DataBus> {
     pub stack_pointer: u8,
 
     pub processor_status: ProcessorStatus,
+
+    /// total clock cycles elapsed since the cpu was started
+    pub cycles: u64,
+    /// idle cycles left before [`Cpu::clock`] can start a new instruction
+    stall: u8,
+
+    /// level of the IRQ input line
+    irq: bool,
+    /// level of the NMI input line
+    nmi: bool,
+    /// set on an NMI edge, until the interrupt gets serviced
+    nmi_pending: bool,
 }
 
 impl<T: DataBus> Display for Cpu<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "PC: 0x{:X?} SP: 0x{:X?} --- ",
-            self.program_counter, self.stack_pointer
-        )?;
+        self.fmt_with(f, None)
+    }
+}
+
+struct DisplayWith<'a, T: DataBus> {
+    cpu: &'a Cpu<T>,
+    symbols: &'a Symbols,
+}
+
+impl<T: DataBus> Display for DisplayWith<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.cpu.fmt_with(f, Some(self.symbols))
+    }
+}
+
+impl<T: DataBus> Cpu<T> {
+    /// Displays the registers, naming the program counter after `symbols`
+    /// as in `PC: 0x8012 (loop+3)`
+    pub fn display_with<'a>(&'a self, symbols: &'a Symbols) -> impl Display + 'a {
+        DisplayWith { cpu: self, symbols }
+    }
+
+    fn fmt_with(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        symbols: Option<&Symbols>,
+    ) -> std::fmt::Result {
+        write!(f, "PC: 0x{:X?} ", self.program_counter)?;
+        if let Some(name) = symbols.and_then(|symbols| symbols.describe(self.program_counter)) {
+            write!(f, "({}) ", name)?;
+        }
+        write!(f, "SP: 0x{:X?} --- ", self.stack_pointer)?;
         write!(
             f,
             "A: 0x{:X?} X: 0x{:X?} Y: 0x{:X?} --- ",
             self.accumulator, self.x_register, self.y_register
         )?;
-        writeln!(f, "ST: {:08b}", self.processor_status.0)?;
+        writeln!(
+            f,
+            "ST: {:08b} --- CYC: {}",
+            self.processor_status.0, self.cycles
+        )?;
 
         Ok(())
     }
@@ -53,10 +105,16 @@ impl<T: DataBus> Display for Cpu<T> {
 
 impl<T: DataBus> Cpu<T> {
     pub fn load_memory(memory: T) -> Cpu<T> {
-        let rst_vector = memory.get_word(VECTOR_RESET);
+        Self::with_variant(memory, Variant::default())
+    }
+
+    pub fn with_variant(mut memory: T, variant: Variant) -> Cpu<T> {
+        let rst_vector = memory.read_word(VECTOR_RESET);
 
         Cpu {
             bus: memory,
+            variant,
+            undocumented_opcodes: false,
 
             program_counter: rst_vector,
             accumulator: 0,
@@ -64,16 +122,79 @@ impl<T: DataBus> Cpu<T> {
             y_register: 0,
             stack_pointer: 0,
             processor_status: Default::default(),
+
+            cycles: 0,
+            stall: 0,
+
+            irq: false,
+            nmi: false,
+            nmi_pending: false,
+        }
+    }
+
+    /// Swaps the bus for `f(bus)`, keeping the state of the cpu,
+    /// for instance to wrap it for debugging
+    pub fn map_bus<U: DataBus>(self, f: impl FnOnce(T) -> U) -> Cpu<U> {
+        Cpu {
+            bus: f(self.bus),
+            variant: self.variant,
+            undocumented_opcodes: self.undocumented_opcodes,
+
+            program_counter: self.program_counter,
+            accumulator: self.accumulator,
+            x_register: self.x_register,
+            y_register: self.y_register,
+            stack_pointer: self.stack_pointer,
+            processor_status: self.processor_status,
+
+            cycles: self.cycles,
+            stall: self.stall,
+
+            irq: self.irq,
+            nmi: self.nmi,
+            nmi_pending: self.nmi_pending,
         }
     }
 
+    /// Fetches the opcode and decodes the instruction. The operand bytes are only
+    /// peeked at, [`Cpu::fetch_operand`] reads them on the cycles the hardware does
     fn read_instruction(&mut self) -> Result<Instruction, CpuError> {
         let opcode = self.read_byte();
 
-        Instruction::read_instruction(opcode, || self.read_byte())
-            .ok_or(CpuError::UnknownOpcode(opcode))
+        let (variant, undocumented) = (self.variant, self.undocumented_opcodes);
+        let (bus, mut pc) = (&self.bus, self.program_counter);
+
+        Instruction::read_variant(variant, undocumented, opcode, || {
+            pc = pc.wrapping_add(1);
+            bus.peek(pc.wrapping_sub(1))
+        })
+        .ok_or(CpuError::UnknownOpcode(opcode))
+    }
+
+    /// ADC, honouring decimal mode
+    fn add(&mut self, x: u8) {
+        self.accumulator = if self.decimal_mode() {
+            self.decimal_add(self.accumulator, x)
+        } else {
+            let res = self.add_with_carry(x, self.accumulator);
+            self.flag_value(res);
+            res
+        };
     }
 
+    /// SBC, honouring decimal mode
+    fn subtract(&mut self, x: u8) {
+        self.accumulator = if self.decimal_mode() {
+            self.decimal_sub(self.accumulator, x)
+        } else {
+            let res = self.add_with_carry(self.accumulator, 255 - x);
+            self.flag_value(res);
+            res
+        };
+    }
+
+    /// Binary addition, setting C and V. SBC goes through here as well,
+    /// by adding the ones' complement of its operand
     fn add_with_carry(&mut self, x: u8, y: u8) -> u8 {
         let carry = self.processor_status.get_flag(StatusFlag::Carry);
 
@@ -83,8 +204,11 @@ impl<T: DataBus> Cpu<T> {
         let res_carry = part_carry | res_carry;
 
         self.processor_status.set_flag(StatusFlag::Carry, res_carry);
+
+        // signed overflow happens when both operands have the same sign,
+        // and the result has the opposite one
         self.processor_status
-            .set_flag(StatusFlag::Overflow, carry ^ res_carry);
+            .set_flag(StatusFlag::Overflow, ((x ^ res) & (y ^ res) & 0x80) != 0);
 
         res
     }
@@ -97,21 +221,96 @@ impl<T: DataBus> Cpu<T> {
 
     fn cmp(&mut self, x: u8, y: Addressing) {
         let y = self.load_addressing(y);
+        self.compare(x, y);
+    }
 
+    fn compare(&mut self, x: u8, y: u8) {
         self.processor_status.set_flag(StatusFlag::Carry, x >= y);
         self.processor_status.set_flag(StatusFlag::Zero, x == y);
         self.processor_status
-            .set_flag(StatusFlag::Negative, ((x - y) & 0b1000_0000) != 0);
+            .set_flag(StatusFlag::Negative, (x.wrapping_sub(y) & 0b1000_0000) != 0);
+    }
+
+    /// Takes the branch if `condition` holds, returning the extra cycles spent
+    fn branch(&mut self, condition: bool, addr: Addressing) -> u8 {
+        if !condition {
+            return 0;
+        }
+
+        // the cpu reads the next opcode while adding the offset, and the
+        // address with the low byte fixed up while carrying into the high one
+        self.bus.read(self.program_counter);
+
+        let target = self.address_addressing(addr);
+        let crossed = (self.program_counter & 0xFF00) != (target & 0xFF00);
+        if crossed {
+            self.dummy_read((self.program_counter & 0xFF00) | (target & 0x00FF));
+        }
+        self.program_counter = target;
+
+        1 + crossed as u8
     }
 
-    fn interrupt(&mut self, vector: u16) {
-        let vector = self.bus.get_word(vector);
+    /// BBR and BBS: branches if bit `bit` of the zeropage operand equals `set`,
+    /// returning the extra cycles spent. The operand gets read twice before
+    /// the branch offset is fetched
+    fn branch_on_bit(&mut self, addr: Addressing, bit: u8, set: bool) -> u8 {
+        let mem = self.load_addressing(addr);
+        self.load_addressing(addr);
+        let condition = (mem & (1 << bit) != 0) == set;
 
-        self.stack_push_word(self.program_counter);
-        self.stack_push(self.processor_status.0);
+        let offset = self.read_byte();
+        self.branch(condition, Addressing::Relative(offset))
+    }
+
+    /// RMB and SMB: sets bit `bit` of the operand to `set`
+    fn write_bit(&mut self, addr: Addressing, bit: u8, set: bool) {
+        let val = self.load_modify_addressing(addr);
+        let val = if set {
+            val | (1 << bit)
+        } else {
+            val & !(1 << bit)
+        };
+
+        self.write_addressing(addr, val);
+    }
+
+    /// The undocumented ARR: and with the accumulator, then rotate it right.
+    /// The flags follow the adder rather than the result, and decimal mode
+    /// applies a BCD fixup much like ADC does
+    fn and_rotate_right(&mut self, x: u8) {
+        let carry = self.processor_status.get_flag(StatusFlag::Carry);
+        let and = self.accumulator & x;
+        let mut res = (and >> 1) | ((carry as u8) << 7);
+
+        if !self.decimal_mode() {
+            self.flag_value(res);
+            self.processor_status
+                .set_flag(StatusFlag::Carry, (res & 0b0100_0000) != 0);
+            self.processor_status
+                .set_flag(StatusFlag::Overflow, ((res >> 6) ^ (res >> 5)) & 1 != 0);
+
+            self.accumulator = res;
+            return;
+        }
+
+        self.processor_status.set_flag(StatusFlag::Negative, carry);
+        self.processor_status.set_flag(StatusFlag::Zero, res == 0);
+        self.processor_status
+            .set_flag(StatusFlag::Overflow, ((and ^ res) & 0b0100_0000) != 0);
+
+        let (high, low) = (and >> 4, and & 0x0F);
+        if low + (low & 1) > 5 {
+            res = (res & 0xF0) | (res.wrapping_add(6) & 0x0F);
+        }
+
+        let carry = high + (high & 1) > 5;
+        if carry {
+            res = res.wrapping_add(0x60);
+        }
 
-        self.processor_status.set_flag(StatusFlag::Interrupt, true);
-        self.program_counter = vector;
+        self.processor_status.set_flag(StatusFlag::Carry, carry);
+        self.accumulator = res;
     }
 
     fn pull_status(&mut self) {
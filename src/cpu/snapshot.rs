@@ -0,0 +1,89 @@
+use super::{addressable_bus::DataBus, variant::Variant, Cpu};
+use crate::savestate::{error::SaveStateError, SaveState, Snapshot};
+use std::convert::TryInto;
+
+/// Name of the section holding the registers and the state of the cpu
+pub const CPU_SECTION: &str = "cpu";
+
+/// variant, undocumented opcodes, pc, a, x, y, sp, p, cycles, stall and interrupt lines
+const CPU_SECTION_LEN: usize = 19;
+
+const IRQ: u8 = 0b001;
+const NMI: u8 = 0b010;
+const NMI_PENDING: u8 = 0b100;
+
+impl<T: DataBus + Snapshot> Cpu<T> {
+    /// Snapshots the cpu, pending interrupts included, and its bus
+    pub fn save_state(&self) -> SaveState {
+        let mut state = SaveState::new();
+
+        let mut data = Vec::with_capacity(CPU_SECTION_LEN);
+        data.push(match self.variant {
+            Variant::Nmos6502 => 0,
+            Variant::Ricoh2A03 => 1,
+            Variant::Wdc65C02 => 2,
+        });
+        data.push(self.undocumented_opcodes as u8);
+        data.extend(self.program_counter.to_le_bytes());
+        data.extend([
+            self.accumulator,
+            self.x_register,
+            self.y_register,
+            self.stack_pointer,
+            self.processor_status.0,
+        ]);
+        data.extend(self.cycles.to_le_bytes());
+        data.push(self.stall);
+
+        let mut lines = 0;
+        for (flag, set) in [
+            (IRQ, self.irq),
+            (NMI, self.nmi),
+            (NMI_PENDING, self.nmi_pending),
+        ] {
+            if set {
+                lines |= flag;
+            }
+        }
+        data.push(lines);
+
+        state.insert(CPU_SECTION, data);
+        self.bus.save(&mut state);
+
+        state
+    }
+
+    /// Puts the cpu and its bus back into a saved state. Nothing changes on errors
+    pub fn restore_state(&mut self, state: &SaveState) -> Result<(), SaveStateError> {
+        let data = state.require(CPU_SECTION, CPU_SECTION_LEN)?;
+        let invalid = || SaveStateError::InvalidSection(CPU_SECTION.to_string());
+
+        let variant = match data[0] {
+            0 => Variant::Nmos6502,
+            1 => Variant::Ricoh2A03,
+            2 => Variant::Wdc65C02,
+            _ => return Err(invalid()),
+        };
+        if data[1] > 1 || data[18] & !(IRQ | NMI | NMI_PENDING) != 0 {
+            return Err(invalid());
+        }
+
+        self.bus.restore(state)?;
+
+        self.variant = variant;
+        self.undocumented_opcodes = data[1] == 1;
+        self.program_counter = u16::from_le_bytes([data[2], data[3]]);
+        self.accumulator = data[4];
+        self.x_register = data[5];
+        self.y_register = data[6];
+        self.stack_pointer = data[7];
+        self.processor_status = data[8].into();
+        self.cycles = u64::from_le_bytes(data[9..17].try_into().unwrap());
+        self.stall = data[17];
+        self.irq = data[18] & IRQ != 0;
+        self.nmi = data[18] & NMI != 0;
+        self.nmi_pending = data[18] & NMI_PENDING != 0;
+
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StatusFlag {
     Negative = 0b1000_0000,
     Overflow = 0b0100_0000,
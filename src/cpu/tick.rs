@@ -1,16 +1,17 @@
-use super::{error::CpuError, addressable_bus::DataBus, Cpu};
-use crate::cpu::{instruction::InstructionType, shifting, status::StatusFlag, VECTOR_NMI};
+use super::{addressable_bus::DataBus, error::CpuError, Cpu};
+use crate::cpu::{
+    instruction::{Addressing, Instruction, InstructionType},
+    shifting,
+    status::StatusFlag,
+    VECTOR_IRQ,
+};
 
 macro_rules! flag_branch {
     ($self:ident, $addr:ident, $status:tt) => {
-        if $self.processor_status.get_flag(StatusFlag::$status) {
-            $self.program_counter = $self.address_addressing($addr);
-        }
+        $self.branch($self.processor_status.get_flag(StatusFlag::$status), $addr)
     };
     ($self:ident, $addr:ident, !$status:tt) => {
-        if !$self.processor_status.get_flag(StatusFlag::$status) {
-            $self.program_counter = $self.address_addressing($addr);
-        };
+        $self.branch(!$self.processor_status.get_flag(StatusFlag::$status), $addr)
     };
 }
 
@@ -33,23 +34,46 @@ macro_rules! assign_flag {
     }};
 }
 
+/// The outcome of a single [`Cpu::tick`]
+#[derive(Debug)]
+pub struct Tick {
+    pub instruction: Instruction,
+    /// clock cycles the instruction took, penalties included
+    pub cycles: u8,
+}
+
 impl<T: DataBus> Cpu<T> {
-    pub fn tick(&mut self) -> Result<crate::cpu::Instruction, CpuError> {
+    /// Executes a whole instruction, reporting how many clock cycles it took.
+    /// Pending interrupts are serviced at the end of the instruction, and the
+    /// cycles they take are included
+    pub fn tick(&mut self) -> Result<Tick, CpuError> {
+        self.bus.sync(self.cycles);
+
         let instruction = self.read_instruction()?;
+        let interrupt_disable = self.processor_status.get_flag(StatusFlag::Interrupt);
+
+        let mut cycles = instruction.cycles;
+
+        if instruction.page_penalty && self.page_crossed(instruction.addressing) {
+            cycles += 1;
+        }
+
+        let addr = self.fetch_operand(&instruction);
 
-        let addr = instruction.addressing;
         match instruction.instruction_type {
             InstructionType::ADC => {
                 let mem = self.load_addressing(addr);
-                assign_flag!(self.accumulator = self.add_with_carry(mem, self.accumulator));
+                cycles += self.cmos_decimal_penalty();
+                self.add(mem);
             }
             InstructionType::SBC => {
                 let mem = self.load_addressing(addr);
-                assign_flag!(self.accumulator = self.add_with_carry(self.accumulator, 255 - mem));
+                cycles += self.cmos_decimal_penalty();
+                self.subtract(mem);
             }
             InstructionType::AND => assign_flag!(self.accumulator &= self.load_addressing(addr)),
             InstructionType::ASL => {
-                let res = shifting::rotate_left(false, self.load_addressing(addr));
+                let res = shifting::rotate_left(false, self.load_modify_addressing(addr));
 
                 self.processor_status.set_flag(StatusFlag::Carry, res.1);
                 self.flag_value(res.0);
@@ -59,22 +83,29 @@ impl<T: DataBus> Cpu<T> {
             InstructionType::BIT => {
                 let mem = self.load_addressing(addr);
 
-                self.flag_value(mem);
                 self.processor_status
-                    .set_flag(StatusFlag::Overflow, (mem & 0b0100_0000) != 0);
+                    .set_flag(StatusFlag::Zero, (self.accumulator & mem) == 0);
+
+                // the 65C02 immediate mode only affects Z
+                if !matches!(addr, Addressing::Immediate(_)) {
+                    self.processor_status
+                        .set_flag(StatusFlag::Negative, (mem & 0b1000_0000) != 0);
+                    self.processor_status
+                        .set_flag(StatusFlag::Overflow, (mem & 0b0100_0000) != 0);
+                }
             }
-            InstructionType::BCC => flag_branch!(self, addr, !Carry),
-            InstructionType::BCS => flag_branch!(self, addr, Carry),
-            InstructionType::BEQ => flag_branch!(self, addr, Zero),
-            InstructionType::BNE => flag_branch!(self, addr, !Zero),
-            InstructionType::BMI => flag_branch!(self, addr, Negative),
-            InstructionType::BPL => flag_branch!(self, addr, !Negative),
-            InstructionType::BVC => flag_branch!(self, addr, !Overflow),
-            InstructionType::BVS => flag_branch!(self, addr, Overflow),
+            InstructionType::BCC => cycles += flag_branch!(self, addr, !Carry),
+            InstructionType::BCS => cycles += flag_branch!(self, addr, Carry),
+            InstructionType::BEQ => cycles += flag_branch!(self, addr, Zero),
+            InstructionType::BNE => cycles += flag_branch!(self, addr, !Zero),
+            InstructionType::BMI => cycles += flag_branch!(self, addr, Negative),
+            InstructionType::BPL => cycles += flag_branch!(self, addr, !Negative),
+            InstructionType::BVC => cycles += flag_branch!(self, addr, !Overflow),
+            InstructionType::BVS => cycles += flag_branch!(self, addr, Overflow),
 
             InstructionType::BRK => {
-                self.program_counter += 1;
-                self.interrupt(VECTOR_NMI);
+                self.program_counter = self.program_counter.wrapping_add(1);
+                self.interrupt(VECTOR_IRQ, true);
             }
 
             // InstructionType::CLC => clear_flag!(self, Carry),
@@ -88,30 +119,32 @@ impl<T: DataBus> Cpu<T> {
             InstructionType::CPY => self.cmp(self.y_register, addr),
 
             InstructionType::DEC => {
-                let addr = self.address_addressing(addr);
-                let val = self.bus.get(addr) - 1;
+                let val = self.load_modify_addressing(addr).wrapping_sub(1);
 
-                self.bus.set(addr, val);
+                self.write_addressing(addr, val);
                 self.flag_value(val);
             }
-            InstructionType::DEX => assign_flag!(self.x_register -= 1),
-            InstructionType::DEY => assign_flag!(self.y_register -= 1),
+            InstructionType::DEX => assign_flag!(self.x_register = self.x_register.wrapping_sub(1)),
+            InstructionType::DEY => assign_flag!(self.y_register = self.y_register.wrapping_sub(1)),
 
             InstructionType::EOR => assign_flag!(self.accumulator ^= self.load_addressing(addr)),
 
             InstructionType::INC => {
-                let addr = self.address_addressing(addr);
-                let val = self.bus.get(addr) + 1;
+                let val = self.load_modify_addressing(addr).wrapping_add(1);
 
-                self.bus.set(addr, val);
+                self.write_addressing(addr, val);
                 self.flag_value(val);
             }
-            InstructionType::INX => assign_flag!(self.x_register += 1),
-            InstructionType::INY => assign_flag!(self.y_register += 1),
+            InstructionType::INX => assign_flag!(self.x_register = self.x_register.wrapping_add(1)),
+            InstructionType::INY => assign_flag!(self.y_register = self.y_register.wrapping_add(1)),
 
             InstructionType::JMP => self.program_counter = self.address_addressing(addr),
             InstructionType::JSR => {
-                self.stack_push_word(self.program_counter + 2);
+                // the return address gets pushed before the high byte of the target
+                // is fetched, so it points to the last byte of JSR
+                self.stack_dummy_read();
+                self.stack_push_word(self.program_counter);
+                self.read_byte();
                 self.program_counter = self.address_addressing(addr);
             }
 
@@ -120,7 +153,7 @@ impl<T: DataBus> Cpu<T> {
             InstructionType::LDY => assign_flag!(self.y_register = self.load_addressing(addr)),
 
             InstructionType::LSR => {
-                let res = shifting::rotate_right(false, self.load_addressing(addr));
+                let res = shifting::rotate_right(false, self.load_modify_addressing(addr));
 
                 self.processor_status.set_flag(StatusFlag::Carry, res.1);
                 self.flag_value(res.0);
@@ -128,7 +161,16 @@ impl<T: DataBus> Cpu<T> {
             }
 
             InstructionType::STA => self.write_addressing(addr, self.accumulator),
-            InstructionType::NOP => (), // nop does nop-thing
+            InstructionType::NOP => {
+                // NOPs with a memory operand still read it, and the eight cycle
+                // $5C of the 65C02 keeps reading for four more cycles
+                if let Addressing::Absolute(address) = addr {
+                    let reads = if instruction.cycles == 8 { 5 } else { 1 };
+                    for _ in 0..reads {
+                        self.bus.read(address);
+                    }
+                }
+            }
             InstructionType::ORA => assign_flag!(self.accumulator |= self.load_addressing(addr)),
 
             InstructionType::PHA => self.stack_push(self.accumulator),
@@ -140,13 +182,19 @@ impl<T: DataBus> Cpu<T> {
 
                 self.stack_push(ps.0);
             }
-            InstructionType::PLA => assign_flag!(self.accumulator = self.stack_pop()),
-            InstructionType::PLP => self.pull_status(),
+            InstructionType::PLA => {
+                self.stack_dummy_read();
+                assign_flag!(self.accumulator = self.stack_pop());
+            }
+            InstructionType::PLP => {
+                self.stack_dummy_read();
+                self.pull_status();
+            }
 
             InstructionType::ROL => {
                 let res = shifting::rotate_left(
                     self.processor_status.get_flag(StatusFlag::Carry),
-                    self.load_addressing(addr),
+                    self.load_modify_addressing(addr),
                 );
 
                 self.processor_status.set_flag(StatusFlag::Carry, res.1);
@@ -156,7 +204,7 @@ impl<T: DataBus> Cpu<T> {
             InstructionType::ROR => {
                 let res = shifting::rotate_right(
                     self.processor_status.get_flag(StatusFlag::Carry),
-                    self.load_addressing(addr),
+                    self.load_modify_addressing(addr),
                 );
 
                 self.processor_status.set_flag(StatusFlag::Carry, res.1);
@@ -165,11 +213,17 @@ impl<T: DataBus> Cpu<T> {
             }
 
             InstructionType::RTI => {
+                self.stack_dummy_read();
                 self.pull_status();
                 self.program_counter = self.stack_pop_word();
             }
 
-            InstructionType::RTS => self.program_counter = self.stack_pop_word() + 1,
+            InstructionType::RTS => {
+                // the cpu reads the last byte of JSR again while incrementing past it
+                self.stack_dummy_read();
+                self.program_counter = self.stack_pop_word();
+                self.read_byte();
+            }
 
             InstructionType::SEC => set_flag!(self, Carry),
             InstructionType::SED => set_flag!(self, Decimal),
@@ -182,11 +236,202 @@ impl<T: DataBus> Cpu<T> {
             InstructionType::TAY => assign_flag!(self.y_register = self.accumulator),
             InstructionType::TSX => assign_flag!(self.x_register = self.stack_pointer),
             InstructionType::TXA => assign_flag!(self.accumulator = self.x_register),
-            InstructionType::TXS => assign_flag!(self.stack_pointer = self.x_register),
+            // the only transfer leaving the flags alone
+            InstructionType::TXS => self.stack_pointer = self.x_register,
             InstructionType::TYA => assign_flag!(self.accumulator = self.y_register),
+
+            InstructionType::ALR => {
+                let mem = self.load_addressing(addr);
+                let res = shifting::rotate_right(false, self.accumulator & mem);
+
+                self.processor_status.set_flag(StatusFlag::Carry, res.1);
+                assign_flag!(self.accumulator = res.0);
+            }
+            InstructionType::ANC => {
+                assign_flag!(self.accumulator &= self.load_addressing(addr));
+                self.processor_status.set_flag(
+                    StatusFlag::Carry,
+                    self.processor_status.get_flag(StatusFlag::Negative),
+                );
+            }
+            InstructionType::ARR => {
+                let mem = self.load_addressing(addr);
+                self.and_rotate_right(mem);
+            }
+            InstructionType::AXS => {
+                let mem = self.load_addressing(addr);
+                let and = self.accumulator & self.x_register;
+
+                self.processor_status
+                    .set_flag(StatusFlag::Carry, and >= mem);
+                assign_flag!(self.x_register = and.wrapping_sub(mem));
+            }
+            InstructionType::DCP => {
+                let val = self.load_modify_addressing(addr).wrapping_sub(1);
+
+                self.write_addressing(addr, val);
+                self.compare(self.accumulator, val);
+            }
+            InstructionType::ISC => {
+                let val = self.load_modify_addressing(addr).wrapping_add(1);
+
+                self.write_addressing(addr, val);
+                self.subtract(val);
+            }
+            InstructionType::JAM => {
+                // the cpu stays stuck on the opcode until it gets reset
+                self.program_counter = self.program_counter.wrapping_sub(1);
+                return Err(CpuError::Jammed(self.bus.peek(self.program_counter)));
+            }
+            InstructionType::LAX => {
+                assign_flag!(self.accumulator = self.load_addressing(addr));
+                self.x_register = self.accumulator;
+            }
+            InstructionType::RLA => {
+                let res = shifting::rotate_left(
+                    self.processor_status.get_flag(StatusFlag::Carry),
+                    self.load_modify_addressing(addr),
+                );
+
+                self.write_addressing(addr, res.0);
+                self.processor_status.set_flag(StatusFlag::Carry, res.1);
+                assign_flag!(self.accumulator &= res.0);
+            }
+            InstructionType::RRA => {
+                let res = shifting::rotate_right(
+                    self.processor_status.get_flag(StatusFlag::Carry),
+                    self.load_modify_addressing(addr),
+                );
+
+                self.write_addressing(addr, res.0);
+                self.processor_status.set_flag(StatusFlag::Carry, res.1);
+                self.add(res.0);
+            }
+            InstructionType::SAX => self.write_addressing(addr, self.accumulator & self.x_register),
+            InstructionType::SLO => {
+                let res = shifting::rotate_left(false, self.load_modify_addressing(addr));
+
+                self.write_addressing(addr, res.0);
+                self.processor_status.set_flag(StatusFlag::Carry, res.1);
+                assign_flag!(self.accumulator |= res.0);
+            }
+            InstructionType::SRE => {
+                let res = shifting::rotate_right(false, self.load_modify_addressing(addr));
+
+                self.write_addressing(addr, res.0);
+                self.processor_status.set_flag(StatusFlag::Carry, res.1);
+                assign_flag!(self.accumulator ^= res.0);
+            }
+
+            InstructionType::BBR0 => cycles += self.branch_on_bit(addr, 0, false),
+            InstructionType::BBR1 => cycles += self.branch_on_bit(addr, 1, false),
+            InstructionType::BBR2 => cycles += self.branch_on_bit(addr, 2, false),
+            InstructionType::BBR3 => cycles += self.branch_on_bit(addr, 3, false),
+            InstructionType::BBR4 => cycles += self.branch_on_bit(addr, 4, false),
+            InstructionType::BBR5 => cycles += self.branch_on_bit(addr, 5, false),
+            InstructionType::BBR6 => cycles += self.branch_on_bit(addr, 6, false),
+            InstructionType::BBR7 => cycles += self.branch_on_bit(addr, 7, false),
+            InstructionType::BBS0 => cycles += self.branch_on_bit(addr, 0, true),
+            InstructionType::BBS1 => cycles += self.branch_on_bit(addr, 1, true),
+            InstructionType::BBS2 => cycles += self.branch_on_bit(addr, 2, true),
+            InstructionType::BBS3 => cycles += self.branch_on_bit(addr, 3, true),
+            InstructionType::BBS4 => cycles += self.branch_on_bit(addr, 4, true),
+            InstructionType::BBS5 => cycles += self.branch_on_bit(addr, 5, true),
+            InstructionType::BBS6 => cycles += self.branch_on_bit(addr, 6, true),
+            InstructionType::BBS7 => cycles += self.branch_on_bit(addr, 7, true),
+            InstructionType::BRA => cycles += self.branch(true, addr),
+
+            InstructionType::PHX => self.stack_push(self.x_register),
+            InstructionType::PHY => self.stack_push(self.y_register),
+            InstructionType::PLX => {
+                self.stack_dummy_read();
+                assign_flag!(self.x_register = self.stack_pop());
+            }
+            InstructionType::PLY => {
+                self.stack_dummy_read();
+                assign_flag!(self.y_register = self.stack_pop());
+            }
+
+            InstructionType::RMB0 => self.write_bit(addr, 0, false),
+            InstructionType::RMB1 => self.write_bit(addr, 1, false),
+            InstructionType::RMB2 => self.write_bit(addr, 2, false),
+            InstructionType::RMB3 => self.write_bit(addr, 3, false),
+            InstructionType::RMB4 => self.write_bit(addr, 4, false),
+            InstructionType::RMB5 => self.write_bit(addr, 5, false),
+            InstructionType::RMB6 => self.write_bit(addr, 6, false),
+            InstructionType::RMB7 => self.write_bit(addr, 7, false),
+            InstructionType::SMB0 => self.write_bit(addr, 0, true),
+            InstructionType::SMB1 => self.write_bit(addr, 1, true),
+            InstructionType::SMB2 => self.write_bit(addr, 2, true),
+            InstructionType::SMB3 => self.write_bit(addr, 3, true),
+            InstructionType::SMB4 => self.write_bit(addr, 4, true),
+            InstructionType::SMB5 => self.write_bit(addr, 5, true),
+            InstructionType::SMB6 => self.write_bit(addr, 6, true),
+            InstructionType::SMB7 => self.write_bit(addr, 7, true),
+
+            InstructionType::STP => {
+                // the cpu stays stopped on the opcode until it gets reset
+                self.bus.read(self.program_counter);
+                self.program_counter = self.program_counter.wrapping_sub(1);
+                return Err(CpuError::Stopped);
+            }
+            InstructionType::STZ => self.write_addressing(addr, 0),
+            InstructionType::TRB => {
+                let mem = self.load_modify_addressing(addr);
+
+                self.processor_status
+                    .set_flag(StatusFlag::Zero, (self.accumulator & mem) == 0);
+                self.write_addressing(addr, mem & !self.accumulator);
+            }
+            InstructionType::TSB => {
+                let mem = self.load_modify_addressing(addr);
+
+                self.processor_status
+                    .set_flag(StatusFlag::Zero, (self.accumulator & mem) == 0);
+                self.write_addressing(addr, mem | self.accumulator);
+            }
+            InstructionType::WAI => {
+                // keep executing WAI until an interrupt line gets asserted.
+                // An IRQ masked by the Interrupt flag just resumes execution
+                self.bus.read(self.program_counter);
+                if !self.irq && !self.nmi_pending {
+                    self.program_counter = self.program_counter.wrapping_sub(1);
+                }
+            }
+        };
+
+        // the interrupt lines are polled before CLI, SEI and PLP
+        // get to change the Interrupt flag
+        let interrupt_disable = match instruction.instruction_type {
+            InstructionType::CLI | InstructionType::SEI | InstructionType::PLP => interrupt_disable,
+            _ => self.processor_status.get_flag(StatusFlag::Interrupt),
         };
+        cycles += self.poll_interrupts(interrupt_disable);
+
+        self.cycles += cycles as u64;
+
+        Ok(Tick {
+            instruction,
+            cycles,
+        })
+    }
+
+    /// Advances the cpu by a single clock cycle.
+    ///
+    /// An instruction is executed as a whole on its first cycle, and the cpu then
+    /// stays idle for the remaining ones, so that from the outside every
+    /// instruction takes as many clock cycles as it would on the real hardware.
+    /// Returns the executed instruction on the cycles that start one.
+    pub fn clock(&mut self) -> Result<Option<Tick>, CpuError> {
+        if self.stall > 0 {
+            self.stall -= 1;
+            return Ok(None);
+        }
+
+        let tick = self.tick()?;
+        self.stall = tick.cycles - 1;
 
-        Ok(instruction)
+        Ok(Some(tick))
     }
 }
 
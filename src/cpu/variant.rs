@@ -0,0 +1,26 @@
+/// The flavour of 6502 being emulated
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    /// The original NMOS 6502
+    #[default]
+    Nmos6502,
+    /// The Ricoh 2A03 found in the NES, an NMOS 6502 with decimal mode wired off
+    Ricoh2A03,
+    /// The WDC 65C02, the CMOS redesign including the Rockwell bit instructions
+    Wdc65C02,
+}
+
+impl Variant {
+    /// Whether ADC and SBC honour the Decimal flag
+    pub fn has_decimal_mode(self) -> bool {
+        match self {
+            Self::Nmos6502 | Self::Wdc65C02 => true,
+            Self::Ricoh2A03 => false,
+        }
+    }
+
+    /// Whether this is a CMOS part, which fixes most of the NMOS quirks
+    pub fn is_cmos(self) -> bool {
+        matches!(self, Self::Wdc65C02)
+    }
+}
@@ -0,0 +1,823 @@
+use self::protocol::{base64, read_message, write_message};
+use crate::{
+    assembler::{
+        assemble_variant,
+        expression::{Context, Expr},
+    },
+    cpu::{addressable_bus::DataBus, status::StatusFlag, variant::Variant, Cpu},
+    debugger::{
+        command::Command,
+        error::CommandError,
+        repl,
+        watch::{Access, Watched},
+        Debugger, Goal, Stop,
+    },
+    disassembler::decode,
+    loader::load_file,
+    stack_memory::StackMemory,
+    symbols::Symbols,
+};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    io::{self, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+pub mod protocol;
+
+/// Instructions run between checks for new requests
+const CHUNK: usize = 10_000;
+
+const THREAD_ID: i64 = 1;
+
+/// Most instructions a disassemble request gets, before or after its address
+const MAX_INSTRUCTIONS: i64 = 0x10000;
+
+// variables references of the scopes
+const REGISTERS: i64 = 1;
+const FLAGS: i64 = 2;
+const ZEROPAGE: i64 = 3;
+const STACK: i64 = 4;
+
+const FLAG_NAMES: [(&str, StatusFlag); 7] = [
+    ("N", StatusFlag::Negative),
+    ("V", StatusFlag::Overflow),
+    ("B", StatusFlag::Break),
+    ("D", StatusFlag::Decimal),
+    ("I", StatusFlag::Interrupt),
+    ("Z", StatusFlag::Zero),
+    ("C", StatusFlag::Carry),
+];
+
+/// Serves the Debug Adapter Protocol until the client disconnects, so that editors
+/// can launch and debug programs. Requests are read on a separate thread, so that
+/// a running program can be paused
+pub fn serve(input: impl Read + Send + 'static, output: impl Write) -> io::Result<()> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut input = BufReader::new(input);
+
+        while let Ok(Some(message)) = read_message(&mut input) {
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    Server::new(output).run(receiver)
+}
+
+/// A launched program and what the client knows about it
+struct Session {
+    debugger: Debugger<StackMemory>,
+    stop_on_entry: bool,
+    /// the assembly source of the program, and where its lines got assembled
+    source: Option<PathBuf>,
+    lines: BTreeMap<usize, u16>,
+    addresses: BTreeMap<u16, usize>,
+    // each kind of breakpoint is set as a whole by its own request
+    source_breakpoints: BTreeSet<u16>,
+    instruction_breakpoints: BTreeSet<u16>,
+    function_breakpoints: BTreeSet<u16>,
+}
+
+impl Session {
+    fn sync_breakpoints(&mut self) {
+        self.debugger.breakpoints = self
+            .source_breakpoints
+            .iter()
+            .chain(&self.instruction_breakpoints)
+            .chain(&self.function_breakpoints)
+            .copied()
+            .collect();
+    }
+
+    fn eval(&self, expr: &str) -> Result<i64, CommandError> {
+        let context = Context {
+            symbols: self.debugger.symbols.values(),
+            program_counter: self.debugger.cpu.program_counter as i64,
+        };
+
+        Ok(Expr::parse(expr)?.eval(&context)?)
+    }
+
+    fn address(&self, expr: &str) -> Result<u16, CommandError> {
+        match self.eval(expr)? {
+            value @ 0..=0xFFFF => Ok(value as u16),
+            value => Err(CommandError::ValueOutOfRange(value)),
+        }
+    }
+
+    fn source(&self) -> Value {
+        match &self.source {
+            Some(path) => json!({
+                "name": path.file_name().map(|name| name.to_string_lossy()),
+                "path": path,
+            }),
+            None => Value::Null,
+        }
+    }
+
+    /// The source location of the instruction at `address`, if known
+    fn location(&self, address: u16, mut value: Value) -> Value {
+        if let (Some(line), Some(_)) = (self.addresses.get(&address), &self.source) {
+            value["line"] = json!(line);
+            value["column"] = json!(1);
+            value["source"] = self.source();
+        }
+
+        value
+    }
+}
+
+struct Server<W: Write> {
+    output: W,
+    seq: i64,
+    session: Option<Session>,
+    /// where the program is heading, while it runs
+    running: Option<Goal>,
+    /// events to send once the response to the current request is out
+    events: Vec<Value>,
+}
+
+impl<W: Write> Server<W> {
+    fn new(output: W) -> Self {
+        Self {
+            output,
+            seq: 0,
+            session: None,
+            running: None,
+            events: Vec::new(),
+        }
+    }
+
+    fn run(mut self, receiver: Receiver<Value>) -> io::Result<()> {
+        loop {
+            let message = match self.running {
+                Some(_) => {
+                    self.run_chunk()?;
+
+                    match receiver.try_recv() {
+                        Ok(message) => message,
+                        Err(TryRecvError::Empty) => continue,
+                        Err(TryRecvError::Disconnected) => return Ok(()),
+                    }
+                }
+                None => match receiver.recv() {
+                    Ok(message) => message,
+                    Err(_) => return Ok(()),
+                },
+            };
+
+            if !self.handle(&message)? {
+                return Ok(());
+            }
+        }
+    }
+
+    fn send(&mut self, mut message: Value) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+
+        write_message(&mut self.output, &message)
+    }
+
+    fn event(&mut self, event: &str, body: Value) {
+        self.events.push(json!({
+            "type": "event",
+            "event": event,
+            "body": body,
+        }));
+    }
+
+    fn stopped(&mut self, reason: &str, text: Option<String>) {
+        self.event(
+            "stopped",
+            json!({
+                "reason": reason,
+                "threadId": THREAD_ID,
+                "allThreadsStopped": true,
+                "text": text,
+            }),
+        );
+    }
+
+    fn run_chunk(&mut self) -> io::Result<()> {
+        let (session, goal) = match (self.session.as_mut(), self.running.as_mut()) {
+            (Some(session), Some(goal)) => (session, goal),
+            _ => return Ok(()),
+        };
+
+        let stepping = !matches!(goal, Goal::None);
+
+        match session.debugger.run_for(goal, CHUNK) {
+            Ok(None) => return Ok(()),
+            Ok(Some(Stop::Done)) if stepping => self.stopped("step", None),
+            Ok(Some(Stop::Done)) => self.stopped("pause", None),
+            Ok(Some(Stop::Breakpoint(_))) => self.stopped("breakpoint", None),
+            Ok(Some(Stop::Watchpoint(access))) => {
+                let text = describe(access, &session.debugger.symbols);
+                self.stopped("data breakpoint", Some(text))
+            }
+            Err(err) => {
+                let debugger = &session.debugger;
+                let text = format!("{} at {}", err, debugger.symbols.format(debugger.last));
+                self.stopped("exception", Some(text))
+            }
+        }
+
+        self.running = None;
+        self.flush_events()
+    }
+
+    fn flush_events(&mut self) -> io::Result<()> {
+        for event in std::mem::take(&mut self.events) {
+            self.send(event)?;
+        }
+
+        Ok(())
+    }
+
+    /// Responds to a request, returning whether to keep serving
+    fn handle(&mut self, message: &Value) -> io::Result<bool> {
+        if message["type"] != "request" {
+            return Ok(true);
+        }
+
+        let command = message["command"].as_str().unwrap_or_default();
+        let arguments = &message["arguments"];
+
+        let result = match command {
+            "initialize" => Ok(capabilities()),
+            "launch" => self.launch(arguments),
+            "disconnect" | "terminate" => Ok(Value::Null),
+            _ if self.session.is_none() => Err("No program was launched".into()),
+            _ => self.request(command, arguments),
+        };
+
+        let mut response = json!({
+            "type": "response",
+            "request_seq": message["seq"],
+            "command": command,
+            "success": result.is_ok(),
+        });
+
+        match result {
+            Ok(Value::Null) => (),
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = json!(message),
+        }
+
+        self.send(response)?;
+        self.flush_events()?;
+
+        Ok(!matches!(command, "disconnect" | "terminate"))
+    }
+
+    fn launch(&mut self, arguments: &Value) -> Result<Value, String> {
+        let program = arguments["program"]
+            .as_str()
+            .ok_or("Missing program to launch")?;
+
+        let offset = match &arguments["offset"] {
+            Value::Null => 0x8000,
+            Value::String(offset) => parse_address(offset).ok_or("Invalid offset")?,
+            offset => offset
+                .as_u64()
+                .filter(|&offset| offset <= 0xFFFF)
+                .ok_or("Invalid offset")? as u16,
+        };
+        let variant = match arguments["variant"].as_str() {
+            None | Some("6502") => Variant::Nmos6502,
+            Some("2a03") => Variant::Ricoh2A03,
+            Some("65c02") => Variant::Wdc65C02,
+            Some(variant) => return Err(format!("Unknown variant {}", variant)),
+        };
+        let undocumented = arguments["undocumented"].as_bool().unwrap_or(false);
+
+        let (memory, entry) = load_file(Path::new(program), offset, variant, undocumented)
+            .map_err(|err| err.to_string())?;
+
+        let mut cpu = Cpu::with_variant(Watched::new(memory), variant);
+        cpu.undocumented_opcodes = undocumented;
+        if let Some(entry) = entry {
+            cpu.program_counter = entry;
+        }
+
+        // assembly sources are their own listing
+        let source = arguments["source"].as_str().or_else(|| {
+            let extension = Path::new(program).extension()?.to_str()?.to_lowercase();
+            matches!(extension.as_str(), "s" | "asm").then_some(program)
+        });
+
+        let mut debugger = Debugger::new(cpu);
+
+        let (source, lines) = match source {
+            Some(source) => {
+                let text = std::fs::read_to_string(source).map_err(|err| err.to_string())?;
+                let assembly = assemble_variant(&text, variant, undocumented)
+                    .map_err(|err| err.to_string())?;
+
+                debugger.symbols.extend(Symbols::from(&assembly));
+                (Some(canonical(source)), assembly.lines)
+            }
+            None => (None, BTreeMap::new()),
+        };
+
+        if let Some(path) = arguments["symbols"].as_str() {
+            let symbols = Symbols::load(Path::new(path)).map_err(|err| err.to_string())?;
+            debugger.symbols.extend(symbols);
+        }
+
+        self.session = Some(Session {
+            debugger,
+            stop_on_entry: arguments["stopOnEntry"].as_bool().unwrap_or(false),
+            source,
+            addresses: lines
+                .iter()
+                .map(|(&line, &address)| (address, line))
+                .collect(),
+            lines,
+            source_breakpoints: BTreeSet::new(),
+            instruction_breakpoints: BTreeSet::new(),
+            function_breakpoints: BTreeSet::new(),
+        });
+
+        self.event("initialized", Value::Null);
+
+        Ok(Value::Null)
+    }
+
+    /// Requests needing a launched program
+    fn request(&mut self, command: &str, arguments: &Value) -> Result<Value, String> {
+        let session = self.session.as_mut().ok_or("No program was launched")?;
+
+        let body = match command {
+            "configurationDone" => {
+                match session.stop_on_entry {
+                    true => self.stopped("entry", None),
+                    false => self.running = Some(Goal::None),
+                }
+
+                Value::Null
+            }
+
+            "setBreakpoints" => {
+                let path = arguments["source"]["path"].as_str().map(canonical);
+                let known = path.is_some() && path == session.source;
+
+                session.source_breakpoints.clear();
+
+                let breakpoints: Vec<Value> = array(&arguments["breakpoints"])
+                    .iter()
+                    .map(|breakpoint| {
+                        let line = breakpoint["line"].as_u64().unwrap_or(0) as usize;
+                        // breakpoints move down to the next line with an instruction
+                        let found = session.lines.range(line..).next().filter(|_| known);
+
+                        match found {
+                            Some((&line, &address)) => {
+                                session.source_breakpoints.insert(address);
+
+                                json!({
+                                    "verified": true,
+                                    "line": line,
+                                    "instructionReference": reference(address),
+                                })
+                            }
+                            None => json!({
+                                "verified": false,
+                                "message": "No instruction at this line",
+                            }),
+                        }
+                    })
+                    .collect();
+
+                session.sync_breakpoints();
+                json!({ "breakpoints": breakpoints })
+            }
+            "setInstructionBreakpoints" => {
+                session.instruction_breakpoints.clear();
+
+                let breakpoints: Vec<Value> = array(&arguments["breakpoints"])
+                    .iter()
+                    .map(|breakpoint| {
+                        let address = breakpoint["instructionReference"]
+                            .as_str()
+                            .and_then(parse_address)
+                            .map(|address| {
+                                let offset = breakpoint["offset"].as_i64().unwrap_or(0);
+                                address.wrapping_add(offset as u16)
+                            });
+
+                        match address {
+                            Some(address) => {
+                                session.instruction_breakpoints.insert(address);
+
+                                session.location(
+                                    address,
+                                    json!({
+                                        "verified": true,
+                                        "instructionReference": reference(address),
+                                    }),
+                                )
+                            }
+                            None => json!({
+                                "verified": false,
+                                "message": "Invalid instruction reference",
+                            }),
+                        }
+                    })
+                    .collect();
+
+                session.sync_breakpoints();
+                json!({ "breakpoints": breakpoints })
+            }
+            "setFunctionBreakpoints" => {
+                session.function_breakpoints.clear();
+
+                let breakpoints: Vec<Value> = array(&arguments["breakpoints"])
+                    .iter()
+                    .map(|breakpoint| {
+                        let name = breakpoint["name"].as_str().unwrap_or_default();
+
+                        match session.address(name) {
+                            Ok(address) => {
+                                session.function_breakpoints.insert(address);
+
+                                session.location(
+                                    address,
+                                    json!({
+                                        "verified": true,
+                                        "instructionReference": reference(address),
+                                    }),
+                                )
+                            }
+                            Err(err) => json!({
+                                "verified": false,
+                                "message": err.to_string(),
+                            }),
+                        }
+                    })
+                    .collect();
+
+                session.sync_breakpoints();
+                json!({ "breakpoints": breakpoints })
+            }
+            "setExceptionBreakpoints" => json!({ "breakpoints": [] }),
+
+            "threads" => json!({ "threads": [{ "id": THREAD_ID, "name": "6502" }] }),
+            "stackTrace" => {
+                let debugger = &session.debugger;
+                let calls = debugger.backtrace();
+
+                let mut frames = Vec::new();
+                let mut address = debugger.cpu.program_counter;
+
+                for (id, &call) in calls.iter().enumerate() {
+                    // each frame is named after the subroutine it's in
+                    let subroutine = debugger.cpu.bus.peek_word(call.wrapping_add(1));
+                    let name = debugger.symbols.format(subroutine);
+                    frames.push(frame(session, id, address, &name));
+                    address = call;
+                }
+                frames.push(frame(session, calls.len(), address, "main"));
+
+                json!({ "stackFrames": frames, "totalFrames": frames.len() })
+            }
+            "scopes" => json!({
+                "scopes": [
+                    { "name": "Registers", "variablesReference": REGISTERS, "expensive": false },
+                    { "name": "Flags", "variablesReference": FLAGS, "expensive": false },
+                    { "name": "Zero page", "variablesReference": ZEROPAGE, "expensive": false },
+                    { "name": "Stack", "variablesReference": STACK, "expensive": false },
+                ]
+            }),
+            "variables" => {
+                let reference = arguments["variablesReference"].as_i64().unwrap_or(0);
+                json!({ "variables": variables(&session.debugger.cpu, reference) })
+            }
+            "setVariable" => {
+                let name = arguments["name"].as_str().unwrap_or_default();
+                let value = arguments["value"].as_str().unwrap_or_default();
+                let value = session.eval(value).map_err(|err| err.to_string())?;
+
+                let cpu = &mut session.debugger.cpu;
+                let out_of_range = |_| CommandError::ValueOutOfRange(value).to_string();
+                let byte = || u8::try_from(value).map_err(out_of_range);
+                let word = || u16::try_from(value).map_err(out_of_range);
+
+                let scope = arguments["variablesReference"].as_i64().unwrap_or(0);
+
+                match (scope, name) {
+                    (REGISTERS, "A") => cpu.accumulator = byte()?,
+                    (REGISTERS, "X") => cpu.x_register = byte()?,
+                    (REGISTERS, "Y") => cpu.y_register = byte()?,
+                    (REGISTERS, "SP") => cpu.stack_pointer = byte()?,
+                    (REGISTERS, "P") => cpu.processor_status = byte()?.into(),
+                    (REGISTERS, "PC") => cpu.program_counter = word()?,
+                    (FLAGS, name) => {
+                        let (_, flag) = FLAG_NAMES
+                            .iter()
+                            .find(|(flag, _)| *flag == name)
+                            .ok_or("Unknown flag")?;
+                        cpu.processor_status.set_flag(*flag, value != 0);
+                    }
+                    _ => return Err(format!("{} can't be set", name)),
+                }
+
+                let variables = variables(cpu, scope);
+                let variable = variables.iter().find(|variable| variable["name"] == name);
+
+                json!({ "value": variable.map(|variable| variable["value"].clone()) })
+            }
+
+            "continue" => {
+                self.running = Some(Goal::None);
+                json!({ "allThreadsContinued": true })
+            }
+            "next" => {
+                self.running = Some(session.debugger.over());
+                Value::Null
+            }
+            "stepIn" => {
+                self.running = Some(Goal::Steps(1));
+                Value::Null
+            }
+            "stepOut" => {
+                self.running = Some(session.debugger.out());
+                Value::Null
+            }
+            "pause" => {
+                if self.running.take().is_some() {
+                    self.stopped("pause", None);
+                }
+
+                Value::Null
+            }
+
+            "readMemory" => {
+                let address = memory_reference(arguments)?;
+                let count = arguments["count"].as_u64().unwrap_or(0).min(0x10000) as usize;
+
+                let bytes: Vec<u8> = (0..count)
+                    .map(|i| {
+                        session
+                            .debugger
+                            .cpu
+                            .bus
+                            .peek(address.wrapping_add(i as u16))
+                    })
+                    .collect();
+
+                json!({ "address": reference(address), "data": base64(&bytes) })
+            }
+            "disassemble" => {
+                let address = memory_reference(arguments)?;
+                let first = arguments["instructionOffset"].as_i64().unwrap_or(0);
+                let count = arguments["instructionCount"].as_i64().unwrap_or(0);
+
+                // no more instructions than the address space holds
+                let first = first.clamp(-MAX_INSTRUCTIONS, MAX_INSTRUCTIONS);
+                let count = count.clamp(0, MAX_INSTRUCTIONS);
+
+                json!({ "instructions": disassemble(session, address, first, count) })
+            }
+            "evaluate" => self.evaluate(arguments)?,
+
+            _ => return Err(format!("Unsupported request {}", command)),
+        };
+
+        Ok(body)
+    }
+
+    /// Evaluates expressions, or runs debugger commands typed in the debug console
+    fn evaluate(&mut self, arguments: &Value) -> Result<Value, String> {
+        let session = self.session.as_mut().ok_or("No program was launched")?;
+        let expression = arguments["expression"].as_str().unwrap_or_default();
+
+        let command = match arguments["context"].as_str() {
+            Some("repl") => Command::parse(expression),
+            _ => Err(CommandError::UnknownCommand(expression.into())),
+        };
+
+        let result = match command {
+            // running goes through the client, so that it learns where the program stopped
+            Ok(Command::Continue) => {
+                self.running = Some(Goal::None);
+                String::new()
+            }
+            Ok(Command::Step(None)) => {
+                self.running = Some(Goal::Steps(1));
+                String::new()
+            }
+            Ok(Command::Next) => {
+                self.running = Some(session.debugger.over());
+                String::new()
+            }
+            Ok(Command::Finish) => {
+                self.running = Some(session.debugger.out());
+                String::new()
+            }
+            Ok(command) => {
+                let mut output = Vec::new();
+                repl::execute(&mut session.debugger, &command, &mut output)
+                    .map_err(|err| err.to_string())?;
+
+                String::from_utf8_lossy(&output).trim_end().to_string()
+            }
+            Err(CommandError::UnknownCommand(_)) => {
+                let value = session.eval(expression).map_err(|err| err.to_string())?;
+                format!("${:X} ({})", value, value)
+            }
+            Err(err) => return Err(err.to_string()),
+        };
+
+        Ok(json!({ "result": result, "variablesReference": 0 }))
+    }
+}
+
+fn capabilities() -> Value {
+    json!({
+        "supportsConfigurationDoneRequest": true,
+        "supportsFunctionBreakpoints": true,
+        "supportsInstructionBreakpoints": true,
+        "supportsSetVariable": true,
+        "supportsReadMemoryRequest": true,
+        "supportsDisassembleRequest": true,
+        "supportsSteppingGranularity": false,
+    })
+}
+
+fn frame(session: &Session, id: usize, address: u16, name: &str) -> Value {
+    session.location(
+        address,
+        json!({
+            "id": id,
+            "name": name,
+            "line": 0,
+            "column": 0,
+            "instructionPointerReference": reference(address),
+        }),
+    )
+}
+
+fn variables<T: DataBus>(cpu: &Cpu<T>, scope: i64) -> Vec<Value> {
+    let variable = |name: String, value: String| json!({ "name": name, "value": value, "variablesReference": 0 });
+    let rows = |page: u16| -> Vec<Value> {
+        (0..16)
+            .map(|row| {
+                let address = page + row * 16;
+                let bytes: Vec<String> = (address..address + 16)
+                    .map(|address| format!("{:02X}", cpu.bus.peek(address)))
+                    .collect();
+
+                let mut row = variable(format!("${:04X}", address), bytes.join(" "));
+                row["memoryReference"] = json!(reference(address));
+                row
+            })
+            .collect()
+    };
+
+    match scope {
+        REGISTERS => {
+            let mut pc = variable("PC".into(), format!("${:04X}", cpu.program_counter));
+            pc["memoryReference"] = json!(reference(cpu.program_counter));
+
+            vec![
+                variable("A".into(), format!("${:02X}", cpu.accumulator)),
+                variable("X".into(), format!("${:02X}", cpu.x_register)),
+                variable("Y".into(), format!("${:02X}", cpu.y_register)),
+                variable("SP".into(), format!("${:02X}", cpu.stack_pointer)),
+                pc,
+                variable("P".into(), format!("${:02X}", cpu.processor_status.0)),
+                variable("Cycles".into(), cpu.cycles.to_string()),
+            ]
+        }
+        FLAGS => FLAG_NAMES
+            .iter()
+            .map(|&(name, flag)| {
+                let set = cpu.processor_status.get_flag(flag);
+                variable(name.into(), (set as u8).to_string())
+            })
+            .collect(),
+        ZEROPAGE => rows(0x0000),
+        STACK => rows(0x0100),
+        _ => Vec::new(),
+    }
+}
+
+/// `instructionCount` instructions, starting `first` instructions away from `address`
+fn disassemble(session: &Session, address: u16, first: i64, count: i64) -> Vec<Value> {
+    let (cpu, symbols) = (&session.debugger.cpu, &session.debugger.symbols);
+    let line = |address: u16| decode(&cpu.bus, address, cpu.variant, cpu.undocumented_opcodes);
+    let invalid = |address: i64| {
+        json!({
+            "address": format!("0x{:04X}", address.clamp(0, 0xFFFF)),
+            "instruction": "",
+            "presentationHint": "invalid",
+        })
+    };
+
+    let mut addresses = Vec::new();
+
+    // instructions before `address` can't be told for sure, so decode from far enough
+    // before it, hoping to fall in step with the instruction stream
+    if first < 0 {
+        let mut before = Vec::new();
+        let mut next = (address as i64 + 3 * first).max(0);
+
+        while next < address as i64 {
+            before.push(next);
+            next += line(next as u16).bytes.len() as i64;
+        }
+
+        let skip = before.len().saturating_sub(-first as usize);
+        addresses.extend(before.into_iter().skip(skip));
+        while (addresses.len() as i64) < -first {
+            addresses.insert(0, -1);
+        }
+    }
+
+    let mut next = address as i64;
+    for i in 0.. {
+        if addresses.len() as i64 >= count - first.min(0) {
+            break;
+        }
+
+        if i >= first.max(0) {
+            addresses.push(next);
+        }
+        if next <= 0xFFFF {
+            next += line(next as u16).bytes.len() as i64;
+        }
+    }
+
+    addresses
+        .into_iter()
+        .take(count as usize)
+        .map(|address| match address {
+            0..=0xFFFF => {
+                let line = line(address as u16);
+                let bytes: Vec<String> = line.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+                let mut instruction = json!({
+                    "address": reference(address as u16),
+                    "instructionBytes": bytes.join(" "),
+                    "instruction": line.mnemonic_with(symbols),
+                });
+                if let Some(label) = symbols.label(address as u16) {
+                    instruction["symbol"] = json!(label);
+                }
+
+                session.location(address as u16, instruction)
+            }
+            address => invalid(address),
+        })
+        .collect()
+}
+
+fn describe(access: Access, symbols: &Symbols) -> String {
+    let address = symbols.format(access.address);
+
+    match access.write {
+        true => format!("Wrote ${:02X} to {}", access.value, address),
+        false => format!("Read ${:02X} from {}", access.value, address),
+    }
+}
+
+fn memory_reference(arguments: &Value) -> Result<u16, String> {
+    let address = arguments["memoryReference"]
+        .as_str()
+        .and_then(parse_address)
+        .ok_or("Invalid memory reference")?;
+    let offset = arguments["offset"].as_i64().unwrap_or(0);
+
+    Ok(address.wrapping_add(offset as u16))
+}
+
+fn reference(address: u16) -> String {
+    format!("0x{:04X}", address)
+}
+
+/// `0x` or `$` prefixed hex, or plain hex as for the offset on the command line
+fn parse_address(text: &str) -> Option<u16> {
+    let digits = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix('$'))
+        .unwrap_or(text);
+
+    u16::from_str_radix(digits, 16).ok()
+}
+
+fn array(value: &Value) -> &[Value] {
+    value.as_array().map(Vec::as_slice).unwrap_or_default()
+}
+
+fn canonical(path: &str) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.into())
+}
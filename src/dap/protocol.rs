@@ -0,0 +1,72 @@
+use serde_json::Value;
+use std::io::{self, BufRead, ErrorKind, Write};
+
+/// Reads the next message, framed by a `Content-Length` header,
+/// or `None` once the client closes its end
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut len = None;
+
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                len = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let len = len.ok_or_else(|| invalid("missing Content-Length"))?;
+    let mut content = vec![0; len];
+    input.read_exact(&mut content)?;
+
+    serde_json::from_slice(&content)
+        .map(Some)
+        .map_err(|err| invalid(&err.to_string()))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let content = message.to_string();
+
+    write!(
+        output,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    output.flush()
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Memory contents travel as base64
+pub fn base64(bytes: &[u8]) -> String {
+    let mut text = String::new();
+
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+            group | (byte as u32) << (16 - 8 * i)
+        });
+
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => text.push(BASE64[(group >> (18 - 6 * i) & 0x3F) as usize] as char),
+                false => text.push('='),
+            }
+        }
+    }
+
+    text
+}
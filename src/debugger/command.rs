@@ -0,0 +1,183 @@
+use super::error::CommandError;
+use crate::assembler::expression::Expr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    A,
+    X,
+    Y,
+    SP,
+    PC,
+    P,
+}
+
+/// A debugger command. Numeric arguments are assembler expressions, without spaces:
+/// `$` hex, `%` binary or decimal numbers, `*` for the program counter and so on
+#[derive(Debug, Clone)]
+pub enum Command {
+    /// sets a breakpoint, or lists breakpoints and watchpoints without an address
+    Break(Option<Expr>),
+    /// deletes the breakpoint at an address, or every breakpoint and watchpoint
+    Delete(Option<Expr>),
+    /// watches `len` bytes from an address, 1 by default
+    Watch {
+        address: Expr,
+        len: Option<Expr>,
+        read: bool,
+        write: bool,
+    },
+    Continue,
+    Step(Option<Expr>),
+    Next,
+    Finish,
+    Regs,
+    Set(Register, Expr),
+    Mem(Expr, Option<Expr>),
+    Poke(Expr, Vec<Expr>),
+    Disasm(Option<Expr>, Option<Expr>),
+    /// writes a save state of the cpu and memory to a file
+    Save(String),
+    /// restores a save state from a file
+    Load(String),
+    Help,
+    Quit,
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Command, CommandError> {
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap_or_default().to_ascii_lowercase();
+        let args: Vec<&str> = words.collect();
+
+        let arg = |i: usize| -> Result<Option<Expr>, CommandError> {
+            args.get(i)
+                .map(|arg| Expr::parse(arg))
+                .transpose()
+                .map_err(From::from)
+        };
+        let required = |i: usize, name: &'static str| -> Result<Expr, CommandError> {
+            arg(i)?.ok_or(CommandError::MissingArgument(name))
+        };
+        let at_most = |count: usize| match args.get(count) {
+            Some(arg) => Err(CommandError::UnexpectedArgument(arg.to_string())),
+            None => Ok(()),
+        };
+
+        let (read, write) = match name.as_str() {
+            "rwatch" => (true, false),
+            "awatch" => (true, true),
+            _ => (false, true),
+        };
+
+        let command = match name.as_str() {
+            "break" | "b" => {
+                at_most(1)?;
+                Command::Break(arg(0)?)
+            }
+            "delete" | "d" => {
+                at_most(1)?;
+                Command::Delete(arg(0)?)
+            }
+            "watch" | "rwatch" | "awatch" => {
+                at_most(2)?;
+                Command::Watch {
+                    address: required(0, "address")?,
+                    len: arg(1)?,
+                    read,
+                    write,
+                }
+            }
+            "continue" | "c" => {
+                at_most(0)?;
+                Command::Continue
+            }
+            "step" | "s" => {
+                at_most(1)?;
+                Command::Step(arg(0)?)
+            }
+            "next" | "n" => {
+                at_most(0)?;
+                Command::Next
+            }
+            "finish" | "f" => {
+                at_most(0)?;
+                Command::Finish
+            }
+            "regs" | "r" => {
+                at_most(0)?;
+                Command::Regs
+            }
+            "set" => {
+                // both `set a=$10` and `set a = $10`
+                let assignment = args.concat();
+                let (register, value) = assignment
+                    .split_once('=')
+                    .ok_or(CommandError::MissingArgument("register=value"))?;
+
+                Command::Set(parse_register(register)?, Expr::parse(value)?)
+            }
+            "mem" | "m" => {
+                at_most(2)?;
+                Command::Mem(required(0, "address")?, arg(1)?)
+            }
+            "poke" => {
+                let address = required(0, "address")?;
+                let bytes = (1..args.len())
+                    .map(|i| required(i, "value"))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                if bytes.is_empty() {
+                    return Err(CommandError::MissingArgument("value"));
+                }
+
+                Command::Poke(address, bytes)
+            }
+            "disasm" | "dis" => {
+                at_most(2)?;
+                Command::Disasm(arg(0)?, arg(1)?)
+            }
+            "save" | "load" => {
+                at_most(1)?;
+                let path = args
+                    .first()
+                    .ok_or(CommandError::MissingArgument("file"))?
+                    .to_string();
+
+                match name.as_str() {
+                    "save" => Command::Save(path),
+                    _ => Command::Load(path),
+                }
+            }
+            "help" | "h" | "?" => Command::Help,
+            "quit" | "q" => Command::Quit,
+            _ => return Err(CommandError::UnknownCommand(name)),
+        };
+
+        Ok(command)
+    }
+
+    /// Whether an empty line runs the command again. Only stepping and
+    /// inspecting are, so that a stray Enter can't write memory or files twice
+    pub fn repeatable(&self) -> bool {
+        matches!(
+            self,
+            Command::Continue
+                | Command::Step(_)
+                | Command::Next
+                | Command::Mem(..)
+                | Command::Disasm(..)
+        )
+    }
+}
+
+fn parse_register(name: &str) -> Result<Register, CommandError> {
+    Ok(match name.to_ascii_lowercase().as_str() {
+        "a" => Register::A,
+        "x" => Register::X,
+        "y" => Register::Y,
+        "sp" | "s" => Register::SP,
+        "pc" => Register::PC,
+        "p" | "st" => Register::P,
+        _ => return Err(CommandError::UnknownRegister(name.into())),
+    })
+}
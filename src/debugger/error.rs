@@ -0,0 +1,36 @@
+use crate::assembler::error::ErrorKind;
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub enum CommandError {
+    UnknownCommand(String),
+    MissingArgument(&'static str),
+    UnexpectedArgument(String),
+    UnknownRegister(String),
+    /// an argument failed to parse or evaluate
+    Expression(ErrorKind),
+    ValueOutOfRange(i64),
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownCommand(command) => {
+                write!(f, "Unknown command {}, try help", command)
+            }
+            Self::MissingArgument(argument) => write!(f, "Missing {}", argument),
+            Self::UnexpectedArgument(argument) => write!(f, "Unexpected argument {}", argument),
+            Self::UnknownRegister(register) => write!(f, "Unknown register {}", register),
+            Self::Expression(kind) => write!(f, "{}", kind),
+            Self::ValueOutOfRange(value) => write!(f, "Value {} is out of range", value),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl From<ErrorKind> for CommandError {
+    fn from(kind: ErrorKind) -> Self {
+        Self::Expression(kind)
+    }
+}
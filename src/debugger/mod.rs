@@ -0,0 +1,204 @@
+use self::watch::{Access, Watched};
+use crate::{
+    cpu::{
+        addressable_bus::DataBus, error::CpuError, instruction::InstructionType, tick::Tick, Cpu,
+    },
+    disassembler::decode,
+    symbols::Symbols,
+};
+use std::collections::BTreeSet;
+
+const JSR: u8 = 0x20;
+
+pub mod command;
+pub mod error;
+pub mod repl;
+pub mod watch;
+
+/// Why execution stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    /// the command ran to completion
+    Done,
+    Breakpoint(u16),
+    Watchpoint(Access),
+}
+
+/// Where a run is heading, besides breakpoints and watchpoints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    /// a number of instructions
+    Steps(usize),
+    /// the instruction after a JSR, once the subroutine has returned
+    Over { address: u16, stack_pointer: u8 },
+    /// an RTS leaving the subroutine which had `stack_pointer`
+    Return { stack_pointer: u8 },
+    /// nothing but breakpoints and watchpoints
+    None,
+}
+
+impl Goal {
+    /// Whether the goal is reached once `tick` executed
+    pub fn reached<T: DataBus>(&mut self, cpu: &Cpu<T>, tick: &Tick) -> bool {
+        match self {
+            Goal::Steps(count) => {
+                *count = count.saturating_sub(1);
+                *count == 0
+            }
+            Goal::Over {
+                address,
+                stack_pointer,
+            } => cpu.program_counter == *address && cpu.stack_pointer == *stack_pointer,
+            // nested subroutines return with the stack pointer no higher than it was
+            Goal::Return { stack_pointer } => {
+                tick.instruction.instruction_type == InstructionType::RTS
+                    && cpu.stack_pointer > *stack_pointer
+            }
+            Goal::None => false,
+        }
+    }
+}
+
+/// Runs a cpu an instruction at a time, stopping at breakpoints and watchpoints
+pub struct Debugger<T: DataBus> {
+    pub cpu: Cpu<Watched<T>>,
+    pub breakpoints: BTreeSet<u16>,
+    /// labels shown in place of addresses, which commands can refer to by name
+    pub symbols: Symbols,
+    /// address of the instruction executed last, or which failed to execute
+    pub last: u16,
+}
+
+impl<T: DataBus> Debugger<T> {
+    pub fn new(cpu: Cpu<Watched<T>>) -> Self {
+        Self {
+            last: cpu.program_counter,
+            cpu,
+            breakpoints: BTreeSet::new(),
+            symbols: Symbols::new(),
+        }
+    }
+
+    /// Executes `count` instructions, or fewer when hitting a breakpoint or watchpoint
+    pub fn step(&mut self, count: usize) -> Result<Stop, CpuError> {
+        self.run(Goal::Steps(count.max(1)))
+    }
+
+    /// Runs until a breakpoint or watchpoint is hit
+    pub fn cont(&mut self) -> Result<Stop, CpuError> {
+        self.run(Goal::None)
+    }
+
+    /// Steps, running whole subroutines when stepping over a JSR
+    pub fn step_over(&mut self) -> Result<Stop, CpuError> {
+        self.run(self.over())
+    }
+
+    /// Runs until the current subroutine returns
+    pub fn finish(&mut self) -> Result<Stop, CpuError> {
+        self.run(self.out())
+    }
+
+    /// Addresses of the JSR instructions which called the subroutines being executed,
+    /// innermost first. Found by looking for return addresses on the stack, so anything
+    /// else pushed that happens to look like one shows up too
+    pub fn backtrace(&self) -> Vec<u16> {
+        let bus = &self.cpu.bus;
+        let mut calls = Vec::new();
+        let mut offset = self.cpu.stack_pointer as u16 + 1;
+
+        while offset < 0xFF {
+            let ll = bus.peek(0x0100 + offset);
+            let hh = bus.peek(0x0100 + offset + 1);
+            // JSR pushes the address of its own last byte
+            let call = u16::from_le_bytes([ll, hh]).wrapping_sub(2);
+
+            if bus.peek(call) == JSR {
+                calls.push(call);
+                offset += 2;
+            } else {
+                offset += 1;
+            }
+        }
+
+        calls
+    }
+
+    /// The goal of stepping over the current instruction
+    pub fn over(&self) -> Goal {
+        let line = decode(
+            &self.cpu.bus,
+            self.cpu.program_counter,
+            self.cpu.variant,
+            self.cpu.undocumented_opcodes,
+        );
+
+        match line.instruction {
+            Some(instruction) if instruction.instruction_type == InstructionType::JSR => {
+                Goal::Over {
+                    address: self.cpu.program_counter.wrapping_add(3),
+                    stack_pointer: self.cpu.stack_pointer,
+                }
+            }
+            _ => Goal::Steps(1),
+        }
+    }
+
+    /// The goal of returning from the current subroutine
+    pub fn out(&self) -> Goal {
+        Goal::Return {
+            stack_pointer: self.cpu.stack_pointer,
+        }
+    }
+
+    fn run(&mut self, mut goal: Goal) -> Result<Stop, CpuError> {
+        self.run_for(&mut goal, usize::MAX)
+            .map(|stop| stop.unwrap_or(Stop::Done))
+    }
+
+    /// Runs towards `goal` for at most `limit` instructions, so that front-ends can
+    /// keep responsive. Returns `None` when the limit is hit first, and `goal` keeps
+    /// track of the progress for the next call
+    pub fn run_for(&mut self, goal: &mut Goal, limit: usize) -> Result<Option<Stop>, CpuError> {
+        let mut left = limit.max(1);
+        let mut reached = false;
+
+        let stop = self.run_until(|cpu, tick| {
+            reached = goal.reached(cpu, tick);
+            left -= 1;
+
+            reached || left == 0
+        })?;
+
+        Ok(match stop {
+            Stop::Done if !reached => None,
+            stop => Some(stop),
+        })
+    }
+
+    /// Ticks until `done` holds after an instruction, or a breakpoint or watchpoint is hit.
+    /// The current instruction always executes, even if there's a breakpoint on it
+    pub fn run_until(
+        &mut self,
+        mut done: impl FnMut(&Cpu<Watched<T>>, &Tick) -> bool,
+    ) -> Result<Stop, CpuError> {
+        self.cpu.bus.take_hit();
+
+        loop {
+            self.last = self.cpu.program_counter;
+            let tick = self.cpu.tick()?;
+
+            if let Some(access) = self.cpu.bus.take_hit() {
+                return Ok(Stop::Watchpoint(access));
+            }
+
+            if done(&self.cpu, &tick) {
+                return Ok(Stop::Done);
+            }
+
+            if self.breakpoints.contains(&self.cpu.program_counter) {
+                return Ok(Stop::Breakpoint(self.cpu.program_counter));
+            }
+        }
+    }
+}
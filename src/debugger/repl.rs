@@ -0,0 +1,307 @@
+use super::{
+    command::{Command, Register},
+    error::CommandError,
+    watch::Watchpoint,
+    Debugger, Stop,
+};
+use crate::{
+    assembler::expression::{Context, Expr},
+    cpu::addressable_bus::DataBus,
+    disassembler::decode,
+    savestate::{SaveState, Snapshot},
+    trace,
+};
+use std::{
+    io::{self, BufRead, Write},
+    path::Path,
+};
+
+const HELP: &str = "\
+break [addr]          set a breakpoint, or list breakpoints and watchpoints
+delete [addr]         delete a breakpoint, or all of them and every watchpoint
+watch addr [len]      stop when the cpu writes to len bytes from addr
+rwatch addr [len]     stop when the cpu reads from them
+awatch addr [len]     stop when the cpu reads from or writes to them
+continue              run until a breakpoint or watchpoint
+step [n]              execute n instructions, 1 by default
+next                  step, running over subroutine calls
+finish                run until the current subroutine returns
+regs                  show the registers
+set reg=value         set a, x, y, sp, pc or p
+mem addr [len]        dump len bytes of memory, 64 by default
+poke addr byte...     write bytes to memory
+disasm [addr] [n]     disassemble n instructions from addr, the pc by default
+save file             save the state of the cpu and memory to a file
+load file             restore the state saved in a file
+quit                  leave the debugger
+
+Values are expressions, without spaces: $ for hex, % for binary, * for the pc.
+An empty line repeats the previous continue, step, next, mem or disasm.";
+
+/// Reads commands from `input` until it ends or `quit` is entered, writing to `output`
+pub fn run<T: DataBus + Snapshot>(
+    debugger: &mut Debugger<T>,
+    mut input: impl BufRead,
+    mut output: impl Write,
+) -> io::Result<()> {
+    let mut last: Option<Command> = None;
+
+    writeln!(
+        output,
+        "{}",
+        trace::line_with(&debugger.cpu, &debugger.symbols)
+    )?;
+
+    loop {
+        write!(output, "(6502) ")?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return writeln!(output);
+        }
+
+        let command = match line.trim() {
+            "" => match last.take() {
+                Some(command) => command,
+                None => continue,
+            },
+            line => match Command::parse(line) {
+                Ok(command) => command,
+                Err(err) => {
+                    writeln!(output, "{}", err)?;
+                    continue;
+                }
+            },
+        };
+
+        if let Command::Quit = command {
+            return Ok(());
+        }
+
+        if let Err(err) = execute(debugger, &command, &mut output) {
+            writeln!(output, "{}", err)?;
+        }
+
+        last = Some(command).filter(Command::repeatable);
+    }
+}
+
+/// Executes a single command, reporting to `output`
+pub fn execute<T: DataBus + Snapshot>(
+    debugger: &mut Debugger<T>,
+    command: &Command,
+    output: &mut impl Write,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let symbols = &debugger.symbols;
+    let context = Context {
+        symbols: symbols.values(),
+        program_counter: debugger.cpu.program_counter as i64,
+    };
+
+    let eval = |expr: &Expr, max: i64| -> Result<i64, CommandError> {
+        match expr.eval(&context)? {
+            value if (0..=max).contains(&value) => Ok(value),
+            value => Err(CommandError::ValueOutOfRange(value)),
+        }
+    };
+    let address = |expr: &Expr| eval(expr, 0xFFFF).map(|x| x as u16);
+    let byte = |expr: &Expr| eval(expr, 0xFF).map(|x| x as u8);
+
+    let stop = match command {
+        Command::Break(None) => {
+            for address in &debugger.breakpoints {
+                writeln!(output, "Breakpoint at {}", symbols.format(*address))?;
+            }
+            for watchpoint in &debugger.cpu.bus.watchpoints {
+                let access = match (watchpoint.read, watchpoint.write) {
+                    (true, true) => "read/write",
+                    (true, false) => "read",
+                    _ => "write",
+                };
+
+                writeln!(
+                    output,
+                    "Watchpoint on {}-{} ({})",
+                    symbols.format(*watchpoint.range.start()),
+                    symbols.format(*watchpoint.range.end()),
+                    access
+                )?;
+            }
+
+            return Ok(());
+        }
+        Command::Break(Some(expr)) => {
+            let address = address(expr)?;
+            debugger.breakpoints.insert(address);
+            writeln!(output, "Breakpoint at {}", symbols.format(address))?;
+
+            return Ok(());
+        }
+        Command::Delete(None) => {
+            debugger.breakpoints.clear();
+            debugger.cpu.bus.watchpoints.clear();
+
+            return Ok(());
+        }
+        Command::Delete(Some(expr)) => {
+            let address = address(expr)?;
+            if !debugger.breakpoints.remove(&address) {
+                writeln!(output, "No breakpoint at {}", symbols.format(address))?;
+            }
+
+            return Ok(());
+        }
+        Command::Watch {
+            address: start,
+            len,
+            read,
+            write,
+        } => {
+            let start = address(start)?;
+            let len = len.as_ref().map(|len| eval(len, 0x10000)).transpose()?;
+            let end = start.saturating_add((len.unwrap_or(1).max(1) - 1) as u16);
+
+            debugger.cpu.bus.watchpoints.push(Watchpoint {
+                range: start..=end,
+                read: *read,
+                write: *write,
+            });
+
+            return Ok(());
+        }
+
+        Command::Continue => debugger.cont(),
+        Command::Step(count) => {
+            let count = count
+                .as_ref()
+                .map(|count| eval(count, i64::MAX))
+                .transpose()?;
+            debugger.step(count.unwrap_or(1) as usize)
+        }
+        Command::Next => debugger.step_over(),
+        Command::Finish => debugger.finish(),
+
+        Command::Regs => {
+            write!(output, "{}", debugger.cpu.display_with(symbols))?;
+
+            return Ok(());
+        }
+        Command::Set(register, value) => {
+            let cpu = &mut debugger.cpu;
+
+            match register {
+                Register::A => cpu.accumulator = byte(value)?,
+                Register::X => cpu.x_register = byte(value)?,
+                Register::Y => cpu.y_register = byte(value)?,
+                Register::SP => cpu.stack_pointer = byte(value)?,
+                Register::PC => cpu.program_counter = address(value)?,
+                Register::P => cpu.processor_status = byte(value)?.into(),
+            }
+
+            return Ok(());
+        }
+        Command::Mem(start, len) => {
+            let start = address(start)?;
+            let len = len.as_ref().map(|len| eval(len, 0x10000)).transpose()?;
+            let bus = &debugger.cpu.bus;
+
+            for row in (0..len.unwrap_or(64)).step_by(16) {
+                let row_address = start.wrapping_add(row as u16);
+                let bytes: Vec<String> = (row..(row + 16).min(len.unwrap_or(64)))
+                    .map(|i| format!("{:02X}", bus.peek(start.wrapping_add(i as u16))))
+                    .collect();
+
+                writeln!(output, "{:04X}  {}", row_address, bytes.join(" "))?;
+            }
+
+            return Ok(());
+        }
+        Command::Poke(start, bytes) => {
+            let start = address(start)?;
+
+            for (i, value) in bytes.iter().enumerate() {
+                let value = byte(value)?;
+                // straight to memory, so that watchpoints don't trigger
+                debugger
+                    .cpu
+                    .bus
+                    .inner
+                    .set(start.wrapping_add(i as u16), value);
+            }
+
+            return Ok(());
+        }
+        Command::Disasm(start, count) => {
+            let mut address = match start {
+                Some(start) => address(start)?,
+                None => debugger.cpu.program_counter,
+            };
+            let count = count
+                .as_ref()
+                .map(|count| eval(count, 0xFFFF))
+                .transpose()?;
+
+            for _ in 0..count.unwrap_or(10) {
+                let cpu = &debugger.cpu;
+                let line = decode(&cpu.bus, address, cpu.variant, cpu.undocumented_opcodes);
+
+                if let Some(label) = debugger.symbols.label(address) {
+                    writeln!(output, "{}:", label)?;
+                }
+                writeln!(output, "{}", line.display_with(&debugger.symbols))?;
+                address = address.wrapping_add(line.bytes.len() as u16);
+            }
+
+            return Ok(());
+        }
+
+        Command::Save(path) => {
+            debugger.cpu.save_state().save(Path::new(path))?;
+
+            return Ok(());
+        }
+        Command::Load(path) => {
+            let state = SaveState::load(Path::new(path))?;
+            debugger.cpu.restore_state(&state)?;
+            debugger.last = debugger.cpu.program_counter;
+
+            writeln!(output, "{}", trace::line_with(&debugger.cpu, symbols))?;
+
+            return Ok(());
+        }
+
+        Command::Help => {
+            writeln!(output, "{}", HELP)?;
+
+            return Ok(());
+        }
+        Command::Quit => return Ok(()),
+    };
+
+    let symbols = &debugger.symbols;
+
+    match stop {
+        Ok(Stop::Done) => (),
+        Ok(Stop::Breakpoint(address)) => {
+            writeln!(output, "Breakpoint at {}", symbols.format(address))?
+        }
+        Ok(Stop::Watchpoint(access)) if access.write => writeln!(
+            output,
+            "Watchpoint: wrote ${:02X} to {}",
+            access.value,
+            symbols.format(access.address)
+        )?,
+        Ok(Stop::Watchpoint(access)) => writeln!(
+            output,
+            "Watchpoint: read ${:02X} from {}",
+            access.value,
+            symbols.format(access.address)
+        )?,
+        Err(err) => writeln!(output, "{} at {}", err, symbols.format(debugger.last))?,
+    }
+
+    writeln!(output, "{}", trace::line_with(&debugger.cpu, symbols))?;
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use crate::{
+    cpu::addressable_bus::DataBus,
+    savestate::{error::SaveStateError, SaveState, Snapshot},
+};
+use std::ops::RangeInclusive;
+
+/// Stops execution when the cpu reads or writes within `range`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub range: RangeInclusive<u16>,
+    pub read: bool,
+    pub write: bool,
+}
+
+/// A memory access which triggered a watchpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Access {
+    pub address: u16,
+    pub value: u8,
+    pub write: bool,
+}
+
+/// A [`DataBus`] wrapper noting the accesses of the cpu that fall within its watchpoints.
+/// Every read counts, opcode and operand fetches included. Peeks never trigger
+pub struct Watched<T: DataBus> {
+    pub inner: T,
+    pub watchpoints: Vec<Watchpoint>,
+    hit: Option<Access>,
+}
+
+impl<T: DataBus> Watched<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            watchpoints: Vec::new(),
+            hit: None,
+        }
+    }
+
+    /// The first access which triggered a watchpoint since the last call
+    pub fn take_hit(&mut self) -> Option<Access> {
+        self.hit.take()
+    }
+
+    fn check(&mut self, address: u16, value: u8, write: bool) {
+        if self.hit.is_some() {
+            return;
+        }
+
+        let watched = self.watchpoints.iter().any(|watchpoint| {
+            watchpoint.range.contains(&address)
+                && if write {
+                    watchpoint.write
+                } else {
+                    watchpoint.read
+                }
+        });
+
+        if watched {
+            self.hit = Some(Access {
+                address,
+                value,
+                write,
+            });
+        }
+    }
+}
+
+impl<T: DataBus> DataBus for Watched<T> {
+    fn peek(&self, addr: u16) -> u8 {
+        self.inner.peek(addr)
+    }
+
+    fn set(&mut self, addr: u16, x: u8) {
+        self.inner.set(addr, x);
+        self.check(addr, x, true);
+    }
+
+    fn read(&mut self, addr: u16) -> u8 {
+        let x = self.inner.read(addr);
+        self.check(addr, x, false);
+
+        x
+    }
+
+    fn sync(&mut self, cycles: u64) {
+        self.inner.sync(cycles);
+    }
+}
+
+impl<T: DataBus + Snapshot> Snapshot for Watched<T> {
+    fn save(&self, state: &mut SaveState) {
+        self.inner.save(state);
+    }
+
+    fn restore(&mut self, state: &SaveState) -> Result<(), SaveStateError> {
+        self.inner.restore(state)
+    }
+}
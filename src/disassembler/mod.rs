@@ -0,0 +1,120 @@
+use crate::{
+    cpu::{addressable_bus::DataBus, instruction::Instruction, variant::Variant},
+    symbols::Symbols,
+};
+use std::fmt::{self, Display, Formatter};
+
+/// A decoded instruction together with where it was found.
+/// Bytes which don't decode to any instruction are kept as data
+#[derive(Debug, Clone)]
+pub struct Line {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    pub instruction: Option<Instruction>,
+}
+
+/// Formats as an address, bytes and mnemonic listing:
+///
+/// ```text
+/// 8000  A9 01     LDA #$01
+/// 8002  D0 FC     BNE $8000
+/// 8004  02        .byt $02
+/// ```
+impl Display for Line {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, None)
+    }
+}
+
+impl Line {
+    /// Displays the line with its operands named after `symbols`, as in `BNE loop`
+    pub fn display_with<'a>(&'a self, symbols: &'a Symbols) -> impl Display + 'a {
+        DisplayWith {
+            line: self,
+            symbols,
+        }
+    }
+
+    /// The mnemonic and operand alone, named after `symbols`, as in `BNE loop`
+    pub fn mnemonic_with(&self, symbols: &Symbols) -> String {
+        match &self.instruction {
+            Some(instruction) => instruction.display_with(self.address, symbols).to_string(),
+            None => format!(".byt ${:02X}", self.bytes[0]),
+        }
+    }
+
+    fn fmt_with(&self, f: &mut Formatter<'_>, symbols: Option<&Symbols>) -> fmt::Result {
+        let bytes: Vec<String> = self.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+
+        write!(f, "{:04X}  {:<8}  ", self.address, bytes.join(" "))?;
+
+        match (&self.instruction, symbols) {
+            (Some(instruction), Some(symbols)) => {
+                write!(f, "{}", instruction.display_with(self.address, symbols))
+            }
+            (Some(instruction), None) => write!(f, "{}", instruction.display_at(self.address)),
+            (None, _) => write!(f, ".byt ${:02X}", self.bytes[0]),
+        }
+    }
+}
+
+struct DisplayWith<'a> {
+    line: &'a Line,
+    symbols: &'a Symbols,
+}
+
+impl Display for DisplayWith<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.line.fmt_with(f, Some(self.symbols))
+    }
+}
+
+/// Disassembles the documented NMOS opcodes found from `start` up to `end`, included.
+/// Memory is only peeked at, so devices on the bus aren't disturbed
+pub fn disassemble(bus: &impl DataBus, start: u16, end: u16) -> Vec<Line> {
+    disassemble_variant(bus, start, end, Variant::Nmos6502, false)
+}
+
+/// Like [`disassemble`], decoding the opcodes `variant` understands
+pub fn disassemble_variant(
+    bus: &impl DataBus,
+    start: u16,
+    end: u16,
+    variant: Variant,
+    undocumented: bool,
+) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut address = start as u32;
+
+    while address <= end as u32 {
+        let line = decode(bus, address as u16, variant, undocumented);
+
+        address += line.bytes.len() as u32;
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Decodes the single instruction at `address`
+pub fn decode(bus: &impl DataBus, address: u16, variant: Variant, undocumented: bool) -> Line {
+    let opcode = bus.peek(address);
+    let mut bytes = vec![opcode];
+
+    let mut next = address;
+    let instruction = Instruction::read_variant(variant, undocumented, opcode, || {
+        next = next.wrapping_add(1);
+        bytes.push(bus.peek(next));
+        bytes[bytes.len() - 1]
+    });
+
+    if instruction.is_none() {
+        bytes.truncate(1);
+    }
+
+    Line {
+        address,
+        bytes,
+        instruction,
+    }
+}
@@ -0,0 +1,325 @@
+use self::packet::Connection;
+use crate::{
+    cpu::{addressable_bus::DataBus, Cpu},
+    debugger::{
+        watch::{Watched, Watchpoint},
+        Debugger, Stop,
+    },
+};
+use std::{
+    io,
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+};
+
+pub mod packet;
+
+/// Instructions run between checks for an interrupt from the client
+const CHUNK: usize = 10_000;
+
+/// Register layout reported to the client: A, X, Y, P and SP
+/// are a byte each, followed by the little endian PC
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.cpu6502.core">
+    <reg name="a" bitsize="8" type="uint8"/>
+    <reg name="x" bitsize="8" type="uint8"/>
+    <reg name="y" bitsize="8" type="uint8"/>
+    <reg name="p" bitsize="8" type="uint8"/>
+    <reg name="sp" bitsize="8" type="uint8"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+  </feature>
+</target>
+"#;
+
+// stop signals
+const SIGINT: u8 = 2;
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+
+/// A GDB remote serial protocol server, letting debuggers attach to a cpu over TCP.
+/// Supports register and memory access, breakpoints, watchpoints, stepping and continuing
+pub struct GdbStub<T: DataBus> {
+    pub debugger: Debugger<T>,
+    /// the stop reply for the last time the cpu stopped
+    stop: String,
+}
+
+impl<T: DataBus> GdbStub<T> {
+    pub fn new(cpu: Cpu<T>) -> Self {
+        Self {
+            debugger: Debugger::new(cpu.map_bus(Watched::new)),
+            stop: format!("S{:02x}", SIGTRAP),
+        }
+    }
+
+    /// Waits for a client on `address`, then serves it until it detaches
+    pub fn listen(&mut self, address: impl ToSocketAddrs) -> io::Result<()> {
+        let (stream, _) = TcpListener::bind(address)?.accept()?;
+
+        self.serve(stream)
+    }
+
+    /// Serves a connected client until it detaches, kills the target or hangs up
+    pub fn serve(&mut self, stream: TcpStream) -> io::Result<()> {
+        stream.set_nodelay(true)?;
+        let mut connection = Connection::new(stream)?;
+
+        while let Some(packet) = connection.read_packet()? {
+            let packet = String::from_utf8_lossy(&packet).into_owned();
+
+            let reply = match packet.as_bytes().first() {
+                Some(b'c') | Some(b's') => self.resume(&packet, &mut connection)?,
+                Some(b'D') => {
+                    connection.write_packet(b"OK")?;
+                    return Ok(());
+                }
+                Some(b'k') => return Ok(()),
+                _ => self
+                    .handle(&packet, &mut connection)
+                    .unwrap_or_else(|| "E01".into()),
+            };
+
+            connection.write_packet(reply.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Replies to the packets which don't run the cpu, `None` for malformed ones
+    fn handle(&mut self, packet: &str, connection: &mut Connection) -> Option<String> {
+        let cpu = &mut self.debugger.cpu;
+
+        // empty packets and ones not starting with an ascii letter are unknown
+        let split = packet.get(..1).map(|kind| (kind, &packet[1..]));
+
+        let reply = match split.unwrap_or_default() {
+            ("?", _) => self.stop.clone(),
+            ("g", _) => hex(&registers(cpu)),
+            ("G", data) => {
+                let bytes = unhex(data)?;
+                if bytes.len() != 7 {
+                    return None;
+                }
+
+                for register in 0..=5 {
+                    set_register(cpu, register, &bytes[register..]);
+                }
+                "OK".into()
+            }
+            ("p", register) => {
+                let register = usize::from_str_radix(register, 16).ok()?;
+                let bytes = registers(cpu);
+
+                match register {
+                    0..=4 => hex(&bytes[register..=register]),
+                    5 => hex(&bytes[5..]),
+                    _ => return None,
+                }
+            }
+            ("P", assignment) => {
+                let (register, value) = assignment.split_once('=')?;
+                let register = usize::from_str_radix(register, 16).ok()?;
+                let value = unhex(value)?;
+
+                match (register, value.len()) {
+                    (0..=4, 1) | (5, 2) => set_register(cpu, register, &value),
+                    _ => return None,
+                }
+                "OK".into()
+            }
+            ("m", range) => {
+                let (address, len) = parse_range(range)?;
+                let bytes: Vec<u8> = (0..len.min(0x800))
+                    .map(|i| cpu.bus.peek(address.wrapping_add(i as u16)))
+                    .collect();
+
+                hex(&bytes)
+            }
+            ("M", write) => {
+                let (range, data) = write.split_once(':')?;
+                let (address, len) = parse_range(range)?;
+                let bytes = unhex(data)?;
+                if bytes.len() != len {
+                    return None;
+                }
+
+                for (i, &byte) in bytes.iter().enumerate() {
+                    // straight to memory, so that watchpoints don't trigger
+                    cpu.bus.inner.set(address.wrapping_add(i as u16), byte);
+                }
+                "OK".into()
+            }
+            ("Z", point) | ("z", point) => {
+                let insert = packet.starts_with('Z');
+                let (kind, range) = point.split_once(',')?;
+                let (address, len) = parse_range(range)?;
+
+                self.point(kind, address, len, insert)?
+            }
+            ("H", _) => "OK".into(),
+            ("q", query) => self.query(query)?,
+            ("Q", "StartNoAckMode") => {
+                connection.ack = false;
+                "OK".into()
+            }
+            _ => String::new(),
+        };
+
+        Some(reply)
+    }
+
+    fn query(&self, query: &str) -> Option<String> {
+        if let Some(annex) = query.strip_prefix("Xfer:features:read:target.xml:") {
+            let (offset, len) = annex.split_once(',')?;
+            let offset = usize::from_str_radix(offset, 16).ok()?;
+            let len = usize::from_str_radix(len, 16).ok()?;
+
+            let rest = TARGET_XML.get(offset.min(TARGET_XML.len())..)?;
+            return Some(match rest.len() > len {
+                true => format!("m{}", &rest[..len]),
+                false => format!("l{}", rest),
+            });
+        }
+
+        Some(match query.split(':').next()? {
+            "Supported" => "PacketSize=1000;qXfer:features:read+;QStartNoAckMode+".into(),
+            "Attached" => "1".into(),
+            "C" => "QC1".into(),
+            "fThreadInfo" => "m1".into(),
+            "sThreadInfo" => "l".into(),
+            _ => String::new(),
+        })
+    }
+
+    /// Inserts or removes a breakpoint (types 0 and 1) or a watchpoint (types 2 to 4)
+    fn point(&mut self, kind: &str, address: u16, len: usize, insert: bool) -> Option<String> {
+        let (read, write) = match kind {
+            "0" | "1" => {
+                match insert {
+                    true => self.debugger.breakpoints.insert(address),
+                    false => self.debugger.breakpoints.remove(&address),
+                };
+
+                return Some("OK".into());
+            }
+            "2" => (false, true),
+            "3" => (true, false),
+            "4" => (true, true),
+            _ => return Some(String::new()),
+        };
+
+        let watchpoint = Watchpoint {
+            range: address..=address.saturating_add((len.max(1) - 1) as u16),
+            read,
+            write,
+        };
+        let watchpoints = &mut self.debugger.cpu.bus.watchpoints;
+
+        match insert {
+            true => watchpoints.push(watchpoint),
+            false => watchpoints.retain(|other| *other != watchpoint),
+        }
+
+        Some("OK".into())
+    }
+
+    /// Steps or continues, optionally from a new address, and returns the stop reply
+    fn resume(&mut self, packet: &str, connection: &mut Connection) -> io::Result<String> {
+        let (command, address) = packet.split_at(1);
+
+        if let Ok(address) = u16::from_str_radix(address, 16) {
+            self.debugger.cpu.program_counter = address;
+        }
+
+        let stop = loop {
+            let stop = match command {
+                "s" => break self.debugger.step(1),
+                _ => self.debugger.step(CHUNK),
+            };
+
+            match stop {
+                Ok(Stop::Done) if connection.interrupted()? => break Ok(Stop::Done),
+                Ok(Stop::Done) => (),
+                stop => break stop,
+            }
+        };
+
+        let watchpoints = &self.debugger.cpu.bus.watchpoints;
+
+        self.stop = match stop {
+            Ok(Stop::Done) if command == "s" => format!("S{:02x}", SIGTRAP),
+            Ok(Stop::Done) => format!("S{:02x}", SIGINT),
+            Ok(Stop::Breakpoint(_)) => format!("S{:02x}", SIGTRAP),
+            Ok(Stop::Watchpoint(access)) => {
+                let both = watchpoints.iter().any(|watchpoint| {
+                    watchpoint.range.contains(&access.address)
+                        && watchpoint.read
+                        && watchpoint.write
+                });
+                let kind = match (both, access.write) {
+                    (true, _) => "awatch",
+                    (false, true) => "watch",
+                    (false, false) => "rwatch",
+                };
+
+                format!("T{:02x}{}:{:04x};", SIGTRAP, kind, access.address)
+            }
+            Err(_) => format!("S{:02x}", SIGILL),
+        };
+
+        Ok(self.stop.clone())
+    }
+}
+
+fn registers<T: DataBus>(cpu: &Cpu<T>) -> [u8; 7] {
+    let [ll, hh] = cpu.program_counter.to_le_bytes();
+
+    [
+        cpu.accumulator,
+        cpu.x_register,
+        cpu.y_register,
+        cpu.processor_status.0,
+        cpu.stack_pointer,
+        ll,
+        hh,
+    ]
+}
+
+/// Sets register number `register`, PC taking both bytes of `value`
+fn set_register<T: DataBus>(cpu: &mut Cpu<T>, register: usize, value: &[u8]) {
+    match register {
+        0 => cpu.accumulator = value[0],
+        1 => cpu.x_register = value[0],
+        2 => cpu.y_register = value[0],
+        3 => cpu.processor_status = value[0].into(),
+        4 => cpu.stack_pointer = value[0],
+        5 => cpu.program_counter = u16::from_le_bytes([value[0], value[1]]),
+        _ => (),
+    }
+}
+
+/// `address,length` in hex
+fn parse_range(range: &str) -> Option<(u16, usize)> {
+    let (address, len) = range.split_once(',')?;
+
+    Some((
+        u16::from_str_radix(address, 16).ok()?,
+        usize::from_str_radix(len, 16).ok()?,
+    ))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 == 1 {
+        return None;
+    }
+
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
@@ -0,0 +1,99 @@
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind, Read, Write},
+    net::TcpStream,
+};
+
+/// Sent by the client to interrupt a running target
+pub const INTERRUPT: u8 = 0x03;
+
+/// A client connection, speaking `$data#checksum` packets
+pub struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    /// whether packets still get acknowledged, until the client asks otherwise
+    pub ack: bool,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        Ok(Self {
+            writer: stream.try_clone()?,
+            reader: BufReader::new(stream),
+            ack: true,
+        })
+    }
+
+    /// The next packet with a valid checksum, or `None` once the client hangs up.
+    /// Acknowledgements and stray interrupts are skipped
+    pub fn read_packet(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            let mut start = [0];
+            if self.reader.read(&mut start)? == 0 {
+                return Ok(None);
+            }
+
+            if start[0] != b'$' {
+                continue;
+            }
+
+            let mut data = Vec::new();
+            self.reader.read_until(b'#', &mut data)?;
+            if data.pop() != Some(b'#') {
+                return Ok(None);
+            }
+
+            let mut checksum = [0; 2];
+            self.reader.read_exact(&mut checksum)?;
+
+            let valid = std::str::from_utf8(&checksum)
+                .ok()
+                .and_then(|checksum| u8::from_str_radix(checksum, 16).ok())
+                == Some(self::checksum(&data));
+
+            if self.ack {
+                self.writer.write_all(if valid { b"+" } else { b"-" })?;
+            }
+
+            if valid {
+                return Ok(Some(data));
+            }
+        }
+    }
+
+    pub fn write_packet(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut packet = Vec::with_capacity(data.len() + 4);
+        packet.push(b'$');
+        packet.extend_from_slice(data);
+        packet.extend_from_slice(format!("#{:02x}", checksum(data)).as_bytes());
+
+        self.writer.write_all(&packet)?;
+        self.writer.flush()
+    }
+
+    /// Whether the client sent an interrupt, without waiting for one
+    pub fn interrupted(&mut self) -> io::Result<bool> {
+        if self.reader.buffer().is_empty() {
+            self.reader.get_ref().set_nonblocking(true)?;
+            let filled = self.reader.fill_buf().map(|buffer| buffer.len());
+            self.reader.get_ref().set_nonblocking(false)?;
+
+            match filled {
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(false),
+                Err(err) => return Err(err),
+                Ok(_) => (),
+            }
+        }
+
+        match self.reader.buffer().first() {
+            Some(&INTERRUPT) => {
+                self.reader.consume(1);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0, |sum, &byte| sum.wrapping_add(byte))
+}
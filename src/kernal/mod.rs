@@ -0,0 +1,131 @@
+use crate::cpu::{addressable_bus::DataBus, status::StatusFlag, Cpu};
+use std::io::{self, Read, Write};
+
+// C64 KERNAL entry points
+pub const CHKIN: u16 = 0xFFC6;
+pub const CHKOUT: u16 = 0xFFC9;
+pub const CLRCHN: u16 = 0xFFCC;
+pub const CHRIN: u16 = 0xFFCF;
+pub const CHROUT: u16 = 0xFFD2;
+pub const GETIN: u16 = 0xFFE4;
+
+/// BASIC warm start, where programs started by `SYS` return to
+pub const READY: u16 = 0xA474;
+
+/// Starts the cpu at `address` the way BASIC's `SYS` does,
+/// so that returning from the program is trapped as an exit
+pub fn sys<T: DataBus>(cpu: &mut Cpu<T>, address: u16) {
+    cpu.stack_push_word(READY.wrapping_sub(1));
+    cpu.program_counter = address;
+}
+
+/// What happened to a trapped call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trap {
+    /// the routine was serviced, and the cpu is back to its caller
+    Serviced,
+    /// the program returned to BASIC
+    Exit,
+}
+
+/// Headless stand-in for the I/O routines of the C64 KERNAL, mapping the screen
+/// and keyboard to `output` and `input`. Check for traps before each tick:
+///
+/// ```no_run
+/// # use cpu6502::{cpu::Cpu, kernal::{Kernal, Trap}, stack_memory::StackMemory};
+/// # let mut cpu = Cpu::load_memory(StackMemory::new());
+/// let mut kernal = Kernal::stdio();
+///
+/// loop {
+///     match kernal.trap(&mut cpu).unwrap() {
+///         Some(Trap::Exit) => break,
+///         Some(Trap::Serviced) => (),
+///         None => {
+///             cpu.tick().unwrap();
+///         }
+///     }
+/// }
+/// ```
+pub struct Kernal<R: Read, W: Write> {
+    pub input: R,
+    pub output: W,
+}
+
+impl Kernal<io::Stdin, io::Stdout> {
+    pub fn stdio() -> Self {
+        Self::new(io::stdin(), io::stdout())
+    }
+}
+
+impl<R: Read, W: Write> Kernal<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Self { input, output }
+    }
+
+    /// Services the KERNAL routine the cpu is about to enter, if it's one of those
+    /// being trapped, and returns to its caller as RTS would. Takes no cycles
+    pub fn trap<T: DataBus>(&mut self, cpu: &mut Cpu<T>) -> io::Result<Option<Trap>> {
+        match cpu.program_counter {
+            READY => return Ok(Some(Trap::Exit)),
+            CHROUT => {
+                if let Some(c) = petscii_to_ascii(cpu.accumulator) {
+                    self.output.write_all(&[c])?;
+                    self.output.flush()?;
+                }
+            }
+            CHRIN => {
+                // end of input reads as the end of a line
+                let c = self.read()?.unwrap_or(b'\n');
+                set_accumulator(cpu, ascii_to_petscii(c));
+            }
+            GETIN => {
+                let c = self.read()?.map_or(0, ascii_to_petscii);
+                set_accumulator(cpu, c);
+            }
+            CHKIN | CHKOUT | CLRCHN => (),
+            _ => return Ok(None),
+        }
+
+        // success
+        cpu.processor_status.set_flag(StatusFlag::Carry, false);
+        cpu.program_counter = cpu.stack_pop_word().wrapping_add(1);
+
+        Ok(Some(Trap::Serviced))
+    }
+
+    fn read(&mut self) -> io::Result<Option<u8>> {
+        let mut c = [0];
+
+        match self.input.read(&mut c)? {
+            0 => Ok(None),
+            _ => Ok(Some(c[0])),
+        }
+    }
+}
+
+fn set_accumulator<T: DataBus>(cpu: &mut Cpu<T>, x: u8) {
+    cpu.accumulator = x;
+    cpu.processor_status.set_flag(StatusFlag::Zero, x == 0);
+    cpu.processor_status
+        .set_flag(StatusFlag::Negative, x & 0x80 != 0);
+}
+
+/// Printable PETSCII, in the default uppercase character set
+pub fn petscii_to_ascii(c: u8) -> Option<u8> {
+    match c {
+        0x0D => Some(b'\n'),
+        0x20..=0x5F => Some(c),
+        // shifted letters, as the lowercase character set shows them
+        0xC1..=0xDA => Some(c - 0x80),
+        _ => None,
+    }
+}
+
+/// Keys as the keyboard types them, unshifted letters being uppercase
+pub fn ascii_to_petscii(c: u8) -> u8 {
+    match c {
+        b'\n' => 0x0D,
+        b'a'..=b'z' => c.to_ascii_uppercase(),
+        c => c,
+    }
+}
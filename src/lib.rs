@@ -1,2 +1,13 @@
+pub mod assembler;
+pub mod bus;
 pub mod cpu;
+pub mod dap;
+pub mod debugger;
+pub mod disassembler;
+pub mod gdb;
+pub mod kernal;
+pub mod loader;
+pub mod savestate;
 pub mod stack_memory;
+pub mod symbols;
+pub mod trace;
@@ -0,0 +1,94 @@
+use crate::assembler::error::AssemblerError;
+use std::{fmt::Display, io};
+
+#[derive(Debug)]
+pub enum LoadError {
+    /// the file ends before a structure it announces
+    Truncated,
+    /// the file doesn't start with the marker of its format
+    BadMagic,
+    InvalidSegment(u8),
+    /// relocation types which only make sense on the 65816
+    UnsupportedRelocation(u8),
+    /// a relocation entry pointing past the end of its segment
+    InvalidRelocation(usize),
+    UndefinedReference(String),
+
+    // text formats report the line, starting from 1, where things went wrong
+    InvalidRecord {
+        line: usize,
+        reason: &'static str,
+    },
+    UnknownRecordType {
+        line: usize,
+        record_type: u8,
+    },
+    Checksum {
+        line: usize,
+        expected: u8,
+        found: u8,
+    },
+    AddressOutOfRange {
+        line: usize,
+        address: u32,
+    },
+    MissingEndRecord,
+    /// record files have to be text
+    NotText,
+
+    Io(io::Error),
+    Assembler(AssemblerError),
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "File is truncated"),
+            Self::BadMagic => write!(f, "File is not in the expected format"),
+            Self::InvalidSegment(id) => write!(f, "Invalid segment id {}", id),
+            Self::UnsupportedRelocation(kind) => {
+                write!(f, "Unsupported relocation type 0x{:02X}", kind)
+            }
+            Self::InvalidRelocation(offset) => {
+                write!(f, "Relocation at offset {} is out of its segment", offset)
+            }
+            Self::UndefinedReference(name) => write!(f, "Undefined reference to {}", name),
+            Self::InvalidRecord { line, reason } => {
+                write!(f, "line {}: invalid record, {}", line, reason)
+            }
+            Self::UnknownRecordType { line, record_type } => {
+                write!(f, "line {}: unknown record type {}", line, record_type)
+            }
+            Self::Checksum {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: checksum is 0x{:02X}, expected 0x{:02X}",
+                line, found, expected
+            ),
+            Self::AddressOutOfRange { line, address } => {
+                write!(f, "line {}: address 0x{:X} is out of 64K", line, address)
+            }
+            Self::MissingEndRecord => write!(f, "File has no end of file record"),
+            Self::NotText => write!(f, "File is not text"),
+            Self::Io(err) => write!(f, "{}", err),
+            Self::Assembler(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<AssemblerError> for LoadError {
+    fn from(err: AssemblerError) -> Self {
+        Self::Assembler(err)
+    }
+}
@@ -0,0 +1,88 @@
+use super::{error::LoadError, hex_bytes, is_record_text, Program};
+
+/// Whether `bytes` look like an Intel HEX file
+pub fn is_ihex(bytes: &[u8]) -> bool {
+    is_record_text(bytes, |line| line.starts_with(':'))
+}
+
+/// Parses an Intel HEX file. Extended segment and linear addresses are honoured,
+/// as long as the data stays within 64K
+pub fn parse(text: &str) -> Result<Program, LoadError> {
+    let mut program = Program::default();
+    // upper address bits set by records 02 and 04
+    let mut base = 0u32;
+
+    for (i, record) in text.lines().enumerate() {
+        let line = i + 1;
+        let record = record.trim();
+
+        if record.is_empty() {
+            continue;
+        }
+
+        let invalid = |reason| LoadError::InvalidRecord { line, reason };
+
+        let bytes = match record.strip_prefix(':') {
+            Some(digits) => hex_bytes(digits, line)?,
+            None => return Err(invalid("missing start code ':'")),
+        };
+
+        if bytes.len() < 5 {
+            return Err(invalid("too short"));
+        }
+
+        let len = bytes[0] as usize;
+        if bytes.len() != len + 5 {
+            return Err(invalid("length doesn't match the byte count"));
+        }
+
+        let (found, bytes) = bytes.split_last().unwrap();
+        let expected = bytes
+            .iter()
+            .fold(0u8, |sum, &byte| sum.wrapping_add(byte))
+            .wrapping_neg();
+        if *found != expected {
+            return Err(LoadError::Checksum {
+                line,
+                expected,
+                found: *found,
+            });
+        }
+
+        let offset = u16::from_be_bytes([bytes[1], bytes[2]]) as u32;
+        let record_type = bytes[3];
+        let data = &bytes[4..];
+
+        let value = || match data.len() {
+            2 => Ok(u16::from_be_bytes([data[0], data[1]]) as u32),
+            4 => Ok(u32::from_be_bytes([data[0], data[1], data[2], data[3]])),
+            _ => Err(invalid("unexpected data length")),
+        };
+
+        match record_type {
+            0x00 => program.push(line, base + offset, data)?,
+            0x01 => return Ok(program),
+            0x02 => base = value()? << 4,
+            0x04 => base = value()? << 16,
+            0x03 | 0x05 => {
+                // CS:IP for record 03, EIP for record 05
+                let entry = match (record_type, value()?) {
+                    (0x03, cs_ip) => (cs_ip >> 16 << 4) + (cs_ip & 0xFFFF),
+                    (_, eip) => eip,
+                };
+
+                if entry > 0xFFFF {
+                    return Err(LoadError::AddressOutOfRange {
+                        line,
+                        address: entry,
+                    });
+                }
+
+                program.entry = Some(entry as u16);
+            }
+            _ => return Err(LoadError::UnknownRecordType { line, record_type }),
+        }
+    }
+
+    Err(LoadError::MissingEndRecord)
+}
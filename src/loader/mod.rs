@@ -0,0 +1,186 @@
+use self::{error::LoadError, o65::O65};
+use crate::{
+    assembler::assemble_variant,
+    cpu::{addressable_bus::DataBus, variant::Variant},
+    stack_memory::StackMemory,
+};
+use std::path::Path;
+
+pub mod error;
+pub mod ihex;
+pub mod o65;
+pub mod prg;
+pub mod srec;
+
+/// Bytes to be loaded starting from `address`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub address: u16,
+    pub data: Vec<u8>,
+}
+
+/// A program made of possibly sparse blocks, as found in Intel HEX and S-record files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Program {
+    pub blocks: Vec<Block>,
+    /// where execution should start, if the file tells
+    pub entry: Option<u16>,
+}
+
+impl Program {
+    /// Copies every block into `bus`
+    pub fn load_into(&self, bus: &mut impl DataBus) {
+        for block in &self.blocks {
+            for (i, &byte) in block.data.iter().enumerate() {
+                bus.set(block.address.wrapping_add(i as u16), byte);
+            }
+        }
+    }
+
+    /// Adds `data` at `address`, extending the last block when it's contiguous
+    fn push(&mut self, line: usize, address: u32, data: &[u8]) -> Result<(), LoadError> {
+        // records may sit anywhere in a 32 bit space, so the end can overflow too
+        match address.checked_add(data.len() as u32) {
+            Some(end) if end <= 0x10000 => {}
+            end => {
+                return Err(LoadError::AddressOutOfRange {
+                    line,
+                    address: match end {
+                        Some(end) if address <= 0xFFFF => end - 1,
+                        _ => address,
+                    },
+                })
+            }
+        }
+
+        match self.blocks.last_mut() {
+            Some(block) if block.address as usize + block.data.len() == address as usize => {
+                block.data.extend(data)
+            }
+            _ => self.blocks.push(Block {
+                address: address as u16,
+                data: data.to_vec(),
+            }),
+        }
+
+        Ok(())
+    }
+}
+
+/// Loads a program file into memory. Assembly sources (`.s`, `.asm`) are assembled at
+/// their own origin, o65 files are relocated to `offset`, Intel HEX, S-record and PRG files
+/// are loaded where they say and anything else is loaded as is at `offset`. Formats are told
+/// apart by extension, then by content. Returns the entry point of files which have one
+pub fn load_file(
+    path: &Path,
+    offset: u16,
+    variant: Variant,
+    undocumented: bool,
+) -> Result<(StackMemory, Option<u16>), LoadError> {
+    let program = load_program(path, offset, variant, undocumented)?;
+
+    let mut memory = StackMemory::new();
+    program.load_into(&mut memory);
+
+    Ok((memory, program.entry))
+}
+
+/// Reads a program file as [`load_file`] does, keeping the blocks it's made of
+pub fn load_program(
+    path: &Path,
+    offset: u16,
+    variant: Variant,
+    undocumented: bool,
+) -> Result<Program, LoadError> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_lowercase);
+
+    // formats loaded as a single block
+    let block = |address, data| Program {
+        blocks: vec![Block { address, data }],
+        entry: None,
+    };
+
+    if let Some("s") | Some("asm") = extension.as_deref() {
+        let source = std::fs::read_to_string(path)?;
+        let assembly = assemble_variant(&source, variant, undocumented)?;
+
+        return Ok(block(assembly.origin, assembly.image));
+    }
+
+    let file = std::fs::read(path)?;
+    let text = || std::str::from_utf8(&file).map_err(|_| LoadError::NotText);
+
+    Ok(match extension.as_deref() {
+        Some("prg") => prg::parse(&file)?,
+        Some("hex") | Some("ihex") | Some("ihx") => ihex::parse(text()?)?,
+        Some("srec") | Some("mot") | Some("s19") | Some("s28") | Some("s37") => {
+            srec::parse(text()?)?
+        }
+        _ if o65::is_o65(&file) => {
+            let object = O65::parse(&file)?;
+            let layout = object.layout_at(offset);
+
+            let mut memory = StackMemory::new();
+            object.load(&mut memory, layout, |_| None)?;
+
+            // the relocated text and data segments
+            let blocks = [
+                (layout.text, object.text.len()),
+                (layout.data, object.data.len()),
+            ]
+            .iter()
+            .filter(|(_, len)| *len > 0)
+            .map(|&(address, len)| Block {
+                address,
+                data: (0..len)
+                    .map(|i| memory.peek(address.wrapping_add(i as u16)))
+                    .collect(),
+            })
+            .collect();
+
+            Program {
+                blocks,
+                entry: Some(offset),
+            }
+        }
+        _ if ihex::is_ihex(&file) => ihex::parse(text()?)?,
+        _ if srec::is_srec(&file) => srec::parse(text()?)?,
+        _ => {
+            // anything past the end of the address space is dropped
+            let len = file.len().min(0x10000 - offset as usize);
+            block(offset, file[..len].to_vec())
+        }
+    })
+}
+
+/// Decodes the hexadecimal digit pairs of a record
+fn hex_bytes(text: &str, line: usize) -> Result<Vec<u8>, LoadError> {
+    let invalid = |reason| LoadError::InvalidRecord { line, reason };
+
+    if !text.is_ascii() {
+        return Err(invalid("not hexadecimal"));
+    }
+    if text.len() % 2 == 1 {
+        return Err(invalid("odd number of digits"));
+    }
+
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).map_err(|_| invalid("not hexadecimal")))
+        .collect()
+}
+
+/// Whether `bytes` are lines of text all starting with `start`
+fn is_record_text(bytes: &[u8], start: impl Fn(&str) -> bool) -> bool {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => {
+            let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+
+            lines.clone().next().is_some() && lines.all(start)
+        }
+        Err(_) => false,
+    }
+}
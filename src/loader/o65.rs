@@ -0,0 +1,420 @@
+use super::error::LoadError;
+use crate::cpu::addressable_bus::DataBus;
+use std::{collections::BTreeMap, convert::TryFrom};
+
+/// Marker and magic number every o65 file starts with
+pub const MAGIC: [u8; 5] = [0x01, 0x00, b'o', b'6', b'5'];
+
+/// Whether `bytes` look like an o65 file, rather than a raw binary
+pub fn is_o65(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+/// The mode word of the header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mode(pub u16);
+
+impl Mode {
+    pub fn is_65816(&self) -> bool {
+        self.0 & 0x8000 != 0
+    }
+
+    /// Whether segments may only be relocated by whole pages,
+    /// in which case HIGH relocations don't carry their low byte
+    pub fn pagewise(&self) -> bool {
+        self.0 & 0x4000 != 0
+    }
+
+    /// Whether sizes and addresses are 32 bits wide
+    pub fn long(&self) -> bool {
+        self.0 & 0x2000 != 0
+    }
+
+    pub fn is_object(&self) -> bool {
+        self.0 & 0x1000 != 0
+    }
+
+    pub fn simple(&self) -> bool {
+        self.0 & 0x0800 != 0
+    }
+
+    /// Whether another o65 file follows this one
+    pub fn chained(&self) -> bool {
+        self.0 & 0x0400 != 0
+    }
+
+    /// Whether the bss segment must be cleared when loading
+    pub fn bss_zero(&self) -> bool {
+        self.0 & 0x0200 != 0
+    }
+
+    /// 0 for the plain 6502, 1 for the 65C02 and so on
+    pub fn cpu(&self) -> u8 {
+        ((self.0 >> 4) & 0x0F) as u8
+    }
+
+    /// Alignment of the segments, in bytes
+    pub fn align(&self) -> u16 {
+        [1, 2, 4, 256][(self.0 & 0x03) as usize]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment {
+    /// a symbol left for the loader to resolve
+    Undefined,
+    Absolute,
+    Text,
+    Data,
+    Bss,
+    Zeropage,
+}
+
+impl TryFrom<u8> for Segment {
+    type Error = LoadError;
+
+    fn try_from(id: u8) -> Result<Self, Self::Error> {
+        Ok(match id {
+            0 => Segment::Undefined,
+            1 => Segment::Absolute,
+            2 => Segment::Text,
+            3 => Segment::Data,
+            4 => Segment::Bss,
+            5 => Segment::Zeropage,
+            _ => return Err(LoadError::InvalidSegment(id)),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelocationKind {
+    Word,
+    /// high byte of an address, along with its low byte when relocating bytewise
+    High(u8),
+    Low,
+}
+
+#[derive(Debug, Clone)]
+pub struct Relocation {
+    /// offset of the relocated bytes from the start of their segment
+    pub offset: usize,
+    pub kind: RelocationKind,
+    pub segment: Segment,
+    /// index into the undefined references, for undefined segments
+    pub undefined: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Global {
+    pub name: String,
+    pub segment: Segment,
+    pub value: u32,
+}
+
+/// A parsed o65 file, as described by André Fachat's specification
+#[derive(Debug, Clone)]
+pub struct O65 {
+    pub mode: Mode,
+
+    pub text_base: u32,
+    pub text: Vec<u8>,
+    pub data_base: u32,
+    pub data: Vec<u8>,
+    pub bss_base: u32,
+    pub bss_len: u32,
+    pub zeropage_base: u32,
+    pub zeropage_len: u32,
+    pub stack_len: u32,
+
+    /// header options, as type and payload
+    pub options: Vec<(u8, Vec<u8>)>,
+    pub undefined: Vec<String>,
+    pub text_relocations: Vec<Relocation>,
+    pub data_relocations: Vec<Relocation>,
+    pub globals: Vec<Global>,
+}
+
+/// Where each segment goes when loading
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    pub text: u16,
+    pub data: u16,
+    pub bss: u16,
+    pub zeropage: u16,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    long: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], LoadError> {
+        if len > self.bytes.len() {
+            return Err(LoadError::Truncated);
+        }
+
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, LoadError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn word(&mut self) -> Result<u16, LoadError> {
+        let bytes = self.take(2)?;
+
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// A size or address, 16 or 32 bits wide depending on the mode
+    fn size(&mut self) -> Result<u32, LoadError> {
+        if !self.long {
+            return Ok(self.word()? as u32);
+        }
+
+        let bytes = self.take(4)?;
+
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// A null terminated string
+    fn string(&mut self) -> Result<String, LoadError> {
+        let len = self
+            .bytes
+            .iter()
+            .position(|&byte| byte == 0)
+            .ok_or(LoadError::Truncated)?;
+        let string = String::from_utf8_lossy(self.take(len)?).into_owned();
+        self.take(1)?;
+
+        Ok(string)
+    }
+
+    fn relocations(&mut self, pagewise: bool) -> Result<Vec<Relocation>, LoadError> {
+        let mut relocations = Vec::new();
+        // offsets are relative to the previous entry, starting from one byte before the segment
+        let mut position: isize = -1;
+
+        loop {
+            let mut offset = self.byte()?;
+
+            while offset == 0xFF {
+                position += 0xFE;
+                offset = self.byte()?;
+            }
+
+            if offset == 0 {
+                return Ok(relocations);
+            }
+
+            position += offset as isize;
+
+            let typebyte = self.byte()?;
+            let segment = Segment::try_from(typebyte & 0x0F)?;
+
+            let undefined = match segment {
+                Segment::Undefined => Some(self.size()? as usize),
+                _ => None,
+            };
+
+            let kind = match typebyte & 0xF0 {
+                0x80 => RelocationKind::Word,
+                0x40 if pagewise => RelocationKind::High(0),
+                0x40 => RelocationKind::High(self.byte()?),
+                0x20 => RelocationKind::Low,
+                _ => return Err(LoadError::UnsupportedRelocation(typebyte)),
+            };
+
+            relocations.push(Relocation {
+                offset: position as usize,
+                kind,
+                segment,
+                undefined,
+            });
+        }
+    }
+}
+
+impl O65 {
+    /// Parses the first o65 file in `bytes`, ignoring any file chained after it
+    pub fn parse(bytes: &[u8]) -> Result<O65, LoadError> {
+        if !is_o65(bytes) {
+            return Err(LoadError::BadMagic);
+        }
+
+        let mut reader = Reader {
+            bytes: &bytes[MAGIC.len()..],
+            long: false,
+        };
+
+        // version
+        reader.byte()?;
+        let mode = Mode(reader.word()?);
+        reader.long = mode.long();
+
+        let text_base = reader.size()?;
+        let text_len = reader.size()?;
+        let data_base = reader.size()?;
+        let data_len = reader.size()?;
+        let bss_base = reader.size()?;
+        let bss_len = reader.size()?;
+        let zeropage_base = reader.size()?;
+        let zeropage_len = reader.size()?;
+        let stack_len = reader.size()?;
+
+        let mut options = Vec::new();
+        loop {
+            let len = reader.byte()? as usize;
+            if len == 0 {
+                break;
+            }
+
+            let option_type = reader.byte()?;
+            let payload = reader.take(len.saturating_sub(2))?.to_vec();
+            options.push((option_type, payload));
+        }
+
+        let text = reader.take(text_len as usize)?.to_vec();
+        let data = reader.take(data_len as usize)?.to_vec();
+
+        let undefined = (0..reader.size()?)
+            .map(|_| reader.string())
+            .collect::<Result<_, _>>()?;
+
+        let text_relocations = reader.relocations(mode.pagewise())?;
+        let data_relocations = reader.relocations(mode.pagewise())?;
+
+        let globals = (0..reader.size()?)
+            .map(|_| {
+                Ok(Global {
+                    name: reader.string()?,
+                    segment: Segment::try_from(reader.byte()?)?,
+                    value: reader.size()?,
+                })
+            })
+            .collect::<Result<_, LoadError>>()?;
+
+        Ok(O65 {
+            mode,
+            text_base,
+            text,
+            data_base,
+            data,
+            bss_base,
+            bss_len,
+            zeropage_base,
+            zeropage_len,
+            stack_len,
+            options,
+            undefined,
+            text_relocations,
+            data_relocations,
+            globals,
+        })
+    }
+
+    /// Text, data and bss one after the other from `base`,
+    /// keeping page zero where it was assembled for
+    pub fn layout_at(&self, base: u16) -> Layout {
+        let data = base.wrapping_add(self.text.len() as u16);
+
+        Layout {
+            text: base,
+            data,
+            bss: data.wrapping_add(self.data.len() as u16),
+            zeropage: self.zeropage_base as u16,
+        }
+    }
+
+    /// Relocates the text and data segments to `layout` and copies them into `bus`.
+    /// Undefined references are looked up through `resolve`. Returns the exported
+    /// globals, at their relocated addresses
+    pub fn load(
+        &self,
+        bus: &mut impl DataBus,
+        layout: Layout,
+        resolve: impl Fn(&str) -> Option<u16>,
+    ) -> Result<BTreeMap<String, u16>, LoadError> {
+        let delta = |segment: Segment, undefined: Option<usize>| -> Result<u16, LoadError> {
+            let (new, old) = match segment {
+                Segment::Undefined => {
+                    let name = undefined
+                        .and_then(|index| self.undefined.get(index))
+                        .ok_or(LoadError::InvalidSegment(0))?;
+
+                    return resolve(name)
+                        .ok_or_else(|| LoadError::UndefinedReference(name.clone()));
+                }
+                Segment::Absolute => return Ok(0),
+                Segment::Text => (layout.text, self.text_base),
+                Segment::Data => (layout.data, self.data_base),
+                Segment::Bss => (layout.bss, self.bss_base),
+                Segment::Zeropage => (layout.zeropage, self.zeropage_base),
+            };
+
+            Ok(new.wrapping_sub(old as u16))
+        };
+
+        let relocate = |segment: &[u8], relocations: &[Relocation]| -> Result<Vec<u8>, LoadError> {
+            let mut segment = segment.to_vec();
+
+            for relocation in relocations {
+                let offset = relocation.offset;
+                let delta = delta(relocation.segment, relocation.undefined)?;
+
+                let len = match relocation.kind {
+                    RelocationKind::Word => 2,
+                    _ => 1,
+                };
+                if offset + len > segment.len() {
+                    return Err(LoadError::InvalidRelocation(offset));
+                }
+
+                match relocation.kind {
+                    RelocationKind::Word => {
+                        let value = u16::from_le_bytes([segment[offset], segment[offset + 1]]);
+                        let bytes = value.wrapping_add(delta).to_le_bytes();
+                        segment[offset..offset + 2].copy_from_slice(&bytes);
+                    }
+                    RelocationKind::High(low) => {
+                        let value = u16::from_le_bytes([low, segment[offset]]);
+                        segment[offset] = (value.wrapping_add(delta) >> 8) as u8;
+                    }
+                    RelocationKind::Low => {
+                        segment[offset] = segment[offset].wrapping_add(delta as u8);
+                    }
+                }
+            }
+
+            Ok(segment)
+        };
+
+        let text = relocate(&self.text, &self.text_relocations)?;
+        let data = relocate(&self.data, &self.data_relocations)?;
+
+        for (base, segment) in [(layout.text, &text), (layout.data, &data)] {
+            for (i, &byte) in segment.iter().enumerate() {
+                bus.set(base.wrapping_add(i as u16), byte);
+            }
+        }
+
+        if self.mode.bss_zero() {
+            for i in 0..self.bss_len {
+                bus.set(layout.bss.wrapping_add(i as u16), 0);
+            }
+        }
+
+        self.globals
+            .iter()
+            .map(|global| {
+                let value = (global.value as u16).wrapping_add(delta(global.segment, None)?);
+
+                Ok((global.name.clone(), value))
+            })
+            .collect()
+    }
+}
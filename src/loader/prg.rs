@@ -0,0 +1,46 @@
+use super::{error::LoadError, Program};
+
+/// Where BASIC programs live on the C64
+pub const BASIC_START: u16 = 0x0801;
+
+/// SYS token of Commodore BASIC
+const SYS: u8 = 0x9E;
+
+/// Parses a Commodore PRG file: a little endian load address followed by the data.
+/// Programs starting with a BASIC `SYS` line, as most machine code PRGs do, get
+/// its address as entry point; other programs are entered from their load address
+/// unless they load where BASIC programs do
+pub fn parse(bytes: &[u8]) -> Result<Program, LoadError> {
+    if bytes.len() < 2 {
+        return Err(LoadError::Truncated);
+    }
+
+    let address = u16::from_le_bytes([bytes[0], bytes[1]]);
+    let data = &bytes[2..];
+
+    let mut program = Program::default();
+    program.push(0, address as u32, data)?;
+
+    program.entry = match address {
+        BASIC_START => sys_address(data),
+        address => Some(address),
+    };
+
+    Ok(program)
+}
+
+/// The address of the `SYS` statement opening a BASIC program, as in `10 SYS 2061`
+fn sys_address(basic: &[u8]) -> Option<u16> {
+    // skip the link to the next line and the line number
+    let statement = basic.get(4..)?;
+    let digits = statement.strip_prefix(&[SYS])?;
+
+    let digits: String = digits
+        .iter()
+        .skip_while(|&&c| c == b' ' || c == b'(')
+        .take_while(|c| c.is_ascii_digit())
+        .map(|&c| c as char)
+        .collect();
+
+    digits.parse().ok()
+}
@@ -0,0 +1,87 @@
+use super::{error::LoadError, hex_bytes, is_record_text, Program};
+
+/// Whether `bytes` look like a Motorola S-record file
+pub fn is_srec(bytes: &[u8]) -> bool {
+    is_record_text(bytes, |line| {
+        line.starts_with('S') && line[1..].starts_with(|c: char| c.is_ascii_digit())
+    })
+}
+
+/// Parses a Motorola S-record file, of the S19, S28 or S37 flavour, which has to
+/// end with a termination record. Termination records set the entry point, unless
+/// their address is zero, which many tools write when there's no entry point to speak of
+pub fn parse(text: &str) -> Result<Program, LoadError> {
+    let mut program = Program::default();
+
+    for (i, record) in text.lines().enumerate() {
+        let line = i + 1;
+        let record = record.trim();
+
+        if record.is_empty() {
+            continue;
+        }
+
+        let invalid = |reason| LoadError::InvalidRecord { line, reason };
+
+        let record = record
+            .strip_prefix('S')
+            .ok_or_else(|| invalid("missing start code 'S'"))?;
+        let record_type = record
+            .chars()
+            .next()
+            .and_then(|c| c.to_digit(10))
+            .ok_or_else(|| invalid("missing record type"))? as u8;
+
+        let bytes = hex_bytes(&record[1..], line)?;
+
+        if bytes.is_empty() || bytes.len() != bytes[0] as usize + 1 {
+            return Err(invalid("length doesn't match the byte count"));
+        }
+
+        let (found, bytes) = bytes.split_last().unwrap();
+        let expected = !bytes.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte));
+        if *found != expected {
+            return Err(LoadError::Checksum {
+                line,
+                expected,
+                found: *found,
+            });
+        }
+
+        let address_len = match record_type {
+            0 | 1 | 5 | 9 => 2,
+            2 | 6 | 8 => 3,
+            3 | 7 => 4,
+            _ => return Err(LoadError::UnknownRecordType { line, record_type }),
+        };
+
+        let bytes = &bytes[1..];
+        if bytes.len() < address_len {
+            return Err(invalid("too short"));
+        }
+
+        let (address, data) = bytes.split_at(address_len);
+        let address = address
+            .iter()
+            .fold(0u32, |address, &byte| (address << 8) | byte as u32);
+
+        match record_type {
+            1..=3 => program.push(line, address, data)?,
+            7..=9 => {
+                if address > 0xFFFF {
+                    return Err(LoadError::AddressOutOfRange { line, address });
+                }
+
+                if address != 0 {
+                    program.entry = Some(address as u16);
+                }
+
+                return Ok(program);
+            }
+            // header and record counts
+            _ => (),
+        }
+    }
+
+    Err(LoadError::MissingEndRecord)
+}
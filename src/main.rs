@@ -1,63 +1,351 @@
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use cpu6502::{
-    cpu::{addressable_bus::DataBus, Cpu},
+    assembler::assemble_variant,
+    cpu::{addressable_bus::DataBus, variant::Variant, Cpu},
+    dap,
+    debugger::{repl, watch::Watched, Debugger},
+    disassembler::disassemble_variant,
+    gdb::GdbStub,
+    kernal::{self, Kernal, Trap},
+    loader::{self, load_file},
     stack_memory::StackMemory,
+    symbols::Symbols,
+    trace,
+};
+use std::{
+    fs::File,
+    io::{BufWriter, Read, Write},
+    path::Path,
 };
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    run()
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
 }
 
 fn run() -> Result<(), Box<dyn std::error::Error>> {
-    let matches = App::new("CPU6502")
-        .author("Pietro T. - BRA1L0R")
-        // .about("6502 emulator")
-        .arg(
-            Arg::with_name("debug")
-                .short("d")
-                .long("debug")
-                .help("Waits for carriage return in stdin to perform a clock cycle and prints cpu information at each"),
-        ).arg(
-            Arg::with_name("offset")
-                .short("o")
-                .long("offset")
-                .default_value("8000")
-                .help("Specifies the load offset into memory for the given binary")
-        )
-        .arg(Arg::with_name("binary").required(true).help("Specifies the program to run")).get_matches();
-
-    let program = std::fs::read(matches.value_of("binary").unwrap())?;
+    let matches =
+        run_args(App::new("CPU6502"))
+            .author("Pietro T. - BRA1L0R")
+            // .about("6502 emulator")
+            .setting(AppSettings::SubcommandsNegateReqs)
+            .setting(AppSettings::ArgsNegateSubcommands)
+            .subcommand(
+                run_args(SubCommand::with_name("run")).about("Runs a binary or an assembly source"),
+            )
+            .subcommand(
+                SubCommand::with_name("asm")
+                    .about("Assembles an xa style source into a raw binary")
+                    .arg(undocumented_arg())
+                    .arg(variant_arg())
+                    .arg(
+                        Arg::with_name("output")
+                            .short("o")
+                            .long("output")
+                            .default_value("a.bin")
+                            .help("Specifies the file to write the binary to"),
+                    )
+                    .arg(
+                        Arg::with_name("source")
+                            .required(true)
+                            .help("Specifies the source to assemble"),
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("disasm")
+                    .about("Prints a disassembly listing of a binary")
+                    .arg(undocumented_arg())
+                    .arg(offset_arg())
+                    .arg(variant_arg())
+                    .arg(symbols_arg())
+                    .arg(
+                        Arg::with_name("binary")
+                            .required(true)
+                            .help("Specifies the binary to disassemble"),
+                    ),
+            )
+            .subcommand(SubCommand::with_name("dap").about(
+                "Serves the Debug Adapter Protocol over stdio, for debugging from an editor",
+            ))
+            .get_matches();
 
-    let offset = matches.value_of("offset").unwrap().trim_start_matches("0x");
-    let offset = u16::from_str_radix(offset, 16)?;
+    match matches.subcommand() {
+        ("run", Some(matches)) => execute(matches),
+        ("asm", Some(matches)) => asm(matches),
+        ("disasm", Some(matches)) => disasm(matches),
+        ("dap", Some(_)) => Ok(dap::serve(std::io::stdin(), std::io::stdout())?),
+        _ => execute(&matches),
+    }
+}
 
-    let mut memory = StackMemory::new();
-    memory.load_data(offset, &program);
+/// The arguments of the `run` subcommand, which are also accepted without it
+fn run_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.arg(
+        Arg::with_name("debug")
+            .short("d")
+            .long("debug")
+            .conflicts_with_all(&["kernal", "trace", "gdb"])
+            .help("Starts an interactive debugger with breakpoints and watchpoints, type help for its commands"),
+    )
+    .arg(
+        Arg::with_name("kernal")
+            .short("k")
+            .long("kernal")
+            .help("Traps calls to the C64 KERNAL I/O routines, such as CHROUT and CHRIN, to stdout and stdin"),
+    )
+    .arg(
+        Arg::with_name("trace")
+            .short("t")
+            .long("trace")
+            .takes_value(true)
+            .value_name("FILE")
+            .help("Writes a nestest.log style line for each instruction executed to a file, or to stdout if -"),
+    )
+    .arg(
+        Arg::with_name("gdb")
+            .long("gdb")
+            .takes_value(true)
+            .value_name("PORT")
+            .conflicts_with_all(&["kernal", "trace"])
+            .help("Waits for a GDB remote protocol client on a localhost port, and runs the program under its control"),
+    )
+    .arg(undocumented_arg())
+    .arg(offset_arg())
+    .arg(variant_arg())
+    .arg(symbols_arg())
+    .arg(
+        Arg::with_name("binary")
+            .required(true)
+            .help("Specifies the program to run, either a binary, an assembly source (.s, .asm) or a C64 program (.prg)"),
+    )
+}
+
+fn execute(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let (memory, entry) = load_program(matches)?;
+    let symbols = program_symbols(matches)?;
 
-    let mut cpu = Cpu::load_memory(memory);
-    let debug = matches.is_present("debug");
+    if matches.is_present("debug") {
+        let mut cpu = Cpu::with_variant(Watched::new(memory), variant(matches));
+        cpu.undocumented_opcodes = matches.is_present("undocumented");
+
+        if let Some(entry) = entry {
+            cpu.program_counter = entry;
+        }
 
-    let err = execution_loop(&mut cpu, debug).expect_err("must never exit without error");
+        let mut debugger = Debugger::new(cpu);
+        debugger.symbols = symbols;
 
-    println!("{}\n", err);
+        let stdin = std::io::stdin();
+        repl::run(&mut debugger, stdin.lock(), std::io::stdout())?;
+
+        return Ok(());
+    }
+
+    let mut cpu = Cpu::with_variant(memory, variant(matches));
+    cpu.undocumented_opcodes = matches.is_present("undocumented");
+
+    if let Some(port) = matches.value_of("gdb") {
+        if let Some(entry) = entry {
+            cpu.program_counter = entry;
+        }
+
+        let port: u16 = port.parse()?;
+        println!("Waiting for gdb on 127.0.0.1:{}", port);
+
+        let mut stub = GdbStub::new(cpu);
+        stub.listen(("127.0.0.1", port))?;
+
+        println!("Cpu status:");
+        println!("{}", stub.debugger.cpu);
+
+        return Ok(());
+    }
+
+    let mut kernal = match matches.is_present("kernal") {
+        true => Some(Kernal::stdio()),
+        false => None,
+    };
+
+    match (entry, &kernal) {
+        // returning from the program ends the run
+        (Some(entry), Some(_)) => kernal::sys(&mut cpu, entry),
+        (Some(entry), None) => cpu.program_counter = entry,
+        (None, _) => (),
+    }
+
+    let mut trace: Option<Box<dyn Write>> = match matches.value_of("trace") {
+        Some("-") => Some(Box::new(std::io::stdout())),
+        Some(path) => Some(Box::new(BufWriter::new(File::create(path)?))),
+        None => None,
+    };
+
+    let result = execution_loop(&mut cpu, &symbols, kernal.as_mut(), trace.as_mut());
+
+    if let Some(trace) = trace.as_mut() {
+        trace.flush()?;
+    }
+
+    match result {
+        Ok(()) => println!("\nProgram returned to BASIC\n"),
+        Err(err) => println!("{}\n", err),
+    }
 
     println!("Cpu status:");
-    println!("{}", cpu);
+    println!("{}", cpu.display_with(&symbols));
+
+    Ok(())
+}
+
+fn load_program(
+    matches: &ArgMatches,
+) -> Result<(StackMemory, Option<u16>), Box<dyn std::error::Error>> {
+    let path = Path::new(matches.value_of("binary").unwrap());
+    let undocumented = matches.is_present("undocumented");
+
+    Ok(load_file(
+        path,
+        offset(matches)?,
+        variant(matches),
+        undocumented,
+    )?)
+}
+
+/// The labels of assembly sources, and the ones of the label file given with `--symbols`
+fn program_symbols(matches: &ArgMatches) -> Result<Symbols, Box<dyn std::error::Error>> {
+    let path = Path::new(matches.value_of("binary").unwrap());
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_lowercase);
+
+    let mut symbols = Symbols::new();
+
+    if let Some("s") | Some("asm") = extension.as_deref() {
+        let source = std::fs::read_to_string(path)?;
+        let undocumented = matches.is_present("undocumented");
+        let assembly = assemble_variant(&source, variant(matches), undocumented)?;
+
+        symbols.extend(Symbols::from(&assembly));
+    }
+
+    if let Some(path) = matches.value_of("symbols") {
+        symbols.extend(Symbols::load(Path::new(path))?);
+    }
+
+    Ok(symbols)
+}
+
+fn asm(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let source = std::fs::read_to_string(matches.value_of("source").unwrap())?;
+    let undocumented = matches.is_present("undocumented");
+
+    let assembly = assemble_variant(&source, variant(matches), undocumented)?;
+    std::fs::write(matches.value_of("output").unwrap(), &assembly.image)?;
 
     Ok(())
 }
 
-fn execution_loop(
-    cpu: &mut Cpu<impl DataBus>,
-    debug_wait: bool,
+fn disasm(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new(matches.value_of("binary").unwrap());
+    let undocumented = matches.is_present("undocumented");
+    let program = loader::load_program(path, offset(matches)?, variant(matches), undocumented)?;
+
+    let mut memory = StackMemory::new();
+    program.load_into(&mut memory);
+
+    let symbols = match matches.value_of("symbols") {
+        Some(path) => Symbols::load(Path::new(path))?,
+        None => Symbols::new(),
+    };
+
+    let mut out = std::io::stdout().lock();
+    for block in program.blocks.iter().filter(|block| !block.data.is_empty()) {
+        let end = block.address as usize + block.data.len() - 1;
+        let end = end.min(0xFFFF) as u16;
+
+        for line in disassemble_variant(&memory, block.address, end, variant(matches), undocumented)
+        {
+            if let Some(label) = symbols.label(line.address) {
+                writeln!(out, "{}:", label)?;
+            }
+            writeln!(out, "{}", line.display_with(&symbols))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn undocumented_arg() -> Arg<'static, 'static> {
+    Arg::with_name("undocumented")
+        .short("u")
+        .long("undocumented")
+        .help("Enables the undocumented NMOS opcodes, such as LAX, DCP and JAM")
+}
+
+fn offset_arg() -> Arg<'static, 'static> {
+    Arg::with_name("offset")
+        .short("o")
+        .long("offset")
+        .default_value("8000")
+        .help("Specifies the load offset into memory for the given binary")
+}
+
+fn variant_arg() -> Arg<'static, 'static> {
+    Arg::with_name("variant")
+        .short("v")
+        .long("variant")
+        .possible_values(&["6502", "2a03", "65c02"])
+        .default_value("6502")
+        .help("Specifies the cpu variant to emulate")
+}
+
+fn symbols_arg() -> Arg<'static, 'static> {
+    Arg::with_name("symbols")
+        .short("s")
+        .long("symbols")
+        .takes_value(true)
+        .value_name("FILE")
+        .help("Loads labels to show in place of addresses, from an xa -l listing, a VICE .lbl file or ca65 .dbg info")
+}
+
+fn offset(matches: &ArgMatches) -> Result<u16, std::num::ParseIntError> {
+    let offset = matches.value_of("offset").unwrap().trim_start_matches("0x");
+
+    u16::from_str_radix(offset, 16)
+}
+
+fn variant(matches: &ArgMatches) -> Variant {
+    match matches.value_of("variant").unwrap() {
+        "2a03" => Variant::Ricoh2A03,
+        "65c02" => Variant::Wdc65C02,
+        _ => Variant::Nmos6502,
+    }
+}
+
+/// Runs until the cpu fails, or the program exits through the KERNAL traps
+fn execution_loop<T: DataBus>(
+    cpu: &mut Cpu<T>,
+    symbols: &Symbols,
+    mut kernal: Option<&mut Kernal<impl Read, impl Write>>,
+    mut trace: Option<impl Write>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     loop {
-        let instr = cpu.tick()?;
+        if let Some(kernal) = kernal.as_mut() {
+            match kernal.trap(cpu)? {
+                Some(Trap::Exit) => return Ok(()),
+                Some(Trap::Serviced) => continue,
+                None => (),
+            }
+        }
 
-        if debug_wait {
-            println!("Executing: {:?}", instr);
-            println!("{}", cpu);
-            std::io::stdin().read_line(&mut String::new())?;
+        if let Some(trace) = trace.as_mut() {
+            writeln!(trace, "{}", trace::line_with(cpu, symbols))?;
         }
+
+        let address = cpu.program_counter;
+        cpu.tick()
+            .map_err(|err| format!("{} at {}", err, symbols.format(address)))?;
     }
 }
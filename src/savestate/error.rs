@@ -0,0 +1,41 @@
+use std::{fmt::Display, io};
+
+#[derive(Debug)]
+pub enum SaveStateError {
+    /// the file doesn't start with the save state magic
+    NotASaveState,
+    /// the file was written by another version of the format
+    UnsupportedVersion(u16),
+    /// the file ends in the middle of a section
+    Truncated,
+    MissingSection(String),
+    /// a section has the wrong size, or holds values out of range
+    InvalidSection(String),
+    Io(io::Error),
+}
+
+impl Display for SaveStateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotASaveState => write!(f, "Not a save state"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "Unsupported save state version {}", version)
+            }
+            Self::Truncated => write!(f, "Save state is truncated"),
+            Self::MissingSection(name) => write!(f, "Save state has no {} section", name),
+            Self::InvalidSection(name) => write!(f, "Invalid {} section", name),
+            Self::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for SaveStateError {}
+
+impl From<io::Error> for SaveStateError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::UnexpectedEof => Self::Truncated,
+            _ => Self::Io(err),
+        }
+    }
+}
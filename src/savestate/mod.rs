@@ -0,0 +1,140 @@
+use self::error::SaveStateError;
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+pub mod error;
+
+/// The first bytes of every save state file
+pub const MAGIC: &[u8; 8] = b"6502SAVE";
+/// The version of the format written. Files of any other version are refused
+pub const VERSION: u16 = 1;
+
+/// State a bus, or a device on it, keeps in save states
+pub trait Snapshot {
+    /// Adds its sections to `state`
+    fn save(&self, state: &mut SaveState);
+
+    /// Restores itself from the sections of `state`. Nothing should change
+    /// when an error is returned
+    fn restore(&mut self, state: &SaveState) -> Result<(), SaveStateError>;
+}
+
+/// A snapshot of a machine, made of named sections of bytes: one for the cpu,
+/// see [`Cpu::save_state`](crate::cpu::Cpu::save_state), and whatever the bus adds.
+///
+/// Files start with [`MAGIC`] and the little endian [`VERSION`], followed by the
+/// sections in order of name, each as its name length (one byte), the name, the
+/// data length (four bytes, little endian) and the data
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SaveState {
+    sections: BTreeMap<String, Vec<u8>>,
+}
+
+impl SaveState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a section, replacing any other with the same name.
+    /// Names are at most 255 bytes long
+    pub fn insert(&mut self, name: &str, data: Vec<u8>) {
+        assert!(name.len() <= u8::MAX as usize, "section name too long");
+
+        self.sections.insert(name.to_string(), data);
+    }
+
+    pub fn section(&self, name: &str) -> Option<&[u8]> {
+        self.sections.get(name).map(Vec::as_slice)
+    }
+
+    /// The section called `name`, which must be `len` bytes long
+    pub fn require(&self, name: &str, len: usize) -> Result<&[u8], SaveStateError> {
+        match self.section(name) {
+            Some(data) if data.len() == len => Ok(data),
+            Some(_) => Err(SaveStateError::InvalidSection(name.to_string())),
+            None => Err(SaveStateError::MissingSection(name.to_string())),
+        }
+    }
+
+    /// Names of the sections, in order
+    pub fn sections(&self) -> impl Iterator<Item = &str> + '_ {
+        self.sections.keys().map(String::as_str)
+    }
+
+    pub fn write(&self, out: &mut impl Write) -> Result<(), SaveStateError> {
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+
+        for (name, data) in &self.sections {
+            let len = u32::try_from(data.len())
+                .map_err(|_| SaveStateError::InvalidSection(name.clone()))?;
+
+            out.write_all(&[name.len() as u8])?;
+            out.write_all(name.as_bytes())?;
+            out.write_all(&len.to_le_bytes())?;
+            out.write_all(data)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read(input: &mut impl Read) -> Result<Self, SaveStateError> {
+        let mut magic = [0; 8];
+        input
+            .read_exact(&mut magic)
+            .map_err(|_| SaveStateError::NotASaveState)?;
+        if &magic != MAGIC {
+            return Err(SaveStateError::NotASaveState);
+        }
+
+        let mut version = [0; 2];
+        input.read_exact(&mut version)?;
+        let version = u16::from_le_bytes(version);
+        if version != VERSION {
+            return Err(SaveStateError::UnsupportedVersion(version));
+        }
+
+        let mut state = Self::new();
+        let mut name_len = [0];
+
+        // sections until the end of the file
+        while input.read(&mut name_len)? == 1 {
+            let mut name = vec![0; name_len[0] as usize];
+            input.read_exact(&mut name)?;
+            let name = String::from_utf8(name)
+                .map_err(|err| SaveStateError::InvalidSection(err.to_string()))?;
+
+            let mut len = [0; 4];
+            input.read_exact(&mut len)?;
+
+            let mut data = Vec::new();
+            input
+                .take(u32::from_le_bytes(len) as u64)
+                .read_to_end(&mut data)?;
+            if data.len() != u32::from_le_bytes(len) as usize {
+                return Err(SaveStateError::Truncated);
+            }
+
+            state.sections.insert(name, data);
+        }
+
+        Ok(state)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SaveStateError> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write(&mut out)?;
+        out.flush()?;
+
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SaveStateError> {
+        Self::read(&mut BufReader::new(File::open(path)?))
+    }
+}
@@ -1,13 +1,25 @@
-use crate::cpu::addressable_bus::DataBus;
+use crate::{
+    cpu::addressable_bus::DataBus,
+    savestate::{error::SaveStateError, SaveState, Snapshot},
+};
+use std::{
+    convert::TryInto,
+    io::{self, Write},
+    ops::RangeInclusive,
+};
 
-// use cpu6502::cpu::addressable_bus::DataBus;
+const MEMORY_SIZE: usize = 0x10000;
+/// Name of the save state section holding the memory
+pub const MEMORY_SECTION: &str = "memory";
 
+/// Flat memory covering the whole 64K address space.
+/// It's kept on the heap, so that it can be moved around freely
 pub struct StackMemory {
-    memory: [u8; 65535],
+    memory: Box<[u8; MEMORY_SIZE]>,
 }
 
 impl DataBus for StackMemory {
-    fn get(&self, addr: u16) -> u8 {
+    fn peek(&self, addr: u16) -> u8 {
         self.memory[addr as usize]
     }
 
@@ -24,9 +36,17 @@ impl Default for StackMemory {
 
 impl StackMemory {
     pub fn new() -> Self {
-        Self { memory: [0; 65535] }
+        let memory = vec![0; MEMORY_SIZE].into_boxed_slice();
+
+        Self {
+            memory: memory
+                .try_into()
+                .expect("memory has the size of the address space"),
+        }
     }
 
+    /// Copies `program` into memory starting from `offset`.
+    /// Anything past the end of the address space is dropped
     pub fn load_data(&mut self, offset: u16, program: &[u8]) {
         self.memory
             .iter_mut()
@@ -34,4 +54,63 @@ impl StackMemory {
             .zip(program)
             .for_each(|(mem, prog)| *mem = *prog);
     }
+
+    /// The whole address space
+    pub fn as_slice(&self) -> &[u8] {
+        &self.memory[..]
+    }
+
+    /// The bytes within `range`, both ends included
+    pub fn read_range(&self, range: RangeInclusive<u16>) -> &[u8] {
+        &self.memory[*range.start() as usize..=*range.end() as usize]
+    }
+
+    /// Writes a hex dump of `range` to `out`, sixteen bytes per line:
+    ///
+    /// ```text
+    /// 8000: A2 01 86 00 38 A0 0A 98 E9 03 A8 18 A9 02 85 01  |....8...........|
+    /// ```
+    pub fn dump(&self, range: RangeInclusive<u16>, out: &mut impl Write) -> io::Result<()> {
+        let start = *range.start();
+
+        for (line, chunk) in self.read_range(range).chunks(16).enumerate() {
+            write!(out, "{:04X}:", start as usize + line * 16)?;
+
+            for byte in chunk {
+                write!(out, " {:02X}", byte)?;
+            }
+
+            let ascii: String = chunk
+                .iter()
+                .map(|&byte| match byte {
+                    0x20..=0x7E => byte as char,
+                    _ => '.',
+                })
+                .collect();
+
+            writeln!(
+                out,
+                "{:width$}  |{}|",
+                "",
+                ascii,
+                width = (16 - chunk.len()) * 3
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The whole address space, as the `memory` section
+impl Snapshot for StackMemory {
+    fn save(&self, state: &mut SaveState) {
+        state.insert(MEMORY_SECTION, self.as_slice().to_vec());
+    }
+
+    fn restore(&mut self, state: &SaveState) -> Result<(), SaveStateError> {
+        let data = state.require(MEMORY_SECTION, MEMORY_SIZE)?;
+        self.memory.copy_from_slice(data);
+
+        Ok(())
+    }
 }
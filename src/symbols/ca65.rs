@@ -0,0 +1,49 @@
+use super::{error::SymbolError, parse_number, Symbols};
+
+/// Parses the `sym` lines of the debug info `ld65 --dbgfile` writes, where a tab
+/// follows the line type.
+/// Labels name addresses, while equates only get defined, as they
+/// are as likely to be constants:
+///
+/// ```text
+/// sym id=3,name="loop",addrsize=absolute,scope=0,def=5,ref=9,val=0x8010,seg=0,type=lab
+/// ```
+pub fn parse(text: &str) -> Result<Symbols, SymbolError> {
+    let mut symbols = Symbols::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let invalid = |reason| SymbolError::InvalidLine {
+            line: i + 1,
+            reason,
+        };
+
+        let attributes = match line.split_once('\t') {
+            Some(("sym", attributes)) => attributes,
+            _ => continue,
+        };
+
+        let (mut name, mut value, mut label) = (None, None, false);
+
+        for attribute in attributes.split(',') {
+            match attribute.split_once('=') {
+                Some(("name", text)) => name = Some(text.trim_matches('"')),
+                Some(("val", text)) => {
+                    value = Some(parse_number(text).ok_or_else(|| invalid("invalid value"))?)
+                }
+                Some(("type", text)) => label = text == "lab",
+                _ => (),
+            }
+        }
+
+        let name = name.ok_or_else(|| invalid("missing name"))?;
+
+        // imports have no value of their own
+        match value {
+            Some(value) if label => symbols.insert(name, value as u16),
+            Some(value) => symbols.define(name, value),
+            None => (),
+        }
+    }
+
+    Ok(symbols)
+}
@@ -0,0 +1,28 @@
+use std::{fmt::Display, io};
+
+#[derive(Debug)]
+pub enum SymbolError {
+    /// lines count from 1
+    InvalidLine {
+        line: usize,
+        reason: &'static str,
+    },
+    Io(io::Error),
+}
+
+impl Display for SymbolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidLine { line, reason } => write!(f, "line {}: {}", line, reason),
+            Self::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for SymbolError {}
+
+impl From<io::Error> for SymbolError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
@@ -0,0 +1,139 @@
+use self::error::SymbolError;
+use crate::assembler::Assembly;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+pub mod ca65;
+pub mod error;
+pub mod vice;
+pub mod xa;
+
+/// How far past a label addresses still get described relative to it
+const MAX_OFFSET: u16 = 0x100;
+
+/// Names of addresses and values, as found in the label files of assemblers and emulators
+#[derive(Debug, Clone, Default)]
+pub struct Symbols {
+    values: HashMap<String, i64>,
+    /// the first label given to each address
+    labels: BTreeMap<u16, String>,
+}
+
+impl Symbols {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a label file. ca65 debug info (`.dbg`) and VICE label files (`.lbl`)
+    /// are told apart by extension, then like [`Symbols::parse`] does
+    pub fn load(path: &Path) -> Result<Self, SymbolError> {
+        let text = std::fs::read_to_string(path)?;
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_lowercase);
+
+        match extension.as_deref() {
+            Some("dbg") => ca65::parse(&text),
+            Some("lbl") | Some("vs") => vice::parse(&text),
+            _ => Self::parse(&text),
+        }
+    }
+
+    /// Parses a label file, telling its format from the first line
+    pub fn parse(text: &str) -> Result<Self, SymbolError> {
+        let first = text.lines().find(|line| !line.trim().is_empty());
+
+        match first.and_then(|line| line.split_whitespace().next()) {
+            Some("al") | Some("add_label") => vice::parse(text),
+            Some("version") | Some("info") | Some("file") | Some("sym") => ca65::parse(text),
+            _ => xa::parse(text),
+        }
+    }
+
+    /// Adds a label naming `address`
+    pub fn insert(&mut self, name: &str, address: u16) {
+        self.values.insert(name.to_string(), address as i64);
+        self.labels
+            .entry(address)
+            .or_insert_with(|| name.to_string());
+    }
+
+    /// Adds a symbol which isn't used to name addresses
+    pub fn define(&mut self, name: &str, value: i64) {
+        self.values.insert(name.to_string(), value);
+    }
+
+    /// Adds the symbols of `other`, which win over the ones already here
+    pub fn extend(&mut self, other: Symbols) {
+        self.values.extend(other.values);
+        self.labels.extend(other.labels);
+    }
+
+    pub fn get(&self, name: &str) -> Option<i64> {
+        self.values.get(name).copied()
+    }
+
+    /// Every symbol, as assembler expressions take them
+    pub fn values(&self) -> &HashMap<String, i64> {
+        &self.values
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The label of exactly `address`
+    pub fn label(&self, address: u16) -> Option<&str> {
+        self.labels.get(&address).map(String::as_str)
+    }
+
+    /// Describes `address` relative to the closest label before it, as in `loop+3`
+    pub fn describe(&self, address: u16) -> Option<String> {
+        let (&label_address, name) = self.labels.range(..=address).next_back()?;
+
+        match address - label_address {
+            0 => Some(name.clone()),
+            offset if offset < MAX_OFFSET => Some(format!("{}+{}", name, offset)),
+            _ => None,
+        }
+    }
+
+    /// Like [`Symbols::describe`], falling back to `$` hex
+    pub fn format(&self, address: u16) -> String {
+        self.describe(address)
+            .unwrap_or_else(|| format!("${:04X}", address))
+    }
+}
+
+/// The labels and definitions of a source assembled by the built-in assembler.
+/// Only labels name addresses, definitions such as `count = $10` are usually constants
+impl From<&Assembly> for Symbols {
+    fn from(assembly: &Assembly) -> Self {
+        let mut symbols = Symbols::new();
+        for (name, &value) in &assembly.symbols {
+            match assembly.labels.contains(name) {
+                true => symbols.insert(name, value),
+                false => symbols.define(name, value as i64),
+            }
+        }
+
+        symbols
+    }
+}
+
+/// Numbers as label files write them: `0x` or `$` hex, or decimal
+fn parse_number(text: &str) -> Option<i64> {
+    let text = text.trim();
+
+    match text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .or_else(|| text.strip_prefix('$'))
+    {
+        Some(digits) => i64::from_str_radix(digits, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
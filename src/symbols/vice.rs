@@ -0,0 +1,42 @@
+use super::{error::SymbolError, Symbols};
+use std::convert::TryFrom;
+
+/// Parses VICE monitor label files, made of `add_label` commands such as
+/// the ones `ld65 -Ln` writes. Other monitor commands are skipped:
+///
+/// ```text
+/// al C:8010 .loop
+/// al 008010 .loop
+/// ```
+pub fn parse(text: &str) -> Result<Symbols, SymbolError> {
+    let mut symbols = Symbols::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let invalid = |reason| SymbolError::InvalidLine {
+            line: i + 1,
+            reason,
+        };
+
+        let mut words = line.split_whitespace();
+        if !matches!(words.next(), Some("al") | Some("add_label")) {
+            continue;
+        }
+
+        let address = words.next().ok_or_else(|| invalid("missing address"))?;
+        let name = words.next().ok_or_else(|| invalid("missing label"))?;
+
+        // the address can be prefixed by its memory space, as in C:
+        let address = match address.split_once(':') {
+            Some((_, address)) => address,
+            None => address,
+        };
+        let address = u32::from_str_radix(address, 16)
+            .ok()
+            .and_then(|address| u16::try_from(address).ok())
+            .ok_or_else(|| invalid("invalid address"))?;
+
+        symbols.insert(name.trim_start_matches('.'), address);
+    }
+
+    Ok(symbols)
+}
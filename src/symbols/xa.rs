@@ -0,0 +1,37 @@
+use super::{error::SymbolError, parse_number, Symbols};
+use std::convert::TryFrom;
+
+/// Parses the label listing `xa -l` writes, a label per line followed by
+/// its value, block and flags:
+///
+/// ```text
+/// loop, 0x8010, 1, 0x0000
+/// ```
+pub fn parse(text: &str) -> Result<Symbols, SymbolError> {
+    let mut symbols = Symbols::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let invalid = |reason| SymbolError::InvalidLine {
+            line: i + 1,
+            reason,
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut fields = line.split(',').map(str::trim);
+        let name = fields.next().filter(|name| !name.is_empty());
+        let value = fields.next().ok_or_else(|| invalid("missing value"))?;
+
+        let name = name.ok_or_else(|| invalid("missing label"))?;
+        let value = parse_number(value).ok_or_else(|| invalid("invalid value"))?;
+
+        match u16::try_from(value) {
+            Ok(address) => symbols.insert(name, address),
+            Err(_) => symbols.define(name, value),
+        }
+    }
+
+    Ok(symbols)
+}
@@ -0,0 +1,154 @@
+use crate::{
+    cpu::{
+        addressable_bus::DataBus,
+        instruction::{Addressing, Instruction, InstructionType},
+        Cpu,
+    },
+    disassembler::decode,
+    symbols::Symbols,
+};
+
+/// Formats the instruction the cpu is about to execute, and the registers before executing it,
+/// in the column layout of nestest.log and Nintendulator traces:
+///
+/// ```text
+/// C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD CYC:7
+/// C72D  B3 89    *LAX ($89),Y = 0300 @ 0300 = 89  A:00 X:00 Y:00 P:24 SP:FD CYC:15
+/// ```
+///
+/// Undocumented opcodes are marked with `*`, and memory operands are followed by the effective
+/// address and the value found there. There's no PPU here, so its column is left out.
+/// Memory is only peeked at
+pub fn line<T: DataBus>(cpu: &Cpu<T>) -> String {
+    line_with(cpu, &Symbols::new())
+}
+
+/// Like [`line`], naming operands after `symbols`
+pub fn line_with<T: DataBus>(cpu: &Cpu<T>, symbols: &Symbols) -> String {
+    let address = cpu.program_counter;
+    let line = decode(&cpu.bus, address, cpu.variant, cpu.undocumented_opcodes);
+    let bytes: Vec<String> = line.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+
+    let (marker, text) = match line.instruction {
+        Some(instruction) => {
+            let documented = decode(&cpu.bus, address, cpu.variant, false).instruction;
+            let marker = if documented.is_some() { ' ' } else { '*' };
+
+            let text = format!(
+                "{}{}",
+                instruction.display_with(address, symbols),
+                operand_value(cpu, &instruction)
+            );
+
+            (marker, text)
+        }
+        None => (' ', format!(".byt ${:02X}", line.bytes[0])),
+    };
+
+    format!(
+        "{:04X}  {:<8} {}{:<31} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} CYC:{}",
+        address,
+        bytes.join(" "),
+        marker,
+        text,
+        cpu.accumulator,
+        cpu.x_register,
+        cpu.y_register,
+        cpu.processor_status.0,
+        cpu.stack_pointer,
+        cpu.cycles
+    )
+}
+
+/// The `@ address = value` part nestest.log shows after memory operands
+fn operand_value<T: DataBus>(cpu: &Cpu<T>, instruction: &Instruction) -> String {
+    let bus = &cpu.bus;
+    let (x, y) = (cpu.x_register, cpu.y_register);
+
+    let zeropage_word = |addr: u8| {
+        let ll = bus.peek(addr as u16);
+        let hh = bus.peek(addr.wrapping_add(1) as u16);
+
+        u16::from_le_bytes([ll, hh])
+    };
+
+    match instruction.addressing {
+        Addressing::Absolute(_)
+            if matches!(
+                instruction.instruction_type,
+                InstructionType::JMP | InstructionType::JSR
+            ) =>
+        {
+            String::new()
+        }
+
+        Addressing::Zeropage(addr) => format!(" = {:02X}", bus.peek(addr as u16)),
+        Addressing::Absolute(addr) => format!(" = {:02X}", bus.peek(addr)),
+
+        Addressing::ZeropageX(addr) | Addressing::ZeropageY(addr) => {
+            let index = match instruction.addressing {
+                Addressing::ZeropageX(_) => x,
+                _ => y,
+            };
+            let effective = addr.wrapping_add(index);
+
+            format!(" @ {:02X} = {:02X}", effective, bus.peek(effective as u16))
+        }
+        Addressing::AbsoluteX(addr) | Addressing::AbsoluteY(addr) => {
+            let index = match instruction.addressing {
+                Addressing::AbsoluteX(_) => x,
+                _ => y,
+            };
+            let effective = addr.wrapping_add(index as u16);
+
+            format!(" @ {:04X} = {:02X}", effective, bus.peek(effective))
+        }
+
+        Addressing::Indirect(addr) => {
+            // same page wrap as the NMOS cpu, see Cpu::address_addressing
+            let hh = match cpu.variant.is_cmos() {
+                true => addr.wrapping_add(1),
+                false => (addr & 0xFF00) | (addr.wrapping_add(1) & 0x00FF),
+            };
+
+            format!(
+                " = {:04X}",
+                u16::from_le_bytes([bus.peek(addr), bus.peek(hh)])
+            )
+        }
+        Addressing::IndirectX(addr) => {
+            let pointer = addr.wrapping_add(x);
+            let effective = zeropage_word(pointer);
+
+            format!(
+                " @ {:02X} = {:04X} = {:02X}",
+                pointer,
+                effective,
+                bus.peek(effective)
+            )
+        }
+        Addressing::IndirectY(addr) => {
+            let base = zeropage_word(addr);
+            let effective = base.wrapping_add(y as u16);
+
+            format!(
+                " = {:04X} @ {:04X} = {:02X}",
+                base,
+                effective,
+                bus.peek(effective)
+            )
+        }
+        Addressing::ZeropageIndirect(addr) => {
+            let effective = zeropage_word(addr);
+
+            format!(" = {:04X} = {:02X}", effective, bus.peek(effective))
+        }
+        Addressing::AbsoluteIndirectX(addr) => {
+            let pointer = addr.wrapping_add(x as u16);
+
+            format!(" @ {:04X} = {:04X}", pointer, bus.peek_word(pointer))
+        }
+
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,201 @@
+mod common;
+
+use common::Ram;
+use cpu6502::cpu::{addressable_bus::DataBus, instruction::Addressing, variant::Variant, Cpu};
+
+fn cpu(variant: Variant) -> Cpu<Ram> {
+    Cpu::with_variant(Ram::new(), variant)
+}
+
+#[test]
+fn zeropage() {
+    let mut cpu = cpu(Variant::Nmos6502);
+
+    assert_eq!(cpu.address_addressing(Addressing::Zeropage(0x42)), 0x0042);
+}
+
+#[test]
+fn zeropage_x_wraps_within_page_zero() {
+    let mut cpu = cpu(Variant::Nmos6502);
+    cpu.x_register = 0x10;
+
+    assert_eq!(cpu.address_addressing(Addressing::ZeropageX(0x20)), 0x0030);
+    assert_eq!(cpu.address_addressing(Addressing::ZeropageX(0xF8)), 0x0008);
+}
+
+#[test]
+fn zeropage_y_wraps_within_page_zero() {
+    let mut cpu = cpu(Variant::Nmos6502);
+    cpu.y_register = 0xFF;
+
+    assert_eq!(cpu.address_addressing(Addressing::ZeropageY(0x00)), 0x00FF);
+    assert_eq!(cpu.address_addressing(Addressing::ZeropageY(0x01)), 0x0000);
+}
+
+#[test]
+fn absolute() {
+    let mut cpu = cpu(Variant::Nmos6502);
+
+    assert_eq!(cpu.address_addressing(Addressing::Absolute(0xBEEF)), 0xBEEF);
+}
+
+#[test]
+fn absolute_x_wraps_around_64k() {
+    let mut cpu = cpu(Variant::Nmos6502);
+    cpu.x_register = 0x20;
+
+    assert_eq!(
+        cpu.address_addressing(Addressing::AbsoluteX(0x12F0)),
+        0x1310
+    );
+    assert_eq!(
+        cpu.address_addressing(Addressing::AbsoluteX(0xFFF0)),
+        0x0010
+    );
+}
+
+#[test]
+fn absolute_y_wraps_around_64k() {
+    let mut cpu = cpu(Variant::Nmos6502);
+    cpu.y_register = 0x01;
+
+    assert_eq!(
+        cpu.address_addressing(Addressing::AbsoluteY(0xFFFF)),
+        0x0000
+    );
+}
+
+#[test]
+fn indirect_has_the_nmos_page_bug() {
+    let mut cpu = cpu(Variant::Nmos6502);
+    cpu.bus.set_word(0x1234, 0xABCD);
+    cpu.bus.set(0x10FF, 0x34);
+    cpu.bus.set(0x1000, 0x12);
+    cpu.bus.set(0x1100, 0x56);
+
+    assert_eq!(cpu.address_addressing(Addressing::Indirect(0x1234)), 0xABCD);
+    assert_eq!(cpu.address_addressing(Addressing::Indirect(0x10FF)), 0x1234);
+}
+
+#[test]
+fn indirect_is_fixed_on_cmos() {
+    let mut cpu = cpu(Variant::Wdc65C02);
+    cpu.bus.set(0x10FF, 0x34);
+    cpu.bus.set(0x1000, 0x12);
+    cpu.bus.set(0x1100, 0x56);
+
+    assert_eq!(cpu.address_addressing(Addressing::Indirect(0x10FF)), 0x5634);
+}
+
+#[test]
+fn indirect_x_pointer_wraps_within_page_zero() {
+    let mut cpu = cpu(Variant::Nmos6502);
+    cpu.bus.set(0x00FF, 0x34);
+    cpu.bus.set(0x0000, 0x12);
+    cpu.bus.set(0x0100, 0x56);
+    cpu.bus.set_word(0x0010, 0xBEEF);
+
+    cpu.x_register = 0x01;
+    assert_eq!(cpu.address_addressing(Addressing::IndirectX(0xFE)), 0x1234);
+
+    cpu.x_register = 0x20;
+    assert_eq!(cpu.address_addressing(Addressing::IndirectX(0xF0)), 0xBEEF);
+}
+
+#[test]
+fn indirect_y_pointer_wraps_within_page_zero() {
+    let mut cpu = cpu(Variant::Nmos6502);
+    cpu.bus.set(0x00FF, 0x34);
+    cpu.bus.set(0x0000, 0x12);
+    cpu.bus.set(0x0100, 0x56);
+    cpu.y_register = 0x10;
+
+    assert_eq!(cpu.address_addressing(Addressing::IndirectY(0xFF)), 0x1244);
+}
+
+#[test]
+fn indirect_y_wraps_around_64k() {
+    let mut cpu = cpu(Variant::Nmos6502);
+    cpu.bus.set_word(0x0040, 0xFFFF);
+    cpu.y_register = 0x02;
+
+    assert_eq!(cpu.address_addressing(Addressing::IndirectY(0x40)), 0x0001);
+}
+
+#[test]
+fn zeropage_indirect_wraps_within_page_zero() {
+    let mut cpu = cpu(Variant::Wdc65C02);
+    cpu.bus.set(0x00FF, 0x34);
+    cpu.bus.set(0x0000, 0x12);
+    cpu.bus.set(0x0100, 0x56);
+
+    assert_eq!(
+        cpu.address_addressing(Addressing::ZeropageIndirect(0xFF)),
+        0x1234
+    );
+}
+
+#[test]
+fn absolute_indirect_x() {
+    let mut cpu = cpu(Variant::Wdc65C02);
+    cpu.bus.set_word(0x1300, 0xBEEF);
+    cpu.x_register = 0x10;
+
+    assert_eq!(
+        cpu.address_addressing(Addressing::AbsoluteIndirectX(0x12F0)),
+        0xBEEF
+    );
+}
+
+#[test]
+fn relative_wraps_around_64k() {
+    let mut cpu = cpu(Variant::Nmos6502);
+
+    cpu.program_counter = 0x8010;
+    assert_eq!(cpu.address_addressing(Addressing::Relative(0x7F)), 0x808F);
+    assert_eq!(cpu.address_addressing(Addressing::Relative(0x80)), 0x7F90);
+
+    cpu.program_counter = 0x0002;
+    assert_eq!(cpu.address_addressing(Addressing::Relative(0xFC)), 0xFFFE);
+
+    cpu.program_counter = 0xFFFE;
+    assert_eq!(cpu.address_addressing(Addressing::Relative(0x04)), 0x0002);
+}
+
+#[test]
+fn page_crossing() {
+    let mut cpu = cpu(Variant::Nmos6502);
+    cpu.bus.set_word(0x0010, 0x12FF);
+    cpu.x_register = 0x01;
+    cpu.y_register = 0x01;
+
+    assert!(!cpu.page_crossed(Addressing::AbsoluteX(0x12FE)));
+    assert!(cpu.page_crossed(Addressing::AbsoluteX(0x12FF)));
+    assert!(cpu.page_crossed(Addressing::AbsoluteY(0xFFFF)));
+    assert!(cpu.page_crossed(Addressing::IndirectY(0x10)));
+    assert!(!cpu.page_crossed(Addressing::ZeropageX(0xFF)));
+}
+
+#[test]
+fn stack_wraps_within_page_one() {
+    let mut cpu = cpu(Variant::Nmos6502);
+    cpu.stack_pointer = 0x00;
+
+    cpu.stack_push_word(0x1234);
+    assert_eq!(cpu.stack_pointer, 0xFE);
+    assert_eq!(cpu.bus.peek(0x0100), 0x12);
+    assert_eq!(cpu.bus.peek(0x01FF), 0x34);
+
+    assert_eq!(cpu.stack_pop_word(), 0x1234);
+    assert_eq!(cpu.stack_pointer, 0x00);
+}
+
+#[test]
+fn program_counter_wraps_around_64k() {
+    let mut cpu = cpu(Variant::Nmos6502);
+    cpu.bus.set(0xFFFF, 0x42);
+    cpu.program_counter = 0xFFFF;
+
+    assert_eq!(cpu.read_byte(), 0x42);
+    assert_eq!(cpu.program_counter, 0x0000);
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use common::Ram;
+use cpu6502::cpu::{addressable_bus::DataBus, status::StatusFlag, Cpu};
+
+const ADC_IMMEDIATE: u8 = 0x69;
+const SBC_IMMEDIATE: u8 = 0xE9;
+
+/// Flags as computed by a reference model working on wider signed and unsigned integers
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    result: u8,
+    carry: bool,
+    overflow: bool,
+    negative: bool,
+    zero: bool,
+}
+
+impl Outcome {
+    fn new(unsigned: i32, signed: i32) -> Self {
+        let result = unsigned as u8;
+
+        Self {
+            result,
+            carry: unsigned > 0xFF,
+            overflow: !(-128..=127).contains(&signed),
+            negative: result & 0x80 != 0,
+            zero: result == 0,
+        }
+    }
+
+    fn adc(a: u8, m: u8, carry: bool) -> Self {
+        let unsigned = a as i32 + m as i32 + carry as i32;
+        let signed = a as i8 as i32 + m as i8 as i32 + carry as i32;
+
+        Self::new(unsigned, signed)
+    }
+
+    fn sbc(a: u8, m: u8, carry: bool) -> Self {
+        let borrow = !carry as i32;
+        let unsigned = a as i32 - m as i32 - borrow;
+        let signed = a as i8 as i32 - m as i8 as i32 - borrow;
+
+        // C is the inverted borrow, so it's set as long as the result didn't go below zero
+        Self {
+            carry: unsigned >= 0,
+            ..Self::new(unsigned, signed)
+        }
+    }
+}
+
+fn execute(cpu: &mut Cpu<Ram>, opcode: u8, a: u8, m: u8, carry: bool) -> Outcome {
+    cpu.bus.set(0x0200, opcode);
+    cpu.bus.set(0x0201, m);
+    cpu.program_counter = 0x0200;
+    cpu.accumulator = a;
+    cpu.processor_status.set_flag(StatusFlag::Carry, carry);
+
+    cpu.tick().unwrap();
+
+    Outcome {
+        result: cpu.accumulator,
+        carry: cpu.processor_status.get_flag(StatusFlag::Carry),
+        overflow: cpu.processor_status.get_flag(StatusFlag::Overflow),
+        negative: cpu.processor_status.get_flag(StatusFlag::Negative),
+        zero: cpu.processor_status.get_flag(StatusFlag::Zero),
+    }
+}
+
+fn exhaustive(opcode: u8, reference: fn(u8, u8, bool) -> Outcome) {
+    let mut cpu = Cpu::load_memory(Ram::new());
+
+    for a in 0..=0xFF {
+        for m in 0..=0xFF {
+            for &carry in &[false, true] {
+                assert_eq!(
+                    execute(&mut cpu, opcode, a, m, carry),
+                    reference(a, m, carry),
+                    "A: 0x{:02X} M: 0x{:02X} C: {}",
+                    a,
+                    m,
+                    carry
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn adc_matches_reference_model() {
+    exhaustive(ADC_IMMEDIATE, Outcome::adc);
+}
+
+#[test]
+fn sbc_matches_reference_model() {
+    exhaustive(SBC_IMMEDIATE, Outcome::sbc);
+}
+
+#[test]
+fn overflow_examples() {
+    let mut cpu = Cpu::load_memory(Ram::new());
+
+    // 0x50 + 0x50 = 0xA0, two positives giving a negative
+    assert!(execute(&mut cpu, ADC_IMMEDIATE, 0x50, 0x50, false).overflow);
+    // 0xD0 + 0x90 = 0x60, two negatives giving a positive
+    assert!(execute(&mut cpu, ADC_IMMEDIATE, 0xD0, 0x90, false).overflow);
+    // 0xFF + 0x01 carries out, but -1 + 1 doesn't overflow
+    assert!(!execute(&mut cpu, ADC_IMMEDIATE, 0xFF, 0x01, false).overflow);
+
+    // 0x50 - 0xB0 = 0xA0, a positive minus a negative giving a negative
+    assert!(execute(&mut cpu, SBC_IMMEDIATE, 0x50, 0xB0, true).overflow);
+    // 0xD0 - 0x70 = 0x60, a negative minus a positive giving a positive
+    assert!(execute(&mut cpu, SBC_IMMEDIATE, 0xD0, 0x70, true).overflow);
+    // 0x00 - 0x01 borrows, but 0 - 1 doesn't overflow
+    assert!(!execute(&mut cpu, SBC_IMMEDIATE, 0x00, 0x01, true).overflow);
+}
@@ -0,0 +1,204 @@
+use cpu6502::{
+    assembler::{assemble, assemble_variant, error::ErrorKind},
+    cpu::{variant::Variant, Cpu},
+    stack_memory::StackMemory,
+};
+
+fn image(source: &str) -> Vec<u8> {
+    assemble(source).unwrap().image
+}
+
+fn error(source: &str) -> (usize, ErrorKind) {
+    let err = assemble(source).unwrap_err();
+
+    (err.line, err.kind)
+}
+
+#[test]
+fn addressing_modes() {
+    let source = "
+        * = $8000
+        LDA #$01
+        LDA $10
+        LDA $10,X
+        LDX $10,Y
+        LDA $1234
+        LDA $1234,X
+        LDA $1234,Y
+        JMP ($1234)
+        LDA ($10,X)
+        LDA ($10),Y
+        ASL A
+        ASL
+        NOP
+    ";
+
+    assert_eq!(
+        image(source),
+        [
+            0xA9, 0x01, 0xA5, 0x10, 0xB5, 0x10, 0xB6, 0x10, 0xAD, 0x34, 0x12, 0xBD, 0x34, 0x12,
+            0xB9, 0x34, 0x12, 0x6C, 0x34, 0x12, 0xA1, 0x10, 0xB1, 0x10, 0x0A, 0x0A, 0xEA,
+        ]
+    );
+}
+
+#[test]
+fn labels_and_branches() {
+    let assembly = assemble(
+        "
+        * = $C000
+        start:  LDX #3
+        loop    DEX
+                BNE loop
+                BEQ done
+                NOP
+        done    JMP start
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(assembly.origin, 0xC000);
+    assert_eq!(
+        assembly.image,
+        [0xA2, 0x03, 0xCA, 0xD0, 0xFD, 0xF0, 0x01, 0xEA, 0x4C, 0x00, 0xC0]
+    );
+    assert_eq!(assembly.symbols["loop"], 0xC002);
+    assert_eq!(assembly.symbols["done"], 0xC008);
+}
+
+#[test]
+fn forward_references_are_absolute() {
+    // `data` isn't known when LDA is sized, so it can't be assumed to be in page zero
+    assert_eq!(
+        image("LDA data\nLDA data2\ndata = $10\ndata2 = $20"),
+        [0xAD, 0x10, 0x00, 0xAD, 0x20, 0x00]
+    );
+    assert_eq!(
+        image("zp = $10\nLDA zp\nLDA !zp"),
+        [0xA5, 0x10, 0xAD, 0x10, 0x00]
+    );
+}
+
+#[test]
+fn expressions() {
+    assert_eq!(
+        image(
+            "
+            * = $1234
+            value = 3
+            here
+            .byt <here, >here, <(here+$100), value*2+1, %1010, 'A', -1
+            .byt (1+2)*3, 1<<4, $F0>>4, $FF&$0F|$30, $0F^$FF, ~0&$FF, 10/3
+            .word here, *
+            "
+        ),
+        [
+            0x34, 0x12, 0x34, 0x07, 0x0A, 0x41, 0xFF, 0x09, 0x10, 0x0F, 0x3F, 0xF0, 0xFF, 0x03,
+            0x34, 0x12, 0x42, 0x12,
+        ]
+    );
+}
+
+#[test]
+fn data_directives() {
+    assert_eq!(
+        image(".byt \"Hi; there\", 0 ; comment\n.dsb 3, $EA\n.dsb 2\n.word $1234"),
+        [
+            b'H', b'i', b';', b' ', b't', b'h', b'e', b'r', b'e', 0, 0xEA, 0xEA, 0xEA, 0, 0, 0x34,
+            0x12
+        ]
+    );
+}
+
+#[test]
+fn origin_keeps_the_image_contiguous() {
+    // the xa idiom used by the examples to fill the space up to the vectors
+    let assembly = assemble(
+        "
+        * = $FFF0
+        start NOP
+        end
+        * = $FFFC
+        .dsb (*-end), 0
+        * = $FFFC
+        .word start
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(assembly.origin, 0xFFF0);
+    assert_eq!(assembly.image.len(), 14);
+    assert_eq!(assembly.image[12..], [0xF0, 0xFF]);
+}
+
+#[test]
+fn cmos_instructions() {
+    let assembly = assemble_variant(
+        "* = $0200\nloop BBR3 $10,loop\nSTZ $10\nLDA ($10)\nJMP ($1234,X)",
+        Variant::Wdc65C02,
+        false,
+    )
+    .unwrap();
+
+    assert_eq!(
+        assembly.image,
+        [0x3F, 0x10, 0xFD, 0x64, 0x10, 0xB2, 0x10, 0x7C, 0x34, 0x12]
+    );
+    assert!(matches!(
+        error("STZ $10"),
+        (1, ErrorKind::UnknownMnemonic(_))
+    ));
+}
+
+#[test]
+fn errors() {
+    assert!(
+        matches!(error("NOP\nFOO $10"), (2, ErrorKind::UnknownMnemonic(name)) if name == "FOO")
+    );
+    assert!(
+        matches!(error("LDA missing"), (1, ErrorKind::UndefinedSymbol(name)) if name == "missing")
+    );
+    assert!(matches!(
+        error("a NOP\na NOP"),
+        (2, ErrorKind::DuplicateSymbol(_))
+    ));
+    assert!(matches!(
+        error("STX $1234,X"),
+        (1, ErrorKind::InvalidAddressing(..))
+    ));
+    assert!(matches!(
+        error("LDA #$100"),
+        (1, ErrorKind::ValueOutOfRange(0x100))
+    ));
+    assert!(matches!(
+        error("here BNE here+200"),
+        (1, ErrorKind::BranchOutOfRange(198))
+    ));
+    assert!(matches!(
+        error(".foo 1"),
+        (1, ErrorKind::UnknownDirective(_))
+    ));
+    assert!(matches!(error("LDA #(1"), (1, ErrorKind::Syntax(_))));
+    assert!(matches!(
+        error("* = $8000\n.dsb $7fffffffffff, 0"),
+        (2, ErrorKind::ValueOutOfRange(0x7FFF_FFFF_FFFF))
+    ));
+    assert!(matches!(
+        error(".dsb $8001\n.dsb $8001"),
+        (2, ErrorKind::ProgramTooLarge)
+    ));
+}
+
+#[test]
+fn runs_the_fibonacci_example() {
+    let source = std::fs::read_to_string("examples/fibonacci.s").unwrap();
+    let assembly = assemble(&source).unwrap();
+
+    let mut memory = StackMemory::new();
+    assembly.load_into(&mut memory);
+
+    let mut cpu = Cpu::load_memory(memory);
+    while cpu.tick().is_ok() {}
+
+    assert_eq!(cpu.accumulator, 55);
+}
@@ -0,0 +1,184 @@
+use cpu6502::{
+    bus::{
+        device::{Device, Ram, Rom},
+        Bus, DEFAULT_OPEN_BUS,
+    },
+    cpu::{addressable_bus::DataBus, Cpu},
+};
+use std::{cell::RefCell, rc::Rc};
+
+/// Latches the last byte written to it, and reads back its complement
+#[derive(Default)]
+struct Latch(u8);
+
+impl Device for Latch {
+    fn peek(&self, _offset: u16) -> u8 {
+        !self.0
+    }
+
+    fn set(&mut self, _offset: u16, x: u8) {
+        self.0 = x
+    }
+}
+
+/// A status register acknowledged by reading it, like many I/O chips have
+#[derive(Default)]
+struct Status {
+    pending: u8,
+    reads: usize,
+}
+
+impl Device for Status {
+    fn peek(&self, _offset: u16) -> u8 {
+        self.pending
+    }
+
+    fn set(&mut self, _offset: u16, x: u8) {
+        self.pending |= x
+    }
+
+    fn read(&mut self, _offset: u16) -> u8 {
+        self.reads += 1;
+        std::mem::take(&mut self.pending)
+    }
+}
+
+#[test]
+fn unmapped_reads_return_open_bus() {
+    let mut bus = Bus::new();
+    bus.set(0x1234, 0x42);
+
+    assert_eq!(bus.peek(0x1234), DEFAULT_OPEN_BUS);
+    assert_eq!(Bus::with_open_bus(0x00).peek(0x1234), 0x00);
+}
+
+#[test]
+fn devices_get_offsets_from_their_range() {
+    let mut bus = Bus::new();
+    bus.map(0x4000..=0x40FF, Ram::new(0x100));
+    bus.set(0x4010, 0x42);
+
+    assert_eq!(bus.peek(0x4010), 0x42);
+    assert_eq!(bus.peek(0x3FFF), DEFAULT_OPEN_BUS);
+    assert_eq!(bus.peek(0x4100), DEFAULT_OPEN_BUS);
+}
+
+#[test]
+fn mirrored_regions() {
+    let mut bus = Bus::new();
+    bus.map_mirrored(0x0000..=0x1FFF, 0x07FF, Ram::new(0x800));
+    bus.set(0x0001, 0x42);
+
+    assert_eq!(bus.peek(0x0801), 0x42);
+    assert_eq!(bus.peek(0x1001), 0x42);
+    assert_eq!(bus.peek(0x1801), 0x42);
+}
+
+#[test]
+fn rom_is_read_only() {
+    let mut bus = Bus::new();
+    bus.map(0xC000..=0xFFFF, Rom::new(vec![0xEA; 0x4000]));
+    bus.set(0xC000, 0x42);
+
+    assert_eq!(bus.peek(0xC000), 0xEA);
+}
+
+#[test]
+#[should_panic(expected = "ram needs at least one byte")]
+fn ram_cant_be_empty() {
+    Ram::new(0);
+}
+
+#[test]
+#[should_panic(expected = "rom needs at least one byte")]
+fn rom_cant_be_empty() {
+    Rom::new(vec![]);
+}
+
+#[test]
+fn later_mappings_win() {
+    let latch = Rc::new(RefCell::new(Latch::default()));
+
+    let mut bus = Bus::new();
+    bus.map(0x0000..=0xFFFF, Ram::new(0x10000));
+    bus.map(0x6000..=0x6000, latch.clone());
+
+    bus.set(0x6000, 0x0F);
+    bus.set(0x6001, 0x0F);
+
+    assert_eq!(latch.borrow().0, 0x0F);
+    assert_eq!(bus.peek(0x6000), 0xF0);
+    assert_eq!(bus.peek(0x6001), 0x0F);
+}
+
+#[test]
+fn runs_a_program() {
+    let mut rom = vec![0; 0x1000];
+    // LDA #$42; STA $0800 (a mirror of $0000); STA $6000
+    rom[..8].copy_from_slice(&[0xA9, 0x42, 0x8D, 0x00, 0x08, 0x8D, 0x00, 0x60]);
+    rom[0xFFC] = 0x00;
+    rom[0xFFD] = 0xF0;
+
+    let latch = Rc::new(RefCell::new(Latch::default()));
+
+    let mut bus = Bus::new();
+    bus.map_mirrored(0x0000..=0x1FFF, 0x07FF, Ram::new(0x800));
+    bus.map(0x6000..=0x6000, latch.clone());
+    bus.map(0xF000..=0xFFFF, Rom::new(rom));
+
+    let mut cpu = Cpu::load_memory(bus);
+    for _ in 0..3 {
+        cpu.tick().unwrap();
+    }
+
+    assert_eq!(cpu.bus.peek(0x0000), 0x42);
+    assert_eq!(latch.borrow().0, 0x42);
+}
+
+#[test]
+fn peeking_has_no_side_effects() {
+    let status = Rc::new(RefCell::new(Status::default()));
+
+    let mut bus = Bus::new();
+    bus.map(0xD000..=0xD000, status.clone());
+    bus.set(0xD000, 0x81);
+
+    assert_eq!(bus.peek(0xD000), 0x81);
+    assert_eq!(bus.read(0xD000), 0x81);
+    assert_eq!(bus.peek(0xD000), 0x00);
+    assert_eq!(status.borrow().reads, 1);
+}
+
+#[test]
+fn instructions_read_their_operand_once() {
+    let mut rom = vec![0; 0x1000];
+    // LDA $D000; LDA ($10),Y; INC $D000
+    rom[..7].copy_from_slice(&[0xAD, 0x00, 0xD0, 0xB1, 0x10, 0xEE, 0x00]);
+    rom[7] = 0xD0;
+    rom[0xFFC] = 0x00;
+    rom[0xFFD] = 0xF0;
+
+    let status = Rc::new(RefCell::new(Status::default()));
+
+    let mut bus = Bus::new();
+    bus.map(0x0000..=0x07FF, Ram::new(0x800));
+    bus.map(0xD000..=0xD000, status.clone());
+    bus.map(0xF000..=0xFFFF, Rom::new(rom));
+    bus.set(0x0010, 0x00);
+    bus.set(0x0011, 0xD0);
+
+    let mut cpu = Cpu::load_memory(bus);
+    cpu.bus.set(0xD000, 0x42);
+
+    cpu.tick().unwrap();
+    assert_eq!(cpu.accumulator, 0x42);
+    assert_eq!(status.borrow().reads, 1);
+
+    cpu.bus.set(0xD000, 0x24);
+    cpu.tick().unwrap();
+    assert_eq!(cpu.accumulator, 0x24);
+    assert_eq!(status.borrow().reads, 2);
+
+    cpu.tick().unwrap();
+    assert_eq!(status.borrow().reads, 3);
+}
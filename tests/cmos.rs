@@ -0,0 +1,226 @@
+use cpu6502::{
+    assembler::assemble_variant,
+    cpu::{addressable_bus::DataBus, error::CpuError, status::StatusFlag, variant::Variant, Cpu},
+    stack_memory::StackMemory,
+};
+
+/// Assembles `source` at $8000 for `variant`, ready to run from there
+fn load(source: &str, variant: Variant) -> Cpu<StackMemory> {
+    let assembly = assemble_variant(source, variant, false).unwrap();
+    let mut memory = StackMemory::new();
+    assembly.load_into(&mut memory);
+
+    let mut cpu = Cpu::with_variant(memory, variant);
+    cpu.program_counter = assembly.origin;
+    cpu.stack_pointer = 0xFF;
+
+    cpu
+}
+
+/// Runs a 65C02 program until it executes STP
+fn run(source: &str) -> Cpu<StackMemory> {
+    let mut cpu = load(source, Variant::Wdc65C02);
+
+    for _ in 0..1000 {
+        match cpu.tick() {
+            Ok(_) => (),
+            Err(CpuError::Stopped) => return cpu,
+            Err(err) => panic!("{} at ${:04X}", err, cpu.program_counter),
+        }
+    }
+
+    panic!("the program never stopped");
+}
+
+#[test]
+fn stores_zero_and_moves_the_index_registers_through_the_stack() {
+    let cpu = run("
+* = $8000
+        lda #$ff
+        sta $10
+        sta $0234
+        ldx #$42
+        stz $10
+        stz $0200,x
+        phx
+        ply
+        bra skip
+        lda #$01
+skip    stp
+");
+
+    assert_eq!(cpu.bus.peek(0x10), 0x00);
+    assert_eq!(cpu.bus.peek(0x0242), 0x00);
+    assert_eq!(cpu.bus.peek(0x0234), 0xFF);
+    assert_eq!(cpu.y_register, 0x42);
+    assert_eq!(cpu.accumulator, 0xFF);
+    assert_eq!(cpu.stack_pointer, 0xFF);
+}
+
+#[test]
+fn sets_resets_and_branches_on_bits() {
+    let cpu = run("
+* = $8000
+        lda #$ff
+        sta $20
+        rmb3 $20
+        smb0 $21
+        bbr3 $20,clear
+        ldx #$01
+clear   bbs0 $21,set
+        ldy #$01
+set     bbs3 $20,wrong
+        bbr0 $21,wrong
+        stp
+wrong   lda #$00
+        stp
+");
+
+    assert_eq!(cpu.bus.peek(0x20), 0xF7);
+    assert_eq!(cpu.bus.peek(0x21), 0x01);
+    assert_eq!(
+        (cpu.accumulator, cpu.x_register, cpu.y_register),
+        (0xFF, 0, 0)
+    );
+}
+
+#[test]
+fn tests_and_resets_or_sets_bits() {
+    let cpu = run("
+* = $8000
+        lda #$ff
+        sta $30
+        lda #$10
+        sta $31
+        lda #$0f
+        trb $30
+        php
+        tsb $31
+        stp
+");
+
+    assert_eq!(cpu.bus.peek(0x30), 0xF0);
+    assert_eq!(cpu.bus.peek(0x31), 0x1F);
+    // Z reflects A AND memory before the change: set by TSB, clear after TRB
+    assert!(cpu.processor_status.get_flag(StatusFlag::Zero));
+    assert_eq!(cpu.bus.peek(0x01FF) & StatusFlag::Zero as u8, 0);
+}
+
+#[test]
+fn decimal_mode_sets_n_and_z_from_the_result() {
+    let source = "
+* = $8000
+        sed
+        clc
+        lda #$99
+        adc #$01
+";
+
+    let mut cmos = load(source, Variant::Wdc65C02);
+    let mut nmos = load(source, Variant::Nmos6502);
+    for _ in 0..3 {
+        cmos.tick().unwrap();
+        nmos.tick().unwrap();
+    }
+
+    // the extra cycle is spent fixing up the flags
+    assert_eq!(cmos.tick().unwrap().cycles, 3);
+    assert_eq!(nmos.tick().unwrap().cycles, 2);
+
+    for cpu in [&cmos, &nmos] {
+        assert_eq!(cpu.accumulator, 0x00);
+        assert!(cpu.processor_status.get_flag(StatusFlag::Carry));
+    }
+
+    assert!(cmos.processor_status.get_flag(StatusFlag::Zero));
+    assert!(!cmos.processor_status.get_flag(StatusFlag::Negative));
+    // the NMOS flags follow the binary sum $9A
+    assert!(!nmos.processor_status.get_flag(StatusFlag::Zero));
+    assert!(nmos.processor_status.get_flag(StatusFlag::Negative));
+}
+
+#[test]
+fn indirect_jumps_cross_pages() {
+    let source = "
+* = $8000
+        jmp ($10ff)
+";
+
+    for (variant, target) in [(Variant::Wdc65C02, 0x5634), (Variant::Nmos6502, 0x1234)] {
+        let mut cpu = load(source, variant);
+        cpu.bus.set(0x10FF, 0x34);
+        cpu.bus.set(0x1000, 0x12);
+        cpu.bus.set(0x1100, 0x56);
+
+        let tick = cpu.tick().unwrap();
+        assert_eq!(cpu.program_counter, target);
+        assert_eq!(tick.cycles, if variant.is_cmos() { 6 } else { 5 });
+    }
+
+    let mut cpu = load("* = $8000\n ldx #$02\n jmp ($9000,x)", Variant::Wdc65C02);
+    cpu.bus.set_word(0x9002, 0xABCD);
+    cpu.tick().unwrap();
+    cpu.tick().unwrap();
+    assert_eq!(cpu.program_counter, 0xABCD);
+}
+
+#[test]
+fn waits_for_interrupts_and_stops() {
+    let mut cpu = load(
+        "
+* = $8000
+        sei
+        wai
+        cli
+        wai
+        stp
+",
+        Variant::Wdc65C02,
+    );
+    cpu.bus.set_word(0xFFFE, 0x9000);
+
+    cpu.tick().unwrap();
+    for _ in 0..3 {
+        cpu.tick().unwrap();
+        assert_eq!(cpu.program_counter, 0x8001);
+    }
+
+    // a masked IRQ wakes the cpu up without being serviced
+    cpu.set_irq(true);
+    cpu.tick().unwrap();
+    assert_eq!(cpu.program_counter, 0x8002);
+    cpu.set_irq(false);
+
+    cpu.tick().unwrap();
+    cpu.tick().unwrap();
+    assert_eq!(cpu.program_counter, 0x8003);
+
+    cpu.set_irq(true);
+    cpu.tick().unwrap();
+    assert_eq!(cpu.program_counter, 0x9000);
+
+    cpu.set_irq(false);
+    cpu.program_counter = 0x8004;
+    assert!(matches!(cpu.tick(), Err(CpuError::Stopped)));
+    assert!(matches!(cpu.tick(), Err(CpuError::Stopped)));
+    assert_eq!(cpu.program_counter, 0x8004);
+}
+
+#[test]
+fn undefined_opcodes_are_nops() {
+    let mut cpu = load("* = $8000", Variant::Wdc65C02);
+    // a one byte, one cycle NOP, a two byte one and the eight cycle $5C
+    cpu.bus
+        .load_data(0x8000, &[0x03, 0x02, 0xFF, 0x5C, 0x34, 0x12, 0xA7, 0x10]);
+    cpu.accumulator = 0x55;
+
+    assert_eq!(cpu.tick().unwrap().cycles, 1);
+    assert_eq!(cpu.tick().unwrap().cycles, 2);
+    assert_eq!(cpu.tick().unwrap().cycles, 8);
+    assert_eq!(cpu.program_counter, 0x8006);
+
+    // $A7 is SMB2 rather than the undocumented LAX
+    cpu.tick().unwrap();
+    assert_eq!(cpu.bus.peek(0x10), 0x04);
+    assert_eq!(cpu.accumulator, 0x55);
+}
@@ -0,0 +1,20 @@
+use cpu6502::cpu::addressable_bus::DataBus;
+
+/// A flat bus spanning the whole address space
+pub struct Ram(pub Vec<u8>);
+
+impl Ram {
+    pub fn new() -> Self {
+        Self(vec![0; 0x10000])
+    }
+}
+
+impl DataBus for Ram {
+    fn peek(&self, addr: u16) -> u8 {
+        self.0[addr as usize]
+    }
+
+    fn set(&mut self, addr: u16, x: u8) {
+        self.0[addr as usize] = x
+    }
+}
@@ -0,0 +1,200 @@
+use cpu6502::dap::{
+    self,
+    protocol::{read_message, write_message},
+};
+use serde_json::{json, Value};
+use std::io::Cursor;
+
+const PROGRAM: &str = "examples/fibonacci.s";
+
+/// Serves `requests` in order, returning everything the server sent back
+fn session(requests: &[(&str, Value)]) -> Vec<Value> {
+    let mut input = Vec::new();
+    for (seq, (command, arguments)) in requests.iter().enumerate() {
+        let request = json!({
+            "seq": seq + 1,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        });
+        write_message(&mut input, &request).unwrap();
+    }
+
+    let mut output = Vec::new();
+    dap::serve(Cursor::new(input), &mut output).unwrap();
+
+    let mut output = Cursor::new(output);
+    let mut messages = Vec::new();
+    while let Some(message) = read_message(&mut output).unwrap() {
+        messages.push(message);
+    }
+
+    messages
+}
+
+fn launch(stop_on_entry: bool) -> Vec<(&'static str, Value)> {
+    vec![
+        ("initialize", json!({ "adapterID": "cpu6502" })),
+        (
+            "launch",
+            json!({ "program": PROGRAM, "stopOnEntry": stop_on_entry }),
+        ),
+    ]
+}
+
+fn response<'a>(messages: &'a [Value], command: &str) -> &'a Value {
+    messages
+        .iter()
+        .find(|message| message["type"] == "response" && message["command"] == command)
+        .unwrap()
+}
+
+fn events<'a>(messages: &'a [Value], event: &str) -> Vec<&'a Value> {
+    messages
+        .iter()
+        .filter(|message| message["type"] == "event" && message["event"] == event)
+        .collect()
+}
+
+#[test]
+fn stops_on_entry() {
+    let mut requests = launch(true);
+    requests.push(("configurationDone", json!({})));
+    requests.push(("disconnect", json!({})));
+
+    let messages = session(&requests);
+
+    assert_eq!(
+        response(&messages, "initialize")["body"]["supportsConfigurationDoneRequest"],
+        true
+    );
+    assert_eq!(response(&messages, "launch")["success"], true);
+    assert_eq!(events(&messages, "initialized").len(), 1);
+    assert_eq!(events(&messages, "stopped")[0]["body"]["reason"], "entry");
+}
+
+#[test]
+fn stops_at_source_breakpoints() {
+    let mut requests = launch(false);
+    requests.push((
+        "setBreakpoints",
+        json!({ "source": { "path": PROGRAM }, "breakpoints": [{ "line": 20 }] }),
+    ));
+    requests.push(("configurationDone", json!({})));
+    requests.push(("stackTrace", json!({ "threadId": 1 })));
+    requests.push(("disconnect", json!({})));
+
+    let messages = session(&requests);
+
+    // the breakpoint moves down to the next instruction
+    let breakpoint = &response(&messages, "setBreakpoints")["body"]["breakpoints"][0];
+    assert_eq!(breakpoint["verified"], true);
+    assert_eq!(breakpoint["line"], 21);
+    assert_eq!(breakpoint["instructionReference"], "0x8010");
+
+    assert_eq!(
+        events(&messages, "stopped")[0]["body"]["reason"],
+        "breakpoint"
+    );
+
+    let frame = &response(&messages, "stackTrace")["body"]["stackFrames"][0];
+    assert_eq!(frame["line"], 21);
+    assert_eq!(frame["instructionPointerReference"], "0x8010");
+}
+
+#[test]
+fn shows_registers_and_memory() {
+    let mut requests = launch(true);
+    requests.push(("configurationDone", json!({})));
+    requests.push(("stepIn", json!({ "threadId": 1 })));
+    requests.push(("variables", json!({ "variablesReference": 1 })));
+    requests.push((
+        "readMemory",
+        json!({ "memoryReference": "0x8000", "count": 3 }),
+    ));
+    requests.push(("disconnect", json!({})));
+
+    let messages = session(&requests);
+
+    let stops = events(&messages, "stopped");
+    assert_eq!(stops[1]["body"]["reason"], "step");
+
+    let registers = &response(&messages, "variables")["body"]["variables"];
+    assert_eq!(
+        registers[1],
+        json!({ "name": "X", "value": "$01", "variablesReference": 0 })
+    );
+
+    // LDX #$01, STX...
+    assert_eq!(response(&messages, "readMemory")["body"]["data"], "ogGG");
+}
+
+#[test]
+fn disassembles_around_an_address() {
+    let mut requests = launch(true);
+    requests.push((
+        "disassemble",
+        json!({
+            "memoryReference": "0x8002",
+            "instructionOffset": -1,
+            "instructionCount": 3,
+        }),
+    ));
+    requests.push(("disconnect", json!({})));
+
+    let messages = session(&requests);
+    let instructions = &response(&messages, "disassemble")["body"]["instructions"];
+
+    assert_eq!(instructions[0]["address"], "0x8000");
+    assert_eq!(instructions[0]["instruction"], "LDX #$01");
+    assert_eq!(instructions[1]["instruction"], "STX $00");
+    assert_eq!(instructions[2]["instruction"], "SEC");
+    assert_eq!(instructions[2]["line"], 11);
+}
+
+#[test]
+fn bounds_disassemble_requests() {
+    let mut requests = launch(true);
+    requests.push((
+        "disassemble",
+        json!({
+            "memoryReference": "0x8000",
+            "instructionOffset": i64::MIN,
+            "instructionCount": i64::MAX,
+        }),
+    ));
+    requests.push(("disconnect", json!({})));
+
+    let messages = session(&requests);
+    let response = response(&messages, "disassemble");
+
+    assert_eq!(response["success"], true);
+    assert_eq!(
+        response["body"]["instructions"].as_array().unwrap().len(),
+        0x10000
+    );
+}
+
+#[test]
+fn sets_registers_within_their_range() {
+    let mut requests = launch(true);
+    requests.push((
+        "setVariable",
+        json!({ "variablesReference": 1, "name": "A", "value": "$100" }),
+    ));
+    requests.push((
+        "setVariable",
+        json!({ "variablesReference": 1, "name": "PC", "value": "$9000" }),
+    ));
+    requests.push(("disconnect", json!({})));
+
+    let messages = session(&requests);
+    let responses: Vec<&Value> = messages
+        .iter()
+        .filter(|message| message["command"] == "setVariable")
+        .collect();
+
+    assert_eq!(responses[0]["success"], false);
+    assert_eq!(responses[0]["message"], "Value 256 is out of range");
+    assert_eq!(responses[1]["body"]["value"], "$9000");
+}
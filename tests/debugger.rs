@@ -0,0 +1,140 @@
+use cpu6502::{
+    assembler::assemble,
+    cpu::Cpu,
+    debugger::{
+        repl,
+        watch::{Access, Watched, Watchpoint},
+        Debugger, Stop,
+    },
+    stack_memory::StackMemory,
+};
+
+const PROGRAM: &str = "
+* = $8000
+reset   ldx #$ff
+        txs
+        jsr double
+        jsr double
+        sta $0200
+        .byt $22
+
+double  jsr twice
+        rts
+twice   asl
+        asl
+        rts
+";
+
+fn debugger() -> Debugger<StackMemory> {
+    let assembly = assemble(PROGRAM).unwrap();
+    let mut memory = StackMemory::new();
+    assembly.load_into(&mut memory);
+
+    let mut cpu = Cpu::load_memory(Watched::new(memory));
+    cpu.program_counter = assembly.origin;
+    cpu.accumulator = 1;
+
+    Debugger::new(cpu)
+}
+
+#[test]
+fn stops_at_breakpoints() {
+    let mut debugger = debugger();
+    debugger.breakpoints.insert(0x8009);
+
+    assert_eq!(debugger.cont().unwrap(), Stop::Breakpoint(0x8009));
+    assert_eq!(debugger.cpu.accumulator, 16);
+}
+
+#[test]
+fn steps_a_number_of_instructions() {
+    let mut debugger = debugger();
+
+    assert_eq!(debugger.step(3).unwrap(), Stop::Done);
+    assert_eq!(debugger.cpu.program_counter, 0x800D);
+}
+
+#[test]
+fn next_runs_over_subroutines() {
+    let mut debugger = debugger();
+    debugger.step(2).unwrap();
+
+    assert_eq!(debugger.step_over().unwrap(), Stop::Done);
+    assert_eq!(debugger.cpu.program_counter, 0x8006);
+    assert_eq!(debugger.cpu.accumulator, 4);
+}
+
+#[test]
+fn finish_returns_from_the_current_subroutine() {
+    let mut debugger = debugger();
+    debugger.step(3).unwrap();
+
+    assert_eq!(debugger.finish().unwrap(), Stop::Done);
+    assert_eq!(debugger.cpu.program_counter, 0x8006);
+}
+
+#[test]
+fn watchpoints_stop_on_access() {
+    let mut debugger = debugger();
+    debugger.cpu.bus.watchpoints.push(Watchpoint {
+        range: 0x0200..=0x02FF,
+        read: false,
+        write: true,
+    });
+
+    assert_eq!(
+        debugger.cont().unwrap(),
+        Stop::Watchpoint(Access {
+            address: 0x0200,
+            value: 16,
+            write: true,
+        })
+    );
+}
+
+#[test]
+fn repl_commands() {
+    let mut debugger = debugger();
+    let input = b"break $8009\ncontinue\nset a=$42\nmem $8000 3\nbogus\n";
+    let mut output = Vec::new();
+
+    repl::run(&mut debugger, &input[..], &mut output).unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("Breakpoint at $8009\n8009  "));
+    assert!(output.contains("8000  A2 FF 9A\n"));
+    assert!(output.contains("Unknown command bogus"));
+    assert_eq!(debugger.cpu.accumulator, 0x42);
+}
+
+#[test]
+fn empty_lines_only_repeat_stepping_and_inspecting() {
+    let mut expected = debugger();
+    expected.step(6).unwrap();
+
+    let mut debugger = debugger();
+    let input = b"step 3\n\nset pc=*+1\n\n";
+    repl::run(&mut debugger, &input[..], Vec::new()).unwrap();
+
+    assert_eq!(
+        debugger.cpu.program_counter,
+        expected.cpu.program_counter + 1
+    );
+}
+
+#[test]
+fn saves_and_loads_states() {
+    let path = std::env::temp_dir().join(format!("debugger-{}.sav", std::process::id()));
+    let path = path.to_str().unwrap();
+
+    let mut debugger = debugger();
+    let input = format!("step 3\nsave {0}\ncontinue\nload {0}\n", path);
+    let mut output = Vec::new();
+
+    repl::run(&mut debugger, input.as_bytes(), &mut output).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    // back inside the first call to double
+    assert_eq!(debugger.cpu.program_counter, 0x800D);
+    assert_eq!(debugger.cpu.accumulator, 1);
+}
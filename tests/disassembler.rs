@@ -0,0 +1,102 @@
+use cpu6502::{
+    cpu::{
+        instruction::{Addressing, Instruction},
+        variant::Variant,
+    },
+    disassembler::{disassemble, disassemble_variant},
+    stack_memory::StackMemory,
+};
+
+fn decode(bytes: &[u8]) -> Instruction {
+    let mut operand = bytes[1..].iter().copied();
+
+    Instruction::read_variant(Variant::Wdc65C02, false, bytes[0], || {
+        operand.next().unwrap()
+    })
+    .unwrap()
+}
+
+#[test]
+fn operand_syntax() {
+    let cases: &[(&[u8], &str)] = &[
+        (&[0xEA], "NOP"),
+        (&[0x0A], "ASL A"),
+        (&[0xA9, 0x01], "LDA #$01"),
+        (&[0xA5, 0x10], "LDA $10"),
+        (&[0xB5, 0x10], "LDA $10,X"),
+        (&[0xB6, 0x10], "LDX $10,Y"),
+        (&[0xAD, 0x34, 0x12], "LDA $1234"),
+        (&[0xBD, 0x34, 0x12], "LDA $1234,X"),
+        (&[0xB9, 0x34, 0x12], "LDA $1234,Y"),
+        (&[0x6C, 0x34, 0x12], "JMP ($1234)"),
+        (&[0x81, 0x10], "STA ($10,X)"),
+        (&[0x91, 0x10], "STA ($10),Y"),
+        (&[0xB2, 0x10], "LDA ($10)"),
+        (&[0x7C, 0x34, 0x12], "JMP ($1234,X)"),
+    ];
+
+    for (bytes, text) in cases {
+        assert_eq!(decode(bytes).to_string(), *text);
+    }
+}
+
+#[test]
+fn branch_targets() {
+    let bne = decode(&[0xD0, 0xFC]);
+    assert_eq!(bne.to_string(), "BNE *-2");
+    assert_eq!(bne.display_at(0x8004).to_string(), "BNE $8002");
+    assert_eq!(bne.addressing.branch_target(0x0000), Some(0xFFFE));
+
+    let bbr = decode(&[0x0F, 0x10, 0x05]);
+    assert_eq!(bbr.to_string(), "BBR0 $10,*+8");
+    assert_eq!(bbr.display_at(0x8000).to_string(), "BBR0 $10,$8008");
+
+    assert_eq!(Addressing::Absolute(0x1234).branch_target(0x8000), None);
+}
+
+#[test]
+fn listing() {
+    let mut memory = StackMemory::new();
+    memory.load_data(0x8000, &[0xA2, 0x00, 0xCA, 0xD0, 0xFD, 0x02, 0x60]);
+
+    let listing: Vec<String> = disassemble(&memory, 0x8000, 0x8006)
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+
+    assert_eq!(
+        listing,
+        [
+            "8000  A2 00     LDX #$00",
+            "8002  CA        DEX",
+            "8003  D0 FD     BNE $8002",
+            "8005  02        .byt $02",
+            "8006  60        RTS",
+        ]
+    );
+}
+
+#[test]
+fn listing_follows_the_variant() {
+    let mut memory = StackMemory::new();
+    memory.load_data(0x8000, &[0xA7, 0x10]);
+
+    let legal = disassemble(&memory, 0x8000, 0x8001);
+    assert_eq!(legal.len(), 2);
+    assert!(legal[0].instruction.is_none());
+
+    let undocumented = disassemble_variant(&memory, 0x8000, 0x8001, Variant::Nmos6502, true);
+    assert_eq!(undocumented.len(), 1);
+    assert_eq!(undocumented[0].to_string(), "8000  A7 10     LAX $10");
+}
+
+#[test]
+fn listing_stops_at_the_end_of_memory() {
+    let mut memory = StackMemory::new();
+    memory.load_data(0xFFFE, &[0xAD, 0x34]);
+
+    let lines = disassemble(&memory, 0xFFFE, 0xFFFF);
+
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].bytes, [0xAD, 0x34, 0x00]);
+}
@@ -0,0 +1,112 @@
+use cpu6502::cpu::{
+    instruction::{Addressing, Instruction, InstructionType},
+    variant::Variant,
+};
+
+/// Every opcode, decoded with some operand, must encode back to
+/// an instruction which decodes the same way
+fn round_trip(variant: Variant, undocumented: bool) {
+    for opcode in 0..=0xFF {
+        let mut operand = [0x34, 0x12].iter().copied();
+        let decoded = match Instruction::read_variant(variant, undocumented, opcode, || {
+            operand.next().unwrap()
+        }) {
+            Some(decoded) => decoded,
+            None => continue,
+        };
+
+        let bytes = Instruction::encode_variant(
+            variant,
+            undocumented,
+            decoded.instruction_type,
+            decoded.addressing,
+        )
+        .unwrap();
+
+        let mut operand = bytes[1..].iter().copied();
+        let encoded =
+            Instruction::read_variant(variant, undocumented, bytes[0], || operand.next().unwrap())
+                .unwrap();
+
+        assert_eq!(
+            (encoded.instruction_type, encoded.addressing),
+            (decoded.instruction_type, decoded.addressing),
+            "opcode 0x{:02X}",
+            opcode
+        );
+        assert_eq!(bytes.len() as u16, decoded.size());
+    }
+}
+
+#[test]
+fn legal_round_trip() {
+    round_trip(Variant::Nmos6502, false);
+}
+
+#[test]
+fn undocumented_round_trip() {
+    round_trip(Variant::Nmos6502, true);
+}
+
+#[test]
+fn cmos_round_trip() {
+    round_trip(Variant::Wdc65C02, false);
+}
+
+#[test]
+fn operands_are_little_endian() {
+    assert_eq!(
+        Instruction::encode(InstructionType::LDA, Addressing::Immediate(0x01)).unwrap(),
+        [0xA9, 0x01]
+    );
+    assert_eq!(
+        Instruction::encode(InstructionType::STA, Addressing::AbsoluteX(0x1234)).unwrap(),
+        [0x9D, 0x34, 0x12]
+    );
+    assert_eq!(
+        Instruction::encode(InstructionType::ASL, Addressing::Accumulator).unwrap(),
+        [0x0A]
+    );
+    assert_eq!(
+        Instruction::encode_variant(
+            Variant::Wdc65C02,
+            false,
+            InstructionType::BBS7,
+            Addressing::ZeropageRelative(0x10, 0xFD)
+        )
+        .unwrap(),
+        [0xFF, 0x10, 0xFD]
+    );
+}
+
+#[test]
+fn legal_opcodes_are_preferred() {
+    let nop = Instruction::encode_variant(
+        Variant::Nmos6502,
+        true,
+        InstructionType::NOP,
+        Addressing::Implied,
+    );
+
+    assert_eq!(nop.unwrap(), [0xEA]);
+}
+
+#[test]
+fn illegal_combinations() {
+    let stx = Instruction::encode(InstructionType::STX, Addressing::AbsoluteX(0x1234));
+    assert_eq!(
+        stx.unwrap_err().to_string(),
+        "STX $1234,X is not a valid instruction"
+    );
+
+    assert!(Instruction::encode(InstructionType::LDA, Addressing::Implied).is_err());
+    assert!(Instruction::encode(InstructionType::LAX, Addressing::Zeropage(0x10)).is_err());
+    assert!(Instruction::encode(InstructionType::STZ, Addressing::Zeropage(0x10)).is_err());
+    assert!(Instruction::encode_variant(
+        Variant::Nmos6502,
+        true,
+        InstructionType::LAX,
+        Addressing::Zeropage(0x10)
+    )
+    .is_ok());
+}
@@ -0,0 +1,227 @@
+//! Runs whole test programs in the style of Klaus Dormann's 6502 test suite. They end
+//! in a self-loop, either on the check that failed or on their success address.
+//!
+//! The programs under `tests/programs` are written for the crate and built by its own
+//! assembler. Their opcodes are checked apart from it by the cases of `single_step.rs`.
+//! Point `DORMANN_TESTS` at the `bin_files` of a checkout of Klaus Dormann's
+//! 6502_65C02_functional_tests to run his binaries as well
+
+use cpu6502::{
+    assembler::assemble,
+    cpu::{variant::Variant, Cpu},
+    stack_memory::StackMemory,
+    symbols::Symbols,
+    trace,
+};
+use std::{collections::VecDeque, path::Path};
+
+/// Instructions shown when a program traps
+const HISTORY: usize = 20;
+
+/// Instructions after which a program is considered hung
+const LIMIT: usize = 100_000_000;
+
+/// Registers before an instruction, enough to trace it again
+struct Step {
+    program_counter: u16,
+    accumulator: u8,
+    x_register: u8,
+    y_register: u8,
+    stack_pointer: u8,
+    processor_status: u8,
+    cycles: u64,
+}
+
+/// A binary, where it gets loaded and started, and where it traps when it passes
+struct Program {
+    name: String,
+    binary: Vec<u8>,
+    load: u16,
+    start: u16,
+    success: u16,
+    variant: Variant,
+    /// name the checks in failure reports
+    symbols: Symbols,
+}
+
+/// Loads `name`.bin at `load` and checks that running it from there
+/// traps on the `success` label of its source
+fn run_checked_in(name: &str, load: u16) {
+    let path = format!("{}/tests/programs/{}", env!("CARGO_MANIFEST_DIR"), name);
+    let binary = std::fs::read(format!("{}.bin", path)).unwrap();
+
+    let source = std::fs::read_to_string(format!("{}.s", path)).unwrap();
+    let assembly = assemble(&source).unwrap();
+    assert!(
+        binary == assembly.image,
+        "{}.bin is out of date with its source",
+        name
+    );
+
+    run_program(Program {
+        name: name.into(),
+        binary,
+        load,
+        start: load,
+        success: assembly.symbols["success"],
+        variant: Variant::Nmos6502,
+        symbols: Symbols::from(&assembly),
+    });
+}
+
+/// The address of the `success` macro in an as65 listing of Dormann's tests
+fn dormann_success(listing: &str) -> Option<u16> {
+    listing
+        .lines()
+        .filter(|line| line.contains(";test passed"))
+        .find_map(|line| {
+            let (address, _) = line.split_once(" : ")?;
+            u16::from_str_radix(address.trim(), 16).ok()
+        })
+}
+
+fn run_program(program: Program) {
+    let Program {
+        name,
+        binary,
+        load,
+        start,
+        success,
+        variant,
+        symbols,
+    } = program;
+
+    let mut memory = StackMemory::new();
+    memory.load_data(load, &binary);
+
+    let mut cpu = Cpu::with_variant(memory, variant);
+    cpu.program_counter = start;
+
+    let mut history = VecDeque::with_capacity(HISTORY);
+
+    for count in 0..LIMIT {
+        let address = cpu.program_counter;
+
+        if history.len() == HISTORY {
+            history.pop_front();
+        }
+        history.push_back(Step {
+            program_counter: address,
+            accumulator: cpu.accumulator,
+            x_register: cpu.x_register,
+            y_register: cpu.y_register,
+            stack_pointer: cpu.stack_pointer,
+            processor_status: cpu.processor_status.0,
+            cycles: cpu.cycles,
+        });
+
+        let result = cpu.tick();
+
+        match result {
+            Ok(_) if cpu.program_counter != address => continue,
+            Ok(_) if address == success => return,
+            Ok(_) => panic!(
+                "{} trapped at {} after {} instructions\n{}",
+                name,
+                location(address, &symbols),
+                count,
+                replay(&mut cpu, &history, &symbols)
+            ),
+            Err(err) => panic!(
+                "{} failed at {}: {}\n{}",
+                name,
+                location(address, &symbols),
+                err,
+                replay(&mut cpu, &history, &symbols)
+            ),
+        }
+    }
+
+    panic!("{} didn't trap in {} instructions", name, LIMIT);
+}
+
+fn location(address: u16, symbols: &Symbols) -> String {
+    match symbols.describe(address) {
+        Some(name) => format!("{} (${:04X})", name, address),
+        None => format!("${:04X}", address),
+    }
+}
+
+/// Traces the last instructions again. Memory is the one after running them,
+/// so the operand values can be off
+fn replay(cpu: &mut Cpu<StackMemory>, history: &VecDeque<Step>, symbols: &Symbols) -> String {
+    let mut lines = Vec::new();
+
+    for step in history {
+        cpu.program_counter = step.program_counter;
+        cpu.accumulator = step.accumulator;
+        cpu.x_register = step.x_register;
+        cpu.y_register = step.y_register;
+        cpu.stack_pointer = step.stack_pointer;
+        cpu.processor_status = step.processor_status.into();
+        cpu.cycles = step.cycles;
+
+        lines.push(trace::line_with(cpu, symbols));
+    }
+
+    lines.join("\n")
+}
+
+#[test]
+fn functional_test() {
+    run_checked_in("functional", 0x0400);
+}
+
+#[test]
+fn decimal_test() {
+    run_checked_in("decimal", 0x0200);
+}
+
+/// Runs the binaries of Dormann's suite which have a listing giving their success
+/// address: whole memory images, loaded at $0000 and started from $0400
+#[test]
+fn dormann_tests() {
+    let directory = match std::env::var_os("DORMANN_TESTS") {
+        Some(directory) => directory,
+        None => return,
+    };
+
+    let mut ran = 0;
+    for entry in std::fs::read_dir(Path::new(&directory)).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("bin") {
+            continue;
+        }
+
+        let success = match std::fs::read_to_string(path.with_extension("lst")) {
+            Ok(listing) => dormann_success(&listing),
+            Err(_) => None,
+        };
+        let name = path.file_stem().unwrap().to_string_lossy().to_string();
+        let success = match success {
+            Some(success) => success,
+            None => {
+                println!("{}: no success address in its listing, skipped", name);
+                continue;
+            }
+        };
+
+        let variant = match name.starts_with("65C02") {
+            true => Variant::Wdc65C02,
+            false => Variant::Nmos6502,
+        };
+
+        run_program(Program {
+            name,
+            binary: std::fs::read(&path).unwrap(),
+            load: 0x0000,
+            start: 0x0400,
+            success,
+            variant,
+            symbols: Symbols::new(),
+        });
+        ran += 1;
+    }
+
+    assert!(ran > 0, "no binary with a listing in {:?}", directory);
+}
@@ -0,0 +1,136 @@
+use cpu6502::{
+    cpu::{addressable_bus::DataBus, Cpu},
+    gdb::GdbStub,
+    stack_memory::StackMemory,
+};
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+};
+
+struct Client(TcpStream);
+
+impl Client {
+    /// Sends `data` and returns the reply, without its framing
+    fn send(&mut self, data: &str) -> String {
+        let checksum = data.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        write!(self.0, "${}#{:02x}", data, checksum).unwrap();
+
+        self.reply()
+    }
+
+    fn reply(&mut self) -> String {
+        let mut reply = Vec::new();
+        let mut byte = [0];
+
+        while !reply.ends_with(b"#") {
+            self.0.read_exact(&mut byte).unwrap();
+            match (reply.is_empty(), byte[0]) {
+                (true, b'+') | (true, b'$') => (),
+                _ => reply.push(byte[0]),
+            }
+        }
+
+        let mut checksum = [0; 2];
+        self.0.read_exact(&mut checksum).unwrap();
+        reply.pop();
+
+        String::from_utf8(reply).unwrap()
+    }
+}
+
+/// Serves `program`, loaded at $8000, on a thread
+fn attach(program: &[u8]) -> (Client, thread::JoinHandle<Cpu<StackMemory>>) {
+    let mut memory = StackMemory::new();
+    for (i, &byte) in program.iter().enumerate() {
+        memory.set(0x8000 + i as u16, byte);
+    }
+
+    let mut cpu = Cpu::load_memory(memory);
+    cpu.program_counter = 0x8000;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let stub = thread::spawn(move || {
+        let mut stub = GdbStub::new(cpu);
+        let (stream, _) = listener.accept().unwrap();
+        stub.serve(stream).unwrap();
+
+        stub.debugger.cpu.map_bus(|bus| bus.inner)
+    });
+
+    (Client(TcpStream::connect(address).unwrap()), stub)
+}
+
+#[test]
+fn registers_and_memory() {
+    // LDA #$42
+    let (mut client, stub) = attach(&[0xA9, 0x42]);
+
+    assert_eq!(client.send("g"), "00000000000080");
+    assert_eq!(client.send("m8000,2"), "a942");
+
+    assert_eq!(client.send("M0200,2:beef"), "OK");
+    assert_eq!(client.send("m0200,2"), "beef");
+
+    assert_eq!(client.send("P1=07"), "OK");
+    assert_eq!(client.send("p1"), "07");
+
+    assert_eq!(client.send("s"), "S05");
+    assert_eq!(client.send("g"), "42070000000280");
+
+    assert_eq!(client.send("D"), "OK");
+    let cpu = stub.join().unwrap();
+    assert_eq!(cpu.bus.peek(0x0200), 0xBE);
+}
+
+#[test]
+fn breakpoints_and_watchpoints() {
+    // INX STX $10 JMP $8000
+    let (mut client, _) = attach(&[0xE8, 0x86, 0x10, 0x4C, 0x00, 0x80]);
+
+    assert_eq!(client.send("Z0,8003,1"), "OK");
+    assert_eq!(client.send("c"), "S05");
+    assert_eq!(client.send("g"), "00010000000380");
+
+    assert_eq!(client.send("z0,8003,1"), "OK");
+    assert_eq!(client.send("Z2,10,1"), "OK");
+    assert_eq!(client.send("c"), "T05watch:0010;");
+    assert_eq!(client.send("m10,1"), "02");
+}
+
+#[test]
+fn interrupts_a_running_target() {
+    // JMP $8000
+    let (mut client, _) = attach(&[0x4C, 0x00, 0x80]);
+
+    assert_eq!(client.send("QStartNoAckMode"), "OK");
+    client.0.write_all(b"$c#63").unwrap();
+    client.0.write_all(&[0x03]).unwrap();
+
+    assert_eq!(client.reply(), "S02");
+    assert_eq!(client.send("?"), "S02");
+}
+
+#[test]
+fn describes_the_registers() {
+    let (mut client, _) = attach(&[]);
+
+    assert!(client.send("qSupported").contains("qXfer:features:read+"));
+
+    let target = client.send("qXfer:features:read:target.xml:0,fff");
+    assert!(target.starts_with("l<?xml"));
+    assert!(target.contains(r#"<reg name="pc" bitsize="16""#));
+}
+
+#[test]
+fn ignores_unknown_packets() {
+    let (mut client, _) = attach(&[]);
+
+    assert_eq!(client.send(""), "");
+    assert_eq!(client.send("\u{e9}t\u{e9}"), "");
+    assert_eq!(client.send("vMustReplyEmpty"), "");
+    assert_eq!(client.send("?"), "S05");
+}
@@ -0,0 +1,113 @@
+use cpu6502::{
+    cpu::{addressable_bus::DataBus, Cpu},
+    loader::{error::LoadError, ihex, Block},
+    stack_memory::StackMemory,
+};
+
+const PROGRAM: &str = "\
+:04800000A201860053
+:02800400EA028E
+:02FFFC00008083
+:00000001FF
+";
+
+#[test]
+fn merges_contiguous_records() {
+    let program = ihex::parse(PROGRAM).unwrap();
+
+    assert_eq!(
+        program.blocks,
+        [
+            Block {
+                address: 0x8000,
+                data: vec![0xA2, 0x01, 0x86, 0x00, 0xEA, 0x02]
+            },
+            Block {
+                address: 0xFFFC,
+                data: vec![0x00, 0x80]
+            },
+        ]
+    );
+    assert_eq!(program.entry, None);
+}
+
+#[test]
+fn runs_from_the_reset_vector() {
+    let mut memory = StackMemory::new();
+    ihex::parse(PROGRAM).unwrap().load_into(&mut memory);
+
+    let mut cpu = Cpu::load_memory(memory);
+    cpu.tick().unwrap();
+    cpu.tick().unwrap();
+
+    assert_eq!(cpu.bus.peek(0x0000), 0x01);
+}
+
+#[test]
+fn extended_addresses_and_entry_points() {
+    let program =
+        ihex::parse(":020000040000FA\n:0102000042BB\n:040000050000800077\n:00000001FF").unwrap();
+    assert_eq!(program.blocks[0].address, 0x0200);
+    assert_eq!(program.entry, Some(0x8000));
+
+    let program = ihex::parse(":0400000300100005E4\n:00000001FF").unwrap();
+    assert_eq!(program.entry, Some(0x0105));
+
+    assert!(matches!(
+        ihex::parse(":020000021000EC\n:0102000042BB\n:00000001FF"),
+        Err(LoadError::AddressOutOfRange {
+            line: 2,
+            address: 0x10200
+        })
+    ));
+    assert!(matches!(
+        ihex::parse(":02000004FFFFFC\n:02FFFF000102FD\n:00000001FF"),
+        Err(LoadError::AddressOutOfRange {
+            line: 2,
+            address: 0xFFFFFFFF
+        })
+    ));
+}
+
+#[test]
+fn detection() {
+    assert!(ihex::is_ihex(PROGRAM.as_bytes()));
+    assert!(!ihex::is_ihex(b":\xA9\x01"));
+    assert!(!ihex::is_ihex(b"S1078000A20186004F"));
+    assert!(!ihex::is_ihex(b""));
+}
+
+#[test]
+fn errors() {
+    assert!(matches!(
+        ihex::parse(":04800000A201860054\n:00000001FF"),
+        Err(LoadError::Checksum {
+            line: 1,
+            expected: 0x53,
+            found: 0x54
+        })
+    ));
+    assert!(matches!(
+        ihex::parse("\n04800000A201860053"),
+        Err(LoadError::InvalidRecord { line: 2, .. })
+    ));
+    assert!(matches!(
+        ihex::parse(":04800000A2018600"),
+        Err(LoadError::InvalidRecord { line: 1, .. })
+    ));
+    assert!(matches!(
+        ihex::parse(":0480000GA201860053"),
+        Err(LoadError::InvalidRecord { line: 1, .. })
+    ));
+    assert!(matches!(
+        ihex::parse(":00000006FA"),
+        Err(LoadError::UnknownRecordType {
+            line: 1,
+            record_type: 6
+        })
+    ));
+    assert!(matches!(
+        ihex::parse(":02800400EA028E"),
+        Err(LoadError::MissingEndRecord)
+    ));
+}
@@ -0,0 +1,132 @@
+use cpu6502::{
+    cpu::{addressable_bus::DataBus, status::StatusFlag, Cpu},
+    stack_memory::StackMemory,
+};
+
+const NOP: u8 = 0xEA;
+const CLI: u8 = 0x58;
+const SEI: u8 = 0x78;
+const BRK: u8 = 0x00;
+
+/// A cpu about to run `program` at $8000, with an IRQ handler at $9000
+/// counting in X and an NMI handler at $9100 counting in Y
+fn cpu(program: &[u8]) -> Cpu<StackMemory> {
+    let mut memory = StackMemory::new();
+    memory.load_data(0x8000, program);
+    // INX RTI, INY RTI
+    memory.load_data(0x9000, &[0xE8, 0x40]);
+    memory.load_data(0x9100, &[0xC8, 0x40]);
+
+    memory.set_word(0xFFFA, 0x9100);
+    memory.set_word(0xFFFC, 0x8000);
+    memory.set_word(0xFFFE, 0x9000);
+
+    let mut cpu = Cpu::load_memory(memory);
+    cpu.stack_pointer = 0xFF;
+
+    cpu
+}
+
+#[test]
+fn irq_is_masked_by_the_interrupt_flag() {
+    let mut cpu = cpu(&[SEI, NOP, CLI, NOP, NOP]);
+    cpu.tick().unwrap();
+
+    cpu.set_irq(true);
+    cpu.tick().unwrap();
+    assert_eq!(cpu.program_counter, 0x8002);
+
+    // CLI takes effect after the interrupts are polled, so the next instruction runs first
+    assert_eq!(cpu.tick().unwrap().cycles, 2);
+    assert_eq!(cpu.program_counter, 0x8003);
+
+    assert_eq!(cpu.tick().unwrap().cycles, 2 + 7);
+    assert_eq!(cpu.program_counter, 0x9000);
+    assert!(cpu.processor_status.get_flag(StatusFlag::Interrupt));
+}
+
+#[test]
+fn irq_is_level_triggered() {
+    let mut cpu = cpu(&[NOP, NOP, NOP]);
+    cpu.processor_status.set_flag(StatusFlag::Interrupt, false);
+    cpu.set_irq(true);
+
+    // every RTI lets the interrupt in again, as long as the line is held
+    for _ in 0..3 {
+        cpu.tick().unwrap();
+        cpu.tick().unwrap();
+    }
+    assert_eq!(cpu.x_register, 3);
+
+    cpu.set_irq(false);
+    cpu.tick().unwrap();
+    assert_eq!(cpu.program_counter, 0x8001);
+    cpu.tick().unwrap();
+    assert_eq!(cpu.program_counter, 0x8002);
+    assert_eq!(cpu.x_register, 3);
+}
+
+#[test]
+fn nmi_is_edge_triggered_and_unmaskable() {
+    let mut cpu = cpu(&[SEI, NOP, NOP, NOP, NOP, NOP, NOP]);
+    cpu.tick().unwrap();
+
+    cpu.set_nmi(true);
+    cpu.tick().unwrap();
+    assert_eq!(cpu.program_counter, 0x9100);
+
+    // holding the line doesn't trigger it again
+    for _ in 0..4 {
+        cpu.tick().unwrap();
+    }
+    assert_eq!(cpu.y_register, 1);
+    assert_eq!(cpu.program_counter, 0x8004);
+
+    cpu.set_nmi(false);
+    cpu.set_nmi(true);
+    cpu.tick().unwrap();
+    cpu.tick().unwrap();
+    assert_eq!(cpu.y_register, 2);
+}
+
+#[test]
+fn brk_pushes_the_break_flag_and_irq_does_not() {
+    let mut cpu = cpu(&[BRK, 0xFF, NOP]);
+    cpu.processor_status.set_flag(StatusFlag::Interrupt, false);
+
+    assert_eq!(cpu.tick().unwrap().cycles, 7);
+    assert_eq!(cpu.program_counter, 0x9000);
+    // the return address skips the padding byte after BRK
+    assert_eq!(cpu.bus.peek_word(0x01FE), 0x8002);
+    assert_eq!(cpu.bus.peek(0x01FD), 0b0011_0000);
+
+    cpu.tick().unwrap();
+    cpu.tick().unwrap();
+    assert_eq!(cpu.program_counter, 0x8002);
+
+    cpu.set_irq(true);
+    cpu.tick().unwrap();
+    assert_eq!(cpu.bus.peek_word(0x01FE), 0x8003);
+    assert_eq!(cpu.bus.peek(0x01FD), 0b0010_0000);
+}
+
+#[test]
+fn reset_restarts_from_the_reset_vector() {
+    let mut cpu = cpu(&[NOP, NOP]);
+    cpu.tick().unwrap();
+    cpu.set_nmi(true);
+
+    let cycles = cpu.cycles;
+    cpu.reset();
+
+    assert_eq!(cpu.program_counter, 0x8000);
+    assert_eq!(cpu.stack_pointer, 0xFC);
+    assert!(cpu.processor_status.get_flag(StatusFlag::Interrupt));
+    assert_eq!(cpu.cycles, cycles + 7);
+    // nothing got pushed
+    assert_eq!(cpu.bus.peek_word(0x01FE), 0);
+
+    // the NMI edge seen before the reset is forgotten
+    cpu.tick().unwrap();
+    assert_eq!(cpu.program_counter, 0x8001);
+}
@@ -0,0 +1,75 @@
+use cpu6502::{
+    cpu::{addressable_bus::DataBus, Cpu},
+    kernal::{self, Kernal, Trap},
+    stack_memory::StackMemory,
+};
+
+/// Runs the program at $C000 until it returns to BASIC
+fn run(program: &[u8], input: &[u8]) -> (Cpu<StackMemory>, Vec<u8>) {
+    let mut memory = StackMemory::new();
+    for (i, &byte) in program.iter().enumerate() {
+        memory.set(0xC000 + i as u16, byte);
+    }
+
+    let mut cpu = Cpu::load_memory(memory);
+    let mut kernal = Kernal::new(input, Vec::new());
+    kernal::sys(&mut cpu, 0xC000);
+
+    for _ in 0..10_000 {
+        match kernal.trap(&mut cpu).unwrap() {
+            Some(Trap::Exit) => return (cpu, kernal.output),
+            Some(Trap::Serviced) => (),
+            None => {
+                cpu.tick().unwrap();
+            }
+        }
+    }
+
+    panic!("program never returned");
+}
+
+#[test]
+fn chrout_prints_the_accumulator() {
+    // LDA #'H' JSR CHROUT LDA #'I' JSR CHROUT LDA #$0D JSR CHROUT RTS
+    let program = [
+        0xA9, 0x48, 0x20, 0xD2, 0xFF, 0xA9, 0x49, 0x20, 0xD2, 0xFF, 0xA9, 0x0D, 0x20, 0xD2, 0xFF,
+        0x60,
+    ];
+
+    let (_, output) = run(&program, b"");
+
+    assert_eq!(output, b"HI\n");
+}
+
+#[test]
+fn chrin_echoes_input() {
+    // loop: JSR CHRIN JSR CHROUT CMP #$0D BNE loop RTS
+    let program = [
+        0x20, 0xCF, 0xFF, 0x20, 0xD2, 0xFF, 0xC9, 0x0D, 0xD0, 0xF6, 0x60,
+    ];
+
+    let (_, output) = run(&program, b"hello\n");
+
+    assert_eq!(output, b"HELLO\n");
+}
+
+#[test]
+fn getin_returns_zero_without_input() {
+    // JSR GETIN STA $02 RTS
+    let program = [0x20, 0xE4, 0xFF, 0x85, 0x02, 0x60];
+
+    let (mut cpu, _) = run(&program, b"");
+
+    assert_eq!(cpu.bus.read(0x02), 0);
+    assert_eq!(cpu.accumulator, 0);
+}
+
+#[test]
+fn other_addresses_are_not_trapped() {
+    let mut cpu = Cpu::load_memory(StackMemory::new());
+    cpu.program_counter = 0xC000;
+
+    let mut kernal = Kernal::new(&b""[..], Vec::new());
+
+    assert_eq!(kernal.trap(&mut cpu).unwrap(), None);
+}
@@ -0,0 +1,166 @@
+use cpu6502::{
+    cpu::addressable_bus::DataBus,
+    loader::{
+        error::LoadError,
+        o65::{Layout, RelocationKind, Segment, O65},
+    },
+    stack_memory::StackMemory,
+};
+
+/// Builds a 16 bit o65 file with a bss of 4 bytes at $3000 and 2 zeropage bytes at $80
+fn object(text: &[u8], relocations: &[u8], data_relocations: &[u8]) -> Vec<u8> {
+    let mut file = vec![0x01, 0x00, b'o', b'6', b'5', 0x00];
+    // mode: clear bss
+    file.extend([0x00, 0x02]);
+
+    for size in [0x1000, text.len(), 0x2000, 2, 0x3000, 4, 0x80, 2, 0] {
+        file.extend((size as u16).to_le_bytes());
+    }
+
+    // a filename option
+    file.extend([0x05, 0x00, b'a', b'b', 0x00, 0x00]);
+
+    file.extend(text);
+    // data: .word $1003
+    file.extend([0x03, 0x10]);
+
+    file.extend([0x01, 0x00]);
+    file.extend(b"print\0");
+
+    file.extend(relocations);
+    file.extend(data_relocations);
+
+    file.extend([0x02, 0x00]);
+    file.extend(b"start\0\x02\x00\x10");
+    file.extend(b"table\0\x03\x00\x20");
+
+    file
+}
+
+fn example() -> Vec<u8> {
+    let text = [
+        0x4C, 0x06, 0x10, // JMP $1006
+        0xA9, 0x00, // LDA #<table
+        0xA9, 0x20, // LDA #>table
+        0x20, 0x00, 0x00, // JSR print
+        0xA5, 0x80, // LDA $80
+    ];
+
+    let relocations = [
+        0x02, 0x82, // word in text
+        0x03, 0x23, // low byte in data
+        0x02, 0x43, 0x00, // high byte in data, with its low byte
+        0x02, 0x80, 0x00, 0x00, // word referring to the first undefined symbol
+        0x03, 0x25, // low byte in zeropage
+        0x00,
+    ];
+
+    object(&text, &relocations, &[0x01, 0x82, 0x00])
+}
+
+#[test]
+fn parses_the_header() {
+    let object = O65::parse(&example()).unwrap();
+
+    assert!(object.mode.bss_zero());
+    assert!(!object.mode.long());
+    assert!(!object.mode.pagewise());
+    assert_eq!(object.text_base, 0x1000);
+    assert_eq!(object.text.len(), 12);
+    assert_eq!(object.data, [0x03, 0x10]);
+    assert_eq!((object.bss_base, object.bss_len), (0x3000, 4));
+    assert_eq!((object.zeropage_base, object.zeropage_len), (0x80, 2));
+    assert_eq!(object.options, [(0x00, b"ab\0".to_vec())]);
+    assert_eq!(object.undefined, ["print"]);
+
+    let offsets: Vec<_> = object.text_relocations.iter().map(|r| r.offset).collect();
+    assert_eq!(offsets, [1, 4, 6, 8, 11]);
+    assert_eq!(object.text_relocations[2].kind, RelocationKind::High(0x00));
+    assert_eq!(object.text_relocations[3].segment, Segment::Undefined);
+    assert_eq!(object.text_relocations[3].undefined, Some(0));
+
+    assert_eq!(object.globals.len(), 2);
+    assert_eq!(object.globals[1].name, "table");
+    assert_eq!(object.globals[1].segment, Segment::Data);
+}
+
+#[test]
+fn relocates_into_memory() {
+    let object = O65::parse(&example()).unwrap();
+
+    let mut memory = StackMemory::new();
+    memory.load_data(0xC00E, &[0xFF; 4]);
+
+    let layout = Layout {
+        zeropage: 0x10,
+        ..object.layout_at(0xC000)
+    };
+    let globals = object
+        .load(&mut memory, layout, |name| match name {
+            "print" => Some(0xFFD2),
+            _ => None,
+        })
+        .unwrap();
+
+    assert_eq!(
+        memory.read_range(0xC000..=0xC00D),
+        [0x4C, 0x06, 0xC0, 0xA9, 0x0C, 0xA9, 0xC0, 0x20, 0xD2, 0xFF, 0xA5, 0x10, 0x03, 0xC0]
+    );
+    assert_eq!(memory.read_range(0xC00E..=0xC011), [0; 4]);
+
+    assert_eq!(globals["start"], 0xC000);
+    assert_eq!(globals["table"], 0xC00C);
+}
+
+#[test]
+fn high_bytes_carry_from_their_low_byte() {
+    // LDA #>$10F0, moved forward by $0020 bytes
+    let file = object(&[0xA9, 0x10], &[0x02, 0x42, 0xF0, 0x00], &[0x00]);
+    let object = O65::parse(&file).unwrap();
+
+    let mut memory = StackMemory::new();
+    object
+        .load(&mut memory, object.layout_at(0x1020), |_| None)
+        .unwrap();
+
+    assert_eq!(memory.peek(0x1021), 0x11);
+}
+
+#[test]
+fn long_relocation_offsets() {
+    let mut text = vec![0xEA; 300];
+    text[260..262].copy_from_slice(&[0x00, 0x10]);
+
+    // 0xFF skips 254 bytes without relocating anything
+    let file = object(&text, &[0xFF, 0x07, 0x82, 0x00], &[0x00]);
+    let object = O65::parse(&file).unwrap();
+    assert_eq!(object.text_relocations[0].offset, 260);
+
+    let mut memory = StackMemory::new();
+    object
+        .load(&mut memory, object.layout_at(0x8000), |_| None)
+        .unwrap();
+
+    assert_eq!(memory.peek_word(0x8000 + 260), 0x8000);
+}
+
+#[test]
+fn errors() {
+    assert!(matches!(
+        O65::parse(b"\x01\x00o66"),
+        Err(LoadError::BadMagic)
+    ));
+
+    let file = example();
+    assert!(matches!(
+        O65::parse(&file[..file.len() - 1]),
+        Err(LoadError::Truncated)
+    ));
+
+    let object = O65::parse(&file).unwrap();
+    let layout = object.layout_at(0x8000);
+    assert!(matches!(
+        object.load(&mut StackMemory::new(), layout, |_| None),
+        Err(LoadError::UndefinedReference(name)) if name == "print"
+    ));
+}
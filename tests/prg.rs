@@ -0,0 +1,58 @@
+use cpu6502::loader::{error::LoadError, prg, Block};
+
+#[test]
+fn loads_after_the_load_address() {
+    let program = prg::parse(&[0x00, 0xC0, 0xA9, 0x01, 0x60]).unwrap();
+
+    assert_eq!(
+        program.blocks,
+        [Block {
+            address: 0xC000,
+            data: vec![0xA9, 0x01, 0x60]
+        }]
+    );
+    assert_eq!(program.entry, Some(0xC000));
+}
+
+#[test]
+fn enters_basic_programs_through_their_sys_line() {
+    // 10 SYS 2061
+    let bytes = [
+        0x01, 0x08, 0x0B, 0x08, 0x0A, 0x00, 0x9E, 0x32, 0x30, 0x36, 0x31, 0x00, 0x00, 0x00, 0x60,
+    ];
+    let program = prg::parse(&bytes).unwrap();
+
+    assert_eq!(program.blocks[0].address, 0x0801);
+    assert_eq!(program.entry, Some(2061));
+}
+
+#[test]
+fn basic_without_sys_has_no_entry() {
+    // 10 PRINT
+    let bytes = [0x01, 0x08, 0x07, 0x08, 0x0A, 0x00, 0x99, 0x00, 0x00, 0x00];
+
+    assert_eq!(prg::parse(&bytes).unwrap().entry, None);
+}
+
+#[test]
+fn needs_a_load_address() {
+    assert!(matches!(prg::parse(&[0x01]), Err(LoadError::Truncated)));
+}
+
+#[test]
+fn disassembles_from_the_load_address() {
+    let path = std::env::temp_dir().join(format!("disasm-{}.prg", std::process::id()));
+    std::fs::write(&path, [0x00, 0xC0, 0xA9, 0x01, 0x60]).unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_cpu6502"))
+        .arg("disasm")
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // the load address isn't an instruction
+    let listing = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success());
+    assert_eq!(listing, "C000  A9 01     LDA #$01\nC002  60        RTS\n");
+}
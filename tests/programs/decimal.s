@@ -0,0 +1,237 @@
+; Decimal mode test of the NMOS 6502, written for this crate after the method of
+; Bruce Clark's "Decimal Mode" tutorial. ADC and SBC run in decimal mode on every
+; pair of operands, valid BCD or not, with and without carry, and the accumulator
+; and the N, V, Z and C flags get checked against predictions computed in binary.
+; On the NMOS 6502, ADC takes N and V from the sum before its high digit gets
+; adjusted and Z from the binary sum, while SBC takes all of its flags from the
+; binary difference. A mismatch branches to itself, success jumps to itself.
+;
+; Load the binary at $0200 and start from $0200. Rebuild it with
+;
+;   cpu6502 asm tests/programs/decimal.s -o tests/programs/decimal.bin
+
+n1      = $00           ; operands and carry in
+n2      = $01
+cin     = $02
+al      = $03           ; low digit of the prediction
+alh     = $04
+ar      = $05           ; predicted result, 16 bit
+arh     = $06
+ec      = $07           ; predicted carry
+da      = $08           ; actual result
+df      = $09           ; actual N, V, Z and C
+tmp     = $0a
+nv      = $0b           ; status with the predicted N and V
+ef      = $0c           ; predicted N, V, Z and C
+flags   = $c3           ; N, V, Z and C out of a pushed status
+
+* = $0200
+start   cld
+        ldx #$ff
+        txs
+        lda #$00
+        sta n1
+        sta n2
+        sta cin
+
+loop    jsr add
+        jsr sub
+        lda cin
+        eor #$01
+        sta cin
+        bne loop
+        inc n2
+        bne loop
+        inc n1
+        bne loop
+
+success jmp success
+
+; ADC: AL = (N1 & $0F) + (N2 & $0F) + C, adjusted by 6 past 9,
+; then AR = (N1 & $F0) + (N2 & $F0) + AL, adjusted by $60 from $A0
+add     lda n1
+        and #$0f
+        sta al
+        lda n2
+        and #$0f
+        clc
+        adc al
+        adc cin
+        sta al
+        cmp #$0a
+        bcc add_low
+        adc #$05        ; carry is set, so this adds 6
+        and #$0f
+        ora #$10
+        sta al
+add_low lda #$00
+        sta arh
+        lda n1
+        and #$f0
+        sta ar
+        lda n2
+        and #$f0
+        clc
+        adc ar
+        rol arh
+        clc
+        adc al
+        sta ar
+        bcc add_high
+        inc arh
+add_high
+        lda arh
+        bne add_adjust
+        lda ar
+        cmp #$a0
+        bcc add_check
+add_adjust
+        lda ar
+        clc
+        adc #$60
+        sta ar
+        bcc add_check
+        inc arh
+add_check
+        lda #$00
+        ldx arh
+        beq add_carry
+        lda #$01
+add_carry
+        sta ec
+
+; N and V: a binary ADC of (N1 & $F0) + (N2 & $F0) + AL. Past 9, AL is $10
+; plus a digit, and the $10 goes in as the carry and $0F on top of N2's digit
+        lda al
+        cmp #$10
+        lda n2
+        and #$f0
+        bcc add_nv
+        ora #$0f
+add_nv  sta tmp
+        lda n1
+        and #$f0
+        sta nv
+        lda al
+        and #$0f
+        ora nv
+        adc tmp
+        php
+        pla
+        sta nv
+
+; Z: the binary sum
+        lda cin
+        lsr
+        lda n1
+        adc n2
+        php
+        pla
+        and #$02
+        sta tmp
+        lda nv
+        and #$c0
+        ora tmp
+        ora ec
+        sta ef
+
+        lda cin
+        lsr
+        lda n1
+        sed
+        adc n2
+        php
+        cld
+        sta da
+        pla
+        and #flags
+        sta df
+
+        lda da
+        cmp ar
+        bne *
+        lda df
+        cmp ef
+        bne *
+        rts
+
+; SBC: AL = (N1 & $0F) - (N2 & $0F) + C - 1, adjusted by 6 below 0,
+; then AR = (N1 & $F0) - (N2 & $F0) + AL, adjusted by $60 below 0.
+; The carry is the one of a binary subtraction
+sub     lda n2
+        and #$0f
+        sta tmp
+        lda n1
+        and #$0f
+        sec
+        sbc tmp
+        clc
+        adc cin
+        sec
+        sbc #$01
+        sta al
+        bpl sub_low
+        sec
+        sbc #$06
+        and #$0f
+        sec
+        sbc #$10
+        sta al
+sub_low lda #$00        ; sign extension of AL
+        ldx al
+        bpl sub_sign
+        lda #$ff
+sub_sign
+        sta alh
+        lda n2
+        and #$f0
+        sta tmp
+        lda n1
+        and #$f0
+        sec
+        sbc tmp
+        sta ar
+        lda #$00
+        sbc #$00
+        sta arh
+        lda ar
+        clc
+        adc al
+        sta ar
+        lda arh
+        adc alh
+        sta arh
+        bpl sub_check
+        lda ar
+        sec
+        sbc #$60
+        sta ar
+sub_check
+        lda cin
+        lsr
+        lda n1
+        sbc n2
+        php
+        pla
+        and #flags
+        sta ef
+
+        lda cin
+        lsr
+        lda n1
+        sed
+        sbc n2
+        php
+        cld
+        sta da
+        pla
+        and #flags
+        sta df
+
+        lda da
+        cmp ar
+        bne *
+        lda df
+        cmp ef
+        bne *
+        rts
@@ -0,0 +1,565 @@
+; Functional test of the documented NMOS 6502 instructions, written for this
+; crate in the style of Klaus Dormann's 6502_functional_test, which it doesn't
+; replace. Every check branches to itself when it fails, so a run always ends in
+; a self-loop: at the failing check, or at success.
+;
+; Load the binary at $0400 and start from $0400. Rebuild it with
+;
+;   cpu6502 asm tests/programs/functional.s -o tests/programs/functional.bin
+
+zp      = $10           ; scratch bytes
+ptr     = $20           ; pointer for the indirect modes
+data    = $0300         ; scratch page
+flags   = $c3           ; N, V, Z and C out of a pushed status
+
+* = $0400
+start   cld
+        sei
+        ldx #$ff
+        txs
+
+; flags and branches
+branches
+        clc
+        bcs *
+        sec
+        bcc *
+        lda #$00
+        bne *
+        bmi *
+        lda #$80
+        beq *
+        bpl *
+        clv
+        bvs *
+        lda #$40        ; 64 + 64 overflows
+        clc
+        adc #$40
+        bvc *
+        clv
+        bvs *
+
+; status register through the stack
+status
+        lda #$ff
+        pha
+        plp
+        php
+        pla
+        cmp #$ff
+        bne *
+        lda #$00
+        pha
+        plp
+        php
+        pla
+        cmp #$30        ; B and the unused bit are always pushed set
+        bne *
+
+; loads and stores
+loads
+        lda #$55
+        sta zp
+        ldx zp
+        cpx #$55
+        bne *
+        ldy zp
+        cpy #$55
+        bne *
+        ldx #$01
+        lda #$aa
+        sta zp,x
+        lda zp+1
+        cmp #$aa
+        bne *
+        ldy #$02
+        stx zp,y        ; zp,y through STX
+        ldx zp+2
+        cpx #$01
+        bne *
+        ldy #$03
+        sty zp,x
+        ldx #$02
+        lda zp,x
+        cmp #$01
+        bne *
+        ldy zp+1
+        cpy #$03
+        bne *
+        ldx #$ff        ; zeropage indexing wraps within page zero
+        lda #$5a
+        sta zp+1,x
+        lda zp
+        cmp #$5a
+        bne *
+
+absolute
+        lda #$12
+        sta data
+        lda data
+        cmp #$12
+        bne *
+        ldx #$34
+        stx !zp         ; absolute addressing of page zero
+        lda zp
+        cmp #$34
+        bne *
+        ldx #$10
+        lda #$56
+        sta data,x
+        lda data+$10
+        cmp #$56
+        bne *
+        ldy #$20
+        lda #$78
+        sta data,y
+        ldx data+$20
+        cpx #$78
+        bne *
+        ldy #$ff        ; crossing into the next page
+        lda #$9a
+        sta data+1,y
+        lda data+$100
+        cmp #$9a
+        bne *
+        ldx #$10
+        ldy data,x
+        cpy #$56
+        bne *
+        ldy #$20
+        ldx data,y
+        cpx #$78
+        bne *
+
+indirect_modes
+        lda #<data      ; (zp,x) and (zp),y
+        sta ptr
+        lda #>data
+        sta ptr+1
+        ldx #$04
+        lda #$bc
+        sta (ptr-4,x)
+        lda data
+        cmp #$bc
+        bne *
+        ldy #$10
+        lda (ptr),y
+        cmp #$56
+        bne *
+        lda #$de
+        sta (ptr),y
+        lda data+$10
+        cmp #$de
+        bne *
+        lda #$ff
+        sta ptr
+        ldy #$01        ; (zp),y crossing a page
+        lda (ptr),y
+        cmp #$9a
+        bne *
+        ldx #$00
+        lda (ptr,x)
+        cmp data+$ff
+        bne *
+
+; transfers
+transfers
+        lda #$81
+        tax
+        bpl *
+        cpx #$81
+        bne *
+        ldx #$00
+        txa
+        bne *
+        lda #$42
+        tay
+        cpy #$42
+        bne *
+        ldy #$80
+        tya
+        cmp #$80
+        bne *
+        tsx
+        cpx #$ff
+        bne *
+        ldx #$80
+        txs
+        ldx #$00
+        tsx
+        bpl *           ; TSX sets the flags
+        cpx #$80
+        bne *
+        ldx #$ff
+        txs
+
+; stack
+stack
+        lda #$11
+        pha
+        lda #$22
+        pha
+        tsx
+        cpx #$fd
+        bne *
+        lda $01ff
+        cmp #$11
+        bne *
+        pla
+        cmp #$22
+        bne *
+        lda #$00
+        pla
+        cmp #$11
+        bne *
+        tsx
+        cpx #$ff
+        bne *
+        lda #$80
+        pha
+        lda #$00
+        pla             ; PLA sets the flags
+        bpl *
+
+; logical operations
+logical
+        lda #$f0
+        and #$3c
+        cmp #$30
+        bne *
+        and #$0f
+        bne *
+        lda #$f0
+        ora #$0f
+        cmp #$ff
+        bne *
+        lda #$ff
+        eor #$5a
+        cmp #$a5
+        bne *
+        eor #$a5
+        bne *
+        lda #$c0        ; BIT copies bits 7 and 6, and sets Z from the AND
+        sta zp
+        lda #$3f
+        bit zp
+        bne *
+        bpl *
+        bvc *
+        lda #$40
+        sta data
+        bit data
+        beq *
+        bmi *
+        bvc *
+        lda #$00
+        sta zp
+        bit zp
+        bvs *
+        bmi *
+
+; binary ADC, flags N, V, Z and C
+binary_adc
+        clc
+        lda #$50
+        adc #$10
+        php
+        cmp #$60
+        bne *
+        pla
+        and #flags
+        bne *
+        clc
+        lda #$50
+        adc #$50
+        php
+        cmp #$a0
+        bne *
+        pla
+        and #flags
+        cmp #$c0
+        bne *
+        clc
+        lda #$d0
+        adc #$90
+        php
+        cmp #$60
+        bne *
+        pla
+        and #flags
+        cmp #$41
+        bne *
+        clc
+        lda #$ff
+        adc #$01
+        php
+        bne *
+        pla
+        and #flags
+        cmp #$03
+        bne *
+        sec
+        lda #$01
+        adc #$01
+        cmp #$03
+        bne *
+        lda #$7f        ; carry in causing the overflow
+        sec
+        adc #$00
+        bvc *
+        bpl *
+
+; binary SBC
+binary_sbc
+        sec
+        lda #$50
+        sbc #$f0
+        php
+        cmp #$60
+        bne *
+        pla
+        and #flags
+        bne *
+        sec
+        lda #$50
+        sbc #$b0
+        php
+        cmp #$a0
+        bne *
+        pla
+        and #flags
+        cmp #$c0
+        bne *
+        sec
+        lda #$d0
+        sbc #$70
+        php
+        cmp #$60
+        bne *
+        pla
+        and #flags
+        cmp #$41
+        bne *
+        sec
+        lda #$05
+        sbc #$05
+        php
+        bne *
+        pla
+        and #flags
+        cmp #$03
+        bne *
+        clc             ; borrow in
+        lda #$05
+        sbc #$03
+        cmp #$01
+        bne *
+
+; compares
+compares
+        lda #$40
+        cmp #$40
+        bne *
+        bcc *
+        cmp #$41
+        beq *
+        bcs *
+        bpl *
+        cmp #$3f
+        beq *
+        bcc *
+        ldx #$80
+        cpx #$7f
+        bcc *
+        bmi *
+        ldy #$00
+        cpy #$01
+        bcs *
+        bpl *
+        lda #$40
+        sta zp
+        ldx #$40
+        cpx zp
+        bne *
+        ldy #$41
+        cpy zp
+        bcc *
+        lda #$40
+        sta data
+        lda #$3f
+        cmp data
+        bcs *
+
+; increments and decrements
+increments
+        ldx #$ff
+        inx
+        bne *
+        dex
+        bpl *
+        cpx #$ff
+        bne *
+        ldy #$7f
+        iny
+        bpl *
+        dey
+        bmi *
+        lda #$ff
+        sta zp
+        inc zp
+        bne *
+        dec zp
+        bpl *
+        lda zp
+        cmp #$ff
+        bne *
+        ldx #$01
+        lda #$0f
+        sta data+1
+        inc data,x
+        lda data+1
+        cmp #$10
+        bne *
+        dec data,x
+        dec data,x
+        lda data+1
+        cmp #$0e
+        bne *
+
+; shifts and rotations
+shifts
+        lda #$81
+        asl
+        bcc *
+        cmp #$02
+        bne *
+        lsr
+        bcs *
+        lsr
+        bcc *
+        bne *
+        lda #$80
+        sec
+        rol
+        bcc *
+        cmp #$01
+        bne *
+        clc
+        ror
+        bcc *
+        bne *
+        ror
+        bcs *
+        bpl *
+        cmp #$80
+        bne *
+        lda #$40
+        sta zp
+        asl zp
+        bpl *
+        lsr zp
+        lsr zp
+        lda zp
+        cmp #$20
+        bne *
+        sec
+        rol zp
+        lda zp
+        cmp #$41
+        bne *
+        sec
+        ror zp
+        bcc *
+        lda zp
+        cmp #$a0
+        bne *
+        ldx #$02
+        lda #$01
+        sta data+2
+        asl data,x
+        rol data,x
+        lda data+2
+        cmp #$04
+        bne *
+
+; jumps and subroutines
+jumps
+        jmp jumped
+        jmp *
+jumped  lda #<indirect
+        sta data
+        lda #>indirect
+        sta data+1
+        jmp (data)
+        jmp *
+indirect
+        lda #<page_bug  ; JMP ($xxFF) takes the high byte from $xx00
+        sta data+$ff
+        lda #>page_bug
+        sta data
+        lda #$ff
+        sta data+$100
+        jmp (data+$ff)
+        jmp *
+page_bug
+        ldx #$ff
+        txs
+        jsr subroutine
+after   cpx #$01
+        bne *
+        tsx
+        cpx #$ff
+        bne *
+        jmp interrupts
+
+subroutine
+        tsx
+        cpx #$fd
+        bne *
+        lda $01ff       ; the return address minus one
+        cmp #>(after-1)
+        bne *
+        lda $01fe
+        cmp #<(after-1)
+        bne *
+        ldx #$01
+        rts
+
+; BRK and RTI
+interrupts
+        lda #<handler
+        sta $fffe
+        lda #>handler
+        sta $ffff
+        lda #$00
+        pha
+        plp
+        brk
+        .byt $ea        ; padding byte skipped on return
+brk_return
+        php
+        pla
+        cmp #$30        ; RTI restored the status pushed by BRK, B aside
+        bne *
+        cpx #$42
+        bne *
+        jmp success
+
+handler tsx
+        lda $0101,x     ; status, with B set
+        cmp #$30
+        bne *
+        lda $0103,x     ; return address, past the padding byte
+        cmp #>brk_return
+        bne *
+        lda $0102,x
+        cmp #<brk_return
+        bne *
+        php
+        pla
+        and #$04        ; BRK sets I
+        beq *
+        ldx #$42
+        rti
+
+success jmp success
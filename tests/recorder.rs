@@ -0,0 +1,216 @@
+use cpu6502::{
+    bus::recorder::{Event, Recorder},
+    cpu::{addressable_bus::DataBus, status::StatusFlag, variant::Variant, Cpu},
+    stack_memory::StackMemory,
+};
+use std::fs;
+
+/// A cpu at $8000 running `program` on a recorded memory
+fn cpu(program: &[u8], capacity: usize) -> Cpu<Recorder<StackMemory>> {
+    let mut memory = StackMemory::new();
+    memory.load_data(0x8000, program);
+
+    let mut cpu = Cpu::load_memory(memory);
+    cpu.program_counter = 0x8000;
+
+    // wrapped once the reset vector is read
+    cpu.map_bus(|memory| Recorder::new(memory, capacity))
+}
+
+fn event(cycle: u64, address: u16, value: u8, write: bool) -> Event {
+    Event {
+        cycle,
+        address,
+        value,
+        write,
+    }
+}
+
+#[test]
+fn records_reads_and_writes_with_their_cycle() {
+    // LDA #$42 STA $0200
+    let mut cpu = cpu(&[0xA9, 0x42, 0x8D, 0x00, 0x02], 16);
+    cpu.tick().unwrap();
+    cpu.tick().unwrap();
+
+    let events: Vec<Event> = cpu.bus.events().copied().collect();
+    assert_eq!(
+        events,
+        vec![
+            event(0, 0x8000, 0xA9, false),
+            event(1, 0x8001, 0x42, false),
+            event(2, 0x8002, 0x8D, false),
+            event(3, 0x8003, 0x00, false),
+            event(4, 0x8004, 0x02, false),
+            event(5, 0x0200, 0x42, true),
+        ]
+    );
+
+    assert_eq!(cpu.bus.at(0x0200).count(), 1);
+    assert_eq!(cpu.bus.between(2..6).count(), 4);
+    assert_eq!(
+        event(5, 0x0200, 0x42, true).to_string(),
+        "5  0200  42  write"
+    );
+}
+
+#[test]
+fn records_dummy_accesses_on_their_cycle() {
+    // LDX #$01 INC $12FF,X
+    let mut cpu = cpu(&[0xA2, 0x01, 0xFE, 0xFF, 0x12], 16);
+    cpu.bus.inner.set(0x1300, 0x41);
+    cpu.tick().unwrap();
+    cpu.bus.clear();
+    cpu.tick().unwrap();
+
+    let events: Vec<Event> = cpu.bus.events().copied().collect();
+    assert_eq!(
+        events,
+        vec![
+            event(2, 0x8002, 0xFE, false),
+            event(3, 0x8003, 0xFF, false),
+            event(4, 0x8004, 0x12, false),
+            // before the carry into the high byte
+            event(5, 0x1200, 0x00, false),
+            event(6, 0x1300, 0x41, false),
+            // written back unchanged while being incremented
+            event(7, 0x1300, 0x41, true),
+            event(8, 0x1300, 0x42, true),
+        ]
+    );
+}
+
+#[test]
+fn records_the_stack_accesses_of_subroutines() {
+    // JSR $9000, with RTS at $9000
+    let mut cpu = cpu(&[0x20, 0x00, 0x90], 16);
+    cpu.bus.inner.set(0x9000, 0x60);
+    cpu.stack_pointer = 0xFF;
+    cpu.tick().unwrap();
+    cpu.tick().unwrap();
+
+    let events: Vec<Event> = cpu.bus.events().copied().collect();
+    assert_eq!(
+        events,
+        vec![
+            event(0, 0x8000, 0x20, false),
+            event(1, 0x8001, 0x00, false),
+            event(2, 0x01FF, 0x00, false),
+            event(3, 0x01FF, 0x80, true),
+            event(4, 0x01FE, 0x02, true),
+            // the high byte of the target comes last
+            event(5, 0x8002, 0x90, false),
+            event(6, 0x9000, 0x60, false),
+            event(7, 0x9001, 0x00, false),
+            event(8, 0x01FD, 0x00, false),
+            event(9, 0x01FE, 0x02, false),
+            event(10, 0x01FF, 0x80, false),
+            event(11, 0x8002, 0x90, false),
+        ]
+    );
+    assert_eq!(cpu.program_counter, 0x8003);
+}
+
+#[test]
+fn every_cycle_accesses_the_bus_once() {
+    for variant in [Variant::Nmos6502, Variant::Wdc65C02] {
+        for opcode in 0..=0xFF {
+            // indexing by $80 crosses a page from $12FF, and each
+            // flag setting takes the branches the other one doesn't
+            for (index, flags) in [(0x00, 0x00), (0x80, 0xFF)] {
+                let mut memory = StackMemory::new();
+                memory.load_data(0x8000, &[opcode, 0xFF, 0x12]);
+                memory.set_word(0x00FF, 0x12FF);
+                memory.set_word(0x12FF, 0x9000);
+
+                let mut cpu = Cpu::with_variant(memory, variant);
+                cpu.undocumented_opcodes = true;
+                cpu.program_counter = 0x8000;
+                cpu.stack_pointer = 0xF0;
+                cpu.x_register = index;
+                cpu.y_register = index;
+                cpu.processor_status = flags.into();
+                cpu.processor_status.set_flag(StatusFlag::Interrupt, true);
+
+                let mut cpu = cpu.map_bus(|memory| Recorder::new(memory, 64));
+                let tick = match cpu.tick() {
+                    Ok(tick) => tick,
+                    Err(_) => continue,
+                };
+
+                assert_eq!(
+                    cpu.bus.len(),
+                    tick.cycles as usize,
+                    "{:?} opcode ${:02X} with index ${:02X}",
+                    variant,
+                    opcode,
+                    index
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn interrupts_and_resets_take_a_cycle_per_access() {
+    // NOP
+    let mut cpu = cpu(&[0xEA], 16);
+    cpu.set_irq(true);
+    cpu.processor_status.set_flag(StatusFlag::Interrupt, false);
+    let tick = cpu.tick().unwrap();
+
+    assert_eq!(tick.cycles, 2 + 7);
+    assert_eq!(cpu.bus.len(), 9);
+    // three pushes, then the vector
+    assert_eq!(cpu.bus.events().filter(|event| event.write).count(), 3);
+    assert_eq!(cpu.bus.last(2).next().unwrap().address, 0xFFFE);
+
+    cpu.bus.clear();
+    cpu.reset();
+    assert_eq!(cpu.bus.len(), 7);
+    assert!(cpu.bus.events().all(|event| !event.write));
+}
+
+#[test]
+fn keeps_the_latest_events() {
+    // NOP NOP NOP
+    let mut cpu = cpu(&[0xEA, 0xEA, 0xEA], 2);
+    for _ in 0..3 {
+        cpu.tick().unwrap();
+    }
+
+    // each NOP reads the byte after it as well
+    let addresses: Vec<u16> = cpu.bus.events().map(|event| event.address).collect();
+    assert_eq!(addresses, vec![0x8002, 0x8003]);
+    assert_eq!(cpu.bus.last(1).next().unwrap().cycle, 5);
+
+    // peeks aren't accesses of the cpu
+    cpu.bus.peek(0x8000);
+    assert_eq!(cpu.bus.len(), 2);
+
+    cpu.bus.clear();
+    assert!(cpu.bus.is_empty());
+}
+
+#[test]
+fn writes_events_to_a_file() {
+    let path = std::env::temp_dir().join(format!("recorder-{}.log", std::process::id()));
+
+    // LDA #$01
+    let mut memory = StackMemory::new();
+    memory.load_data(0x8000, &[0xA9, 0x01]);
+
+    let mut cpu = Cpu::load_memory(memory);
+    cpu.program_counter = 0x8000;
+
+    let output = fs::File::create(&path).unwrap();
+    let mut cpu = cpu.map_bus(|memory| Recorder::new(memory, 0).with_output(output));
+    cpu.tick().unwrap();
+    cpu.bus.flush().unwrap();
+
+    let log = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(log, "0  8000  A9  read\n1  8001  01  read\n");
+    assert!(cpu.bus.is_empty());
+}
@@ -0,0 +1,296 @@
+use cpu6502::{
+    assembler::assemble,
+    bus::{
+        device::{Device, Ram, Rom},
+        Bus,
+    },
+    cpu::{addressable_bus::DataBus, Cpu},
+    savestate::{error::SaveStateError, SaveState, Snapshot, MAGIC},
+    stack_memory::StackMemory,
+};
+
+const PROGRAM: &str = "
+* = $8000
+reset   ldx #$ff
+        txs
+        lda #0
+loop    clc
+        adc #3
+        sta $0200,x
+        dex
+        bne loop
+        jmp *
+
+nmi     inc $10
+        rti
+";
+
+fn cpu() -> Cpu<StackMemory> {
+    let assembly = assemble(PROGRAM).unwrap();
+    let mut memory = StackMemory::new();
+    assembly.load_into(&mut memory);
+    memory.set_word(0xFFFA, assembly.symbols["nmi"]);
+    memory.set_word(0xFFFC, assembly.symbols["reset"]);
+
+    Cpu::load_memory(memory)
+}
+
+fn registers<T: DataBus>(cpu: &Cpu<T>) -> (u16, u8, u8, u8, u8, u8, u64) {
+    (
+        cpu.program_counter,
+        cpu.accumulator,
+        cpu.x_register,
+        cpu.y_register,
+        cpu.stack_pointer,
+        cpu.processor_status.0,
+        cpu.cycles,
+    )
+}
+
+/// Writes and reads back the state, as a file would
+fn round_trip(state: &SaveState) -> SaveState {
+    let mut file = Vec::new();
+    state.write(&mut file).unwrap();
+
+    SaveState::read(&mut &file[..]).unwrap()
+}
+
+#[test]
+fn resumes_from_a_saved_state() {
+    let mut cpu = cpu();
+    for _ in 0..100 {
+        cpu.tick().unwrap();
+    }
+
+    let state = round_trip(&cpu.save_state());
+    let mut restored = Cpu::load_memory(StackMemory::new());
+    restored.restore_state(&state).unwrap();
+
+    for _ in 0..500 {
+        cpu.tick().unwrap();
+        restored.tick().unwrap();
+    }
+
+    assert_eq!(registers(&restored), registers(&cpu));
+    assert_eq!(restored.bus.as_slice(), cpu.bus.as_slice());
+}
+
+#[test]
+fn keeps_pending_interrupts() {
+    let mut cpu = cpu();
+    cpu.tick().unwrap();
+    cpu.set_nmi(true);
+
+    let mut restored = Cpu::load_memory(StackMemory::new());
+    restored.restore_state(&cpu.save_state()).unwrap();
+
+    // the NMI is serviced after the next instruction, and isn't retriggered
+    restored.tick().unwrap();
+    assert_eq!(restored.program_counter, 0x8011);
+    restored.set_nmi(true);
+    for _ in 0..3 {
+        restored.tick().unwrap();
+    }
+    assert_eq!(restored.bus.peek(0x10), 1);
+}
+
+/// Counts the writes to memory, keeping the count in its own section
+struct Counted {
+    memory: StackMemory,
+    writes: u32,
+}
+
+impl DataBus for Counted {
+    fn peek(&self, addr: u16) -> u8 {
+        self.memory.peek(addr)
+    }
+
+    fn set(&mut self, addr: u16, x: u8) {
+        self.writes += 1;
+        self.memory.set(addr, x);
+    }
+}
+
+impl Snapshot for Counted {
+    fn save(&self, state: &mut SaveState) {
+        self.memory.save(state);
+        state.insert("counter", self.writes.to_le_bytes().to_vec());
+    }
+
+    fn restore(&mut self, state: &SaveState) -> Result<(), SaveStateError> {
+        let data = state.require("counter", 4)?;
+        self.memory.restore(state)?;
+        self.writes = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn devices_add_their_own_sections() {
+    let mut cpu = cpu().map_bus(|memory| Counted { memory, writes: 0 });
+    for _ in 0..20 {
+        cpu.tick().unwrap();
+    }
+
+    let state = round_trip(&cpu.save_state());
+    assert_eq!(
+        state.sections().collect::<Vec<_>>(),
+        vec!["counter", "cpu", "memory"]
+    );
+
+    let mut restored = Cpu::load_memory(Counted {
+        memory: StackMemory::new(),
+        writes: 0,
+    });
+    restored.restore_state(&state).unwrap();
+    assert_eq!(restored.bus.writes, cpu.bus.writes);
+    assert_eq!(restored.bus.peek(0x02FF), 3);
+
+    // plain memory can't be restored from a state missing the counter
+    let mut memory_only = SaveState::new();
+    cpu.bus.memory.save(&mut memory_only);
+    assert!(matches!(
+        restored.restore_state(&memory_only),
+        Err(SaveStateError::MissingSection(name)) if name == "cpu"
+    ));
+}
+
+#[test]
+fn refuses_broken_files() {
+    let mut file = Vec::new();
+    cpu().save_state().write(&mut file).unwrap();
+
+    let read = |file: &[u8]| SaveState::read(&mut &file[..]);
+
+    assert!(matches!(
+        read(b"garbage"),
+        Err(SaveStateError::NotASaveState)
+    ));
+    assert!(matches!(
+        read(&file[..file.len() - 1]),
+        Err(SaveStateError::Truncated)
+    ));
+
+    for version in [0u16, 2] {
+        let mut other = MAGIC.to_vec();
+        other.extend(version.to_le_bytes());
+        assert!(matches!(
+            read(&other),
+            Err(SaveStateError::UnsupportedVersion(found)) if found == version
+        ));
+    }
+
+    // a cpu section out of range leaves the cpu as it was
+    let mut state = read(&file).unwrap();
+    let mut data = state.section("cpu").unwrap().to_vec();
+    data[0] = 9;
+    state.insert("cpu", data);
+
+    let mut cpu = cpu();
+    assert!(matches!(
+        cpu.restore_state(&state),
+        Err(SaveStateError::InvalidSection(_))
+    ));
+    assert_eq!(cpu.program_counter, 0x8000);
+}
+
+/// Counts the reads of the cpu, keeping the count in its own section
+#[derive(Default)]
+struct Timer(u8);
+
+impl Device for Timer {
+    fn peek(&self, _offset: u16) -> u8 {
+        self.0
+    }
+
+    fn set(&mut self, _offset: u16, _x: u8) {}
+
+    fn read(&mut self, _offset: u16) -> u8 {
+        self.0 += 1;
+        self.0
+    }
+
+    fn save(&self, name: &str, state: &mut SaveState) {
+        state.insert(name, vec![self.0]);
+    }
+
+    fn check_restore(&self, name: &str, state: &SaveState) -> Result<(), SaveStateError> {
+        state.require(name, 1).map(|_| ())
+    }
+
+    fn restore(&mut self, name: &str, state: &SaveState) -> Result<(), SaveStateError> {
+        self.0 = state.require(name, 1)?[0];
+
+        Ok(())
+    }
+}
+
+/// 2K of mirrored ram, a timer at $4000 and a rom at $8000 running
+/// `LDX #0 loop LDA $4000 STA $00,X INX JMP loop`
+fn machine() -> Cpu<Bus> {
+    let mut rom = vec![0; 0x8000];
+    rom[..11].copy_from_slice(&[
+        0xA2, 0x00, 0xAD, 0x00, 0x40, 0x95, 0x00, 0xE8, 0x4C, 0x02, 0x80,
+    ]);
+    rom[0x7FFD] = 0x80;
+
+    let mut bus = Bus::new();
+    bus.map_mirrored(0x0000..=0x1FFF, 0x07FF, Ram::new(0x800));
+    bus.map(0x4000..=0x4000, Timer::default());
+    bus.map(0x8000..=0xFFFF, Rom::new(rom));
+
+    Cpu::load_memory(bus)
+}
+
+#[test]
+fn saves_devices_on_a_bus() {
+    let mut cpu = machine();
+    for _ in 0..40 {
+        cpu.tick().unwrap();
+    }
+
+    let state = round_trip(&cpu.save_state());
+    assert_eq!(
+        state.sections().collect::<Vec<_>>(),
+        vec!["bus.0", "bus.1", "cpu"]
+    );
+
+    let mut restored = machine();
+    restored.restore_state(&state).unwrap();
+    for _ in 0..100 {
+        cpu.tick().unwrap();
+        restored.tick().unwrap();
+    }
+
+    assert_eq!(registers(&restored), registers(&cpu));
+    assert_eq!(restored.bus.peek(0x4000), cpu.bus.peek(0x4000));
+    for address in 0..0x40 {
+        assert_eq!(restored.bus.peek(address), cpu.bus.peek(address));
+    }
+
+    // states missing the section of a device leave every device alone
+    let mut partial = SaveState::new();
+    partial.insert("cpu", state.section("cpu").unwrap().to_vec());
+    partial.insert("bus.1", vec![7]);
+
+    let mut fresh = machine();
+    assert!(matches!(
+        fresh.restore_state(&partial),
+        Err(SaveStateError::MissingSection(name)) if name == "bus.0"
+    ));
+    assert_eq!(fresh.bus.peek(0x4000), 0);
+
+    // and so do states with a broken section after a good one
+    let mut broken = state.clone();
+    broken.insert("bus.1", vec![7, 7]);
+
+    let mut fresh = machine();
+    assert!(matches!(
+        fresh.restore_state(&broken),
+        Err(SaveStateError::InvalidSection(name)) if name == "bus.1"
+    ));
+    for address in 0..0x40 {
+        assert_eq!(fresh.bus.peek(address), 0);
+    }
+}
@@ -0,0 +1,285 @@
+//! Runs test cases in the format of the SingleStepTests (formerly ProcessorTests) suite:
+//! registers and memory before and after a single instruction, and the bus activity of
+//! each of its cycles.
+//!
+//! The cases of the suite itself, captured from hardware, are read from
+//! `tests/single_step/{6502,wdc65c02}/v1`, where `fetch.py` downloads them, or from the
+//! checkout `SINGLE_STEP_TESTS` points at. The cases under `tests/single_step/model` are
+//! not hardware data: they come from the reference model of `generate.py`, written apart
+//! from the crate, and only check the crate against that model
+
+use cpu6502::cpu::{
+    addressable_bus::DataBus, error::CpuError, instruction::Instruction, variant::Variant, Cpu,
+};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+/// B and the unused bit aren't stored in the status register
+const STATUS_MASK: u8 = 0xCF;
+
+/// Memory made of the addresses a test sets, noting the address,
+/// value and direction of every access
+#[derive(Default)]
+struct SparseBus {
+    ram: HashMap<u16, u8>,
+    accesses: Vec<(u16, u8, bool)>,
+}
+
+impl DataBus for SparseBus {
+    fn peek(&self, addr: u16) -> u8 {
+        self.ram.get(&addr).copied().unwrap_or(0)
+    }
+
+    fn set(&mut self, addr: u16, x: u8) {
+        self.accesses.push((addr, x, true));
+        self.ram.insert(addr, x);
+    }
+
+    fn read(&mut self, addr: u16) -> u8 {
+        let x = self.peek(addr);
+        self.accesses.push((addr, x, false));
+        x
+    }
+}
+
+/// How the cases of an opcode went
+#[derive(Default)]
+struct Outcome {
+    passed: usize,
+    failed: Vec<String>,
+    unsupported: bool,
+}
+
+fn number(value: &Value) -> u16 {
+    value.as_u64().unwrap() as u16
+}
+
+fn load_state(cpu: &mut Cpu<SparseBus>, state: &Value) {
+    cpu.program_counter = number(&state["pc"]);
+    cpu.stack_pointer = number(&state["s"]) as u8;
+    cpu.accumulator = number(&state["a"]) as u8;
+    cpu.x_register = number(&state["x"]) as u8;
+    cpu.y_register = number(&state["y"]) as u8;
+    cpu.processor_status = (number(&state["p"]) as u8).into();
+
+    for cell in state["ram"].as_array().unwrap() {
+        cpu.bus.ram.insert(number(&cell[0]), number(&cell[1]) as u8);
+    }
+}
+
+/// Differences between the cpu and the expected `state` after running `case`
+fn compare(cpu: &Cpu<SparseBus>, case: &Value, cycles: u8) -> Vec<String> {
+    let state = &case["final"];
+    let mut errors = Vec::new();
+
+    let registers = [
+        ("pc", cpu.program_counter, number(&state["pc"])),
+        ("s", cpu.stack_pointer as u16, number(&state["s"])),
+        ("a", cpu.accumulator as u16, number(&state["a"])),
+        ("x", cpu.x_register as u16, number(&state["x"])),
+        ("y", cpu.y_register as u16, number(&state["y"])),
+        (
+            "p",
+            (cpu.processor_status.0 & STATUS_MASK) as u16,
+            number(&state["p"]) & STATUS_MASK as u16,
+        ),
+    ];
+    for (name, found, expected) in registers.iter() {
+        if found != expected {
+            errors.push(format!(
+                "{} is ${:02X}, expected ${:02X}",
+                name, found, expected
+            ));
+        }
+    }
+
+    for cell in state["ram"].as_array().unwrap() {
+        let (address, expected) = (number(&cell[0]), number(&cell[1]) as u8);
+        let found = cpu.bus.peek(address);
+
+        if found != expected {
+            errors.push(format!(
+                "${:04X} is ${:02X}, expected ${:02X}",
+                address, found, expected
+            ));
+        }
+    }
+
+    let activity: Vec<(u16, u8, bool)> = case["cycles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|cycle| {
+            (
+                number(&cycle[0]),
+                number(&cycle[1]) as u8,
+                cycle[2] == "write",
+            )
+        })
+        .collect();
+    if cycles as usize != activity.len() {
+        errors.push(format!(
+            "took {} cycles, expected {}",
+            cycles,
+            activity.len()
+        ));
+    }
+
+    // one access per cycle, in the order of the hardware
+    if cpu.bus.accesses != activity {
+        errors.push(format!(
+            "bus activity {}, expected {}",
+            describe(&cpu.bus.accesses),
+            describe(&activity)
+        ));
+    }
+
+    errors
+}
+
+fn describe(accesses: &[(u16, u8, bool)]) -> String {
+    let accesses: Vec<String> = accesses
+        .iter()
+        .map(|&(address, value, write)| {
+            let kind = if write { "w" } else { "r" };
+            format!("{}:{:04X}={:02X}", kind, address, value)
+        })
+        .collect();
+
+    format!("[{}]", accesses.join(" "))
+}
+
+/// Runs every case of `file`, unless the cpu can't decode its opcode
+fn run_file(file: &Path, variant: Variant) -> Outcome {
+    let text = std::fs::read_to_string(file).unwrap();
+    let cases: Vec<Value> = serde_json::from_str(&text).unwrap();
+    let mut outcome = Outcome::default();
+
+    let opcode = u8::from_str_radix(&file.file_stem().unwrap().to_string_lossy(), 16).unwrap();
+    if Instruction::read_variant(variant, true, opcode, || 0).is_none() {
+        outcome.unsupported = true;
+        return outcome;
+    }
+
+    for case in &cases {
+        let mut cpu = Cpu::with_variant(SparseBus::default(), variant);
+        cpu.undocumented_opcodes = true;
+        load_state(&mut cpu, &case["initial"]);
+        cpu.bus.accesses.clear();
+
+        // halting instructions still have their bus activity checked
+        let cycles = match cpu.tick() {
+            Ok(tick) => tick.cycles,
+            Err(CpuError::Jammed(_)) | Err(CpuError::Stopped) => cpu.bus.accesses.len() as u8,
+            Err(err) => {
+                outcome.failed.push(format!("{}: {}", case["name"], err));
+                continue;
+            }
+        };
+
+        match compare(&cpu, case, cycles).as_slice() {
+            [] => outcome.passed += 1,
+            errors => outcome
+                .failed
+                .push(format!("{}: {}", case["name"], errors.join(", "))),
+        }
+    }
+
+    outcome
+}
+
+/// The directory of the cases captured from hardware for the suite's `directory`
+fn hardware_cases(directory: &str) -> PathBuf {
+    let root = match std::env::var_os("SINGLE_STEP_TESTS") {
+        Some(root) => PathBuf::from(root),
+        None => Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/single_step"),
+    };
+
+    root.join(directory).join("v1")
+}
+
+/// The directory of the cases generated by the reference model
+fn model_cases(directory: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/single_step/model")
+        .join(directory)
+}
+
+/// Runs the cases found in `directory` for `variant`, reporting per opcode.
+/// Returns how many cases passed
+fn run_suite(directory: &Path, variant: Variant) -> usize {
+    let mut files: Vec<PathBuf> = match std::fs::read_dir(directory) {
+        Ok(entries) => entries.map(|entry| entry.unwrap().path()).collect(),
+        Err(_) => return 0,
+    };
+    files.retain(|file| file.extension().is_some_and(|ext| ext == "json"));
+    files.sort();
+
+    let mut outcomes = BTreeMap::new();
+    for file in &files {
+        let opcode = file.file_stem().unwrap().to_string_lossy().to_string();
+        outcomes.insert(opcode, run_file(file, variant));
+    }
+
+    let unsupported: Vec<&str> = outcomes
+        .iter()
+        .filter(|(_, outcome)| outcome.unsupported)
+        .map(|(opcode, _)| opcode.as_str())
+        .collect();
+    if !unsupported.is_empty() {
+        println!(
+            "{}: skipped opcodes the cpu doesn't decode: {}",
+            directory.display(),
+            unsupported.join(" ")
+        );
+    }
+
+    let mut report = Vec::new();
+    for (opcode, outcome) in &outcomes {
+        if let Some(first) = outcome.failed.first() {
+            report.push(format!(
+                "{}: {} of {} cases failed, first {}",
+                opcode,
+                outcome.failed.len(),
+                outcome.failed.len() + outcome.passed,
+                first
+            ));
+        }
+    }
+
+    assert!(
+        report.is_empty(),
+        "{}\n{}",
+        directory.display(),
+        report.join("\n")
+    );
+
+    outcomes.values().map(|outcome| outcome.passed).sum()
+}
+
+#[test]
+fn nmos_6502() {
+    if run_suite(&hardware_cases("6502"), Variant::Nmos6502) == 0 {
+        println!("no hardware cases for the 6502, run tests/single_step/fetch.py");
+    }
+}
+
+#[test]
+fn wdc_65c02() {
+    if run_suite(&hardware_cases("wdc65c02"), Variant::Wdc65C02) == 0 {
+        println!("no hardware cases for the 65C02, run tests/single_step/fetch.py");
+    }
+}
+
+#[test]
+fn nmos_6502_model() {
+    assert!(run_suite(&model_cases("6502"), Variant::Nmos6502) > 0);
+}
+
+#[test]
+fn wdc_65c02_model() {
+    assert!(run_suite(&model_cases("wdc65c02"), Variant::Wdc65C02) > 0);
+}
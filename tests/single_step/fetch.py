@@ -0,0 +1,37 @@
+#!/usr/bin/env python3
+"""Downloads the first cases of every opcode of the real SingleStepTests 65x02
+suite, for the NMOS 6502 and the WDC 65C02, into tests/single_step/{6502,wdc65c02}/v1
+where the runner looks for hardware data. Needs network access.
+
+The suite covers opcodes the crate doesn't decode, which the runner skips.
+
+    python3 tests/single_step/fetch.py [cases per opcode]
+"""
+
+import json
+import sys
+import urllib.request
+from pathlib import Path
+
+URL = "https://raw.githubusercontent.com/SingleStepTests/65x02/main/%s/v1/%02x.json"
+
+
+def main():
+    count = int(sys.argv[1]) if len(sys.argv) > 1 else 10
+    root = Path(__file__).resolve().parent
+
+    for suite in ("6502", "wdc65c02"):
+        directory = root / suite / "v1"
+        directory.mkdir(parents=True, exist_ok=True)
+
+        for opcode in range(0x100):
+            with urllib.request.urlopen(URL % (suite, opcode)) as response:
+                cases = json.load(response)[:count]
+
+            lines = ",\n".join(json.dumps(case, separators=(",", ":")) for case in cases)
+            (directory / ("%02x.json" % opcode)).write_text("[\n%s\n]\n" % lines)
+            print(suite, "%02x" % opcode, len(cases))
+
+
+if __name__ == "__main__":
+    main()
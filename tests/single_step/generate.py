@@ -0,0 +1,547 @@
+#!/usr/bin/env python3
+"""Generates test cases in the format of the SingleStepTests 65x02 suite.
+
+These aren't hardware captures: they come from the reference model below and go
+to tests/single_step/model, apart from the real suite's cases which fetch.py
+puts in tests/single_step/{6502,wdc65c02}/v1. The model is written independently
+of the crate: it has its own opcode table, and the cycle
+count of an instruction is the length of its bus activity, which follows the
+cycle by cycle descriptions of "64doc" (John West and Marko Makela) for NMOS
+parts, the decimal mode appendix of Bruce Clark's "Decimal Mode" tutorial, and
+the WDC W65C02S datasheet. The 65C02 reads made while an address is being
+computed are modelled as re-reading the last byte of the instruction.
+
+Instructions which halt the cpu (JAM, STP, and WAI with no interrupt pending)
+are modelled as leaving the program counter on their opcode. No source tells
+what the 65C02's eight cycle $5C puts on the bus: it is modelled as reading its
+absolute operand for the five cycles after the operand bytes.
+
+    python3 tests/single_step/generate.py [cases per opcode]
+"""
+
+import json
+import random
+import sys
+from pathlib import Path
+
+# addressing modes
+IMP, ACC, IMM, ZP, ZPX, ZPY, ABS, ABX, ABY, IND, IZX, IZY, IZP, IAX, REL, ZPR = (
+    "imp acc imm zp zpx zpy abs abx aby ind izx izy izp iax rel zpr".split()
+)
+
+# the eight columns of the 6502 ALU group, then the read-modify-write group
+ALU = ["ORA", "AND", "EOR", "ADC", "STA", "LDA", "CMP", "SBC"]
+ALU_MODES = {0x01: IZX, 0x05: ZP, 0x09: IMM, 0x0D: ABS, 0x11: IZY, 0x15: ZPX, 0x19: ABY, 0x1D: ABX}
+SHIFTS = ["ASL", "ROL", "LSR", "ROR"]
+
+NMOS = {
+    0x00: ("BRK", IMP), 0x08: ("PHP", IMP), 0x10: ("BPL", REL), 0x18: ("CLC", IMP),
+    0x20: ("JSR", ABS), 0x24: ("BIT", ZP), 0x28: ("PLP", IMP), 0x2C: ("BIT", ABS),
+    0x30: ("BMI", REL), 0x38: ("SEC", IMP), 0x40: ("RTI", IMP), 0x48: ("PHA", IMP),
+    0x4C: ("JMP", ABS), 0x50: ("BVC", REL), 0x58: ("CLI", IMP), 0x60: ("RTS", IMP),
+    0x68: ("PLA", IMP), 0x6C: ("JMP", IND), 0x70: ("BVS", REL), 0x78: ("SEI", IMP),
+    0x84: ("STY", ZP), 0x88: ("DEY", IMP), 0x8C: ("STY", ABS), 0x90: ("BCC", REL),
+    0x94: ("STY", ZPX), 0x98: ("TYA", IMP), 0xA0: ("LDY", IMM), 0xA4: ("LDY", ZP),
+    0xA8: ("TAY", IMP), 0xAC: ("LDY", ABS), 0xB0: ("BCS", REL), 0xB4: ("LDY", ZPX),
+    0xB8: ("CLV", IMP), 0xBC: ("LDY", ABX), 0xC0: ("CPY", IMM), 0xC4: ("CPY", ZP),
+    0xC8: ("INY", IMP), 0xCC: ("CPY", ABS), 0xD0: ("BNE", REL), 0xD8: ("CLD", IMP),
+    0xE0: ("CPX", IMM), 0xE4: ("CPX", ZP), 0xE8: ("INX", IMP), 0xEC: ("CPX", ABS),
+    0xF0: ("BEQ", REL), 0xF8: ("SED", IMP),
+    0x86: ("STX", ZP), 0x8A: ("TXA", IMP), 0x8E: ("STX", ABS), 0x96: ("STX", ZPY),
+    0x9A: ("TXS", IMP), 0xA2: ("LDX", IMM), 0xA6: ("LDX", ZP), 0xAA: ("TAX", IMP),
+    0xAE: ("LDX", ABS), 0xB6: ("LDX", ZPY), 0xBA: ("TSX", IMP), 0xBE: ("LDX", ABY),
+    0xC6: ("DEC", ZP), 0xCA: ("DEX", IMP), 0xCE: ("DEC", ABS), 0xD6: ("DEC", ZPX),
+    0xDE: ("DEC", ABX), 0xE6: ("INC", ZP), 0xEA: ("NOP", IMP), 0xEE: ("INC", ABS),
+    0xF6: ("INC", ZPX), 0xFE: ("INC", ABX),
+}
+for column, name in enumerate(ALU):
+    for low, mode in ALU_MODES.items():
+        if (name, mode) != ("STA", IMM):
+            NMOS[column << 5 | low] = (name, mode)
+for column, name in enumerate(SHIFTS):
+    for low, mode in {0x06: ZP, 0x0A: ACC, 0x0E: ABS, 0x16: ZPX, 0x1E: ABX}.items():
+        NMOS[column << 5 | low] = (name, mode)
+
+# the undocumented opcodes the crate implements
+UNDOCUMENTED = dict(NMOS)
+for base, name in ((0x00, "SLO"), (0x20, "RLA"), (0x40, "SRE"), (0x60, "RRA"), (0xC0, "DCP"), (0xE0, "ISC")):
+    for low, mode in {0x03: IZX, 0x07: ZP, 0x0F: ABS, 0x13: IZY, 0x17: ZPX, 0x1B: ABY, 0x1F: ABX}.items():
+        UNDOCUMENTED[base | low] = (name, mode)
+UNDOCUMENTED.update({
+    0x83: ("SAX", IZX), 0x87: ("SAX", ZP), 0x8F: ("SAX", ABS), 0x97: ("SAX", ZPY),
+    0xA3: ("LAX", IZX), 0xA7: ("LAX", ZP), 0xAF: ("LAX", ABS), 0xB3: ("LAX", IZY),
+    0xB7: ("LAX", ZPY), 0xBF: ("LAX", ABY),
+})
+UNDOCUMENTED.update({
+    0x0B: ("ANC", IMM), 0x2B: ("ANC", IMM), 0x4B: ("ALR", IMM), 0x6B: ("ARR", IMM),
+    0xCB: ("AXS", IMM), 0xEB: ("SBC", IMM),
+})
+for opcode in (0x80, 0x82, 0x89, 0xC2, 0xE2):
+    UNDOCUMENTED[opcode] = ("NOP", IMM)
+for opcode in (0x04, 0x44, 0x64):
+    UNDOCUMENTED[opcode] = ("NOP", ZP)
+for opcode in (0x14, 0x34, 0x54, 0x74, 0xD4, 0xF4):
+    UNDOCUMENTED[opcode] = ("NOP", ZPX)
+for opcode in (0x1C, 0x3C, 0x5C, 0x7C, 0xDC, 0xFC):
+    UNDOCUMENTED[opcode] = ("NOP", ABX)
+for opcode in (0x1A, 0x3A, 0x5A, 0x7A, 0xDA, 0xFA):
+    UNDOCUMENTED[opcode] = ("NOP", IMP)
+UNDOCUMENTED[0x0C] = ("NOP", ABS)
+for opcode in (0x02, 0x12, 0x22, 0x32, 0x42, 0x52, 0x62, 0x72, 0x92, 0xB2, 0xD2, 0xF2):
+    UNDOCUMENTED[opcode] = ("JAM", IMP)
+
+CMOS = dict(NMOS)
+for column, name in enumerate(ALU):
+    CMOS[column << 5 | 0x12] = (name, IZP)
+for bit in range(8):
+    CMOS[bit << 4 | 0x07] = ("RMB", ZP)
+    CMOS[bit << 4 | 0x87] = ("SMB", ZP)
+    CMOS[bit << 4 | 0x0F] = ("BBR", ZPR)
+    CMOS[bit << 4 | 0x8F] = ("BBS", ZPR)
+CMOS.update({
+    0x04: ("TSB", ZP), 0x0C: ("TSB", ABS), 0x14: ("TRB", ZP), 0x1C: ("TRB", ABS),
+    0x1A: ("INC", ACC), 0x3A: ("DEC", ACC), 0x34: ("BIT", ZPX), 0x3C: ("BIT", ABX),
+    0x89: ("BIT", IMM), 0x5A: ("PHY", IMP), 0x7A: ("PLY", IMP), 0xDA: ("PHX", IMP),
+    0xFA: ("PLX", IMP), 0x64: ("STZ", ZP), 0x74: ("STZ", ZPX), 0x9C: ("STZ", ABS),
+    0x9E: ("STZ", ABX), 0x7C: ("JMP", IAX), 0x80: ("BRA", REL),
+    0x44: ("NOP", ZP), 0x54: ("NOP", ZPX), 0xD4: ("NOP", ZPX), 0xF4: ("NOP", ZPX),
+    0xDC: ("NOP", ABS), 0xFC: ("NOP", ABS), 0x5C: ("NOP8", ABS),
+    0xCB: ("WAI", IMP), 0xDB: ("STP", IMP),
+})
+for opcode in (0x02, 0x22, 0x42, 0x62, 0x82, 0xC2, 0xE2):
+    CMOS[opcode] = ("NOP", IMM)
+for opcode in range(0x03, 0x100, 0x10):
+    CMOS[opcode] = ("NOP1", IMP)
+for opcode in (0x0B, 0x1B, 0x2B, 0x3B, 0x4B, 0x5B, 0x6B, 0x7B, 0x8B, 0x9B, 0xAB, 0xBB, 0xEB, 0xFB):
+    CMOS[opcode] = ("NOP1", IMP)
+
+STORES = {"STA", "STX", "STY", "STZ", "SAX"}
+MODIFIES = {"ASL", "LSR", "ROL", "ROR", "INC", "DEC", "SLO", "RLA", "SRE", "RRA", "DCP", "ISC", "TRB", "TSB", "RMB", "SMB"}
+BRANCHES = {"BPL": (0x80, 0), "BMI": (0x80, 1), "BVC": (0x40, 0), "BVS": (0x40, 1),
+            "BCC": (0x01, 0), "BCS": (0x01, 1), "BNE": (0x02, 0), "BEQ": (0x02, 1)}
+
+C, Z, I, D, B, U, V, N = (1 << bit for bit in range(8))
+
+
+class Model:
+    def __init__(self, cmos, rng, opcode):
+        self.cmos = cmos
+        self.rng = rng
+        self.ram = {}
+        self.initial = {}
+        self.cycles = []
+
+        self.pc = rng.randrange(0x10000)
+        self.s, self.a, self.x, self.y = (rng.randrange(0x100) for _ in range(4))
+        self.p = rng.randrange(0x100) | U | B
+        self.poke(self.pc, opcode)
+
+    def state(self, ram):
+        return {"pc": self.pc, "s": self.s, "a": self.a, "x": self.x, "y": self.y, "p": self.p,
+                "ram": [[address, value] for address, value in sorted(ram.items())]}
+
+    def poke(self, address, value):
+        self.ram[address] = self.initial[address] = value
+
+    def touch(self, address):
+        if address not in self.ram:
+            self.poke(address, self.rng.randrange(0x100))
+
+    def read(self, address):
+        address &= 0xFFFF
+        self.touch(address)
+        self.cycles.append([address, self.ram[address], "read"])
+        return self.ram[address]
+
+    def write(self, address, value):
+        address &= 0xFFFF
+        self.touch(address)
+        self.ram[address] = value & 0xFF
+        self.cycles.append([address, value & 0xFF, "write"])
+
+    def fetch(self):
+        value = self.read(self.pc)
+        self.pc = (self.pc + 1) & 0xFFFF
+        return value
+
+    def dummy(self, address):
+        """A read made while computing an address"""
+        self.read((self.pc - 1) if self.cmos else address)
+
+    def push(self, value):
+        self.write(0x100 | self.s, value)
+        self.s = (self.s - 1) & 0xFF
+
+    def pull(self):
+        self.s = (self.s + 1) & 0xFF
+        return self.read(0x100 | self.s)
+
+    def flag(self, mask, on):
+        self.p = (self.p | mask) if on else (self.p & ~mask & 0xFF)
+
+    def nz(self, value):
+        value &= 0xFF
+        self.flag(Z, value == 0)
+        self.flag(N, value & 0x80)
+        return value
+
+    # addressing
+
+    def address(self, mode, kind):
+        """Runs the cycles computing the effective address of `mode`"""
+        if mode in (ZP, ZPR):
+            return self.fetch()
+        if mode in (ZPX, ZPY):
+            base = self.fetch()
+            self.dummy(base)
+            return (base + (self.x if mode == ZPX else self.y)) & 0xFF
+        if mode == ABS:
+            return self.fetch() | self.fetch() << 8
+        if mode == IZX:
+            pointer = self.fetch()
+            self.dummy(pointer)
+            pointer = (pointer + self.x) & 0xFF
+            return self.read(pointer) | self.read((pointer + 1) & 0xFF) << 8
+        if mode == IZP:
+            pointer = self.fetch()
+            return self.read(pointer) | self.read((pointer + 1) & 0xFF) << 8
+
+        if mode in (ABX, ABY):
+            base = self.fetch() | self.fetch() << 8
+        else:
+            pointer = self.fetch()
+            base = self.read(pointer) | self.read((pointer + 1) & 0xFF) << 8
+        index = self.x if mode == ABX else self.y
+        address = (base + index) & 0xFFFF
+
+        # the low byte is added first, and the half computed address gets read
+        # while carrying. Reads which don't cross a page go without that cycle
+        if (base ^ address) & 0xFF00 or kind != "read":
+            self.dummy((base & 0xFF00) | (address & 0xFF))
+        return address
+
+    def operand(self, mode):
+        if mode == IMM:
+            return self.fetch()
+        if mode == ACC:
+            return self.a
+        return self.read(self.address(mode, "read"))
+
+    # arithmetic
+
+    def adc(self, value):
+        a, carry = self.a, self.p & C
+        binary = a + value + carry
+        if not self.p & D:
+            self.flag(C, binary > 0xFF)
+            self.flag(V, (a ^ binary) & (value ^ binary) & 0x80)
+            self.a = self.nz(binary)
+            return
+
+        low = (a & 0x0F) + (value & 0x0F) + carry
+        if low >= 0x0A:
+            low = ((low + 0x06) & 0x0F) + 0x10
+        result = (a & 0xF0) + (value & 0xF0) + low
+
+        # N and V come from the sum before the high nibble gets adjusted
+        signed = (a & 0xF0) - (0x100 if a & 0x80 else 0) + (value & 0xF0) - (0x100 if value & 0x80 else 0) + low
+        self.flag(V, signed < -128 or signed > 127)
+        if result >= 0xA0:
+            result += 0x60
+        self.flag(C, result >= 0x100)
+
+        if self.cmos:
+            self.nz(result)
+            self.read(self.pc - 1)
+        else:
+            self.flag(N, signed & 0x80)
+            self.flag(Z, not (binary & 0xFF))
+        self.a = result & 0xFF
+
+    def sbc(self, value):
+        a, borrow = self.a, 1 - (self.p & C)
+        binary = a - value - borrow
+        self.flag(C, binary >= 0)
+        self.flag(V, (a ^ value) & (a ^ binary) & 0x80)
+        if not self.p & D:
+            self.a = self.nz(binary)
+            return
+
+        low = (a & 0x0F) - (value & 0x0F) - borrow
+        if self.cmos:
+            result = binary
+            if result < 0:
+                result -= 0x60
+            if low < 0:
+                result -= 0x06
+            self.a = self.nz(result)
+            self.read(self.pc - 1)
+            return
+
+        if low < 0:
+            low = ((low - 0x06) & 0x0F) - 0x10
+        result = (a & 0xF0) - (value & 0xF0) + low
+        if result < 0:
+            result -= 0x60
+        self.nz(binary)
+        self.a = result & 0xFF
+
+    def compare(self, register, value):
+        self.flag(C, register >= value)
+        self.nz(register - value)
+
+    def modify(self, name, value):
+        carry = self.p & C
+        if name in ("ASL", "SLO", "ROL", "RLA"):
+            result = (value << 1 | (carry if name in ("ROL", "RLA") else 0)) & 0xFF
+            self.flag(C, value & 0x80)
+        elif name in ("LSR", "SRE", "ROR", "RRA"):
+            result = value >> 1 | (carry << 7 if name in ("ROR", "RRA") else 0)
+            self.flag(C, value & 0x01)
+        elif name in ("INC", "ISC"):
+            result = (value + 1) & 0xFF
+        elif name in ("DEC", "DCP"):
+            result = (value - 1) & 0xFF
+        elif name == "TRB":
+            self.flag(Z, not self.a & value)
+            return value & ~self.a & 0xFF
+        elif name == "TSB":
+            self.flag(Z, not self.a & value)
+            return value | self.a
+
+        if name in ("ASL", "ROL", "LSR", "ROR", "INC", "DEC"):
+            self.nz(result)
+        elif name == "SLO":
+            self.a = self.nz(self.a | result)
+        elif name == "RLA":
+            self.a = self.nz(self.a & result)
+        elif name == "SRE":
+            self.a = self.nz(self.a ^ result)
+        elif name == "RRA":
+            self.adc(result)
+        elif name == "DCP":
+            self.compare(self.a, result)
+        elif name == "ISC":
+            self.sbc(result)
+        return result
+
+    def arr(self, value):
+        carry = self.p & C
+        value &= self.a
+        result = value >> 1 | carry << 7
+        if not self.p & D:
+            self.a = self.nz(result)
+            self.flag(C, result & 0x40)
+            self.flag(V, (result >> 6 ^ result >> 5) & 1)
+            return
+
+        self.flag(N, carry)
+        self.flag(Z, result == 0)
+        self.flag(V, (value ^ result) & 0x40)
+        high, low = value >> 4, value & 0x0F
+        if low + (low & 1) > 5:
+            result = (result & 0xF0) | ((result + 6) & 0x0F)
+        self.flag(C, high + (high & 1) > 5)
+        if self.p & C:
+            result = (result + 0x60) & 0xFF
+        self.a = result
+
+    # control flow
+
+    def branch(self, taken):
+        offset = self.fetch()
+        if not taken:
+            return
+        self.read(self.pc)
+        target = (self.pc + offset - (0x100 if offset & 0x80 else 0)) & 0xFFFF
+        if (target ^ self.pc) & 0xFF00:
+            self.dummy((self.pc & 0xFF00) | (target & 0xFF))
+        self.pc = target
+
+    def step(self):
+        opcode = self.fetch()
+        name, mode = (CMOS if self.cmos else UNDOCUMENTED)[opcode]
+
+        if mode in (IMP, ACC) and name != "NOP1":
+            self.read(self.pc)
+
+        if name in BRANCHES:
+            mask, set_ = BRANCHES[name]
+            self.branch(bool(self.p & mask) == bool(set_))
+        elif name == "BRA":
+            self.branch(True)
+        elif name in ("BBR", "BBS"):
+            address = self.fetch()
+            value = self.read(address)
+            self.read(address)
+            self.branch(bool(value >> (opcode >> 4 & 7) & 1) == (name == "BBS"))
+        elif name == "BRK":
+            self.pc = (self.pc + 1) & 0xFFFF
+            self.push(self.pc >> 8)
+            self.push(self.pc & 0xFF)
+            self.push(self.p | B | U)
+            self.flag(I, True)
+            if self.cmos:
+                self.flag(D, False)
+            self.pc = self.read(0xFFFE) | self.read(0xFFFF) << 8
+        elif name == "JSR":
+            low = self.fetch()
+            self.read(0x100 | self.s)
+            self.push(self.pc >> 8)
+            self.push(self.pc & 0xFF)
+            self.pc = low | self.fetch() << 8
+        elif name == "RTS":
+            self.read(0x100 | self.s)
+            self.pc = self.pull() | self.pull() << 8
+            self.fetch()
+        elif name == "RTI":
+            self.read(0x100 | self.s)
+            self.p = self.pull() | B | U
+            self.pc = self.pull() | self.pull() << 8
+        elif name in ("PHA", "PHP", "PHX", "PHY"):
+            self.push({"PHA": self.a, "PHP": self.p | B | U, "PHX": self.x, "PHY": self.y}[name])
+        elif name in ("PLA", "PLP", "PLX", "PLY"):
+            self.read(0x100 | self.s)
+            value = self.pull()
+            if name == "PLP":
+                self.p = value | B | U
+            else:
+                setattr(self, name[2].lower(), self.nz(value))
+        elif name == "JMP" and mode == ABS:
+            self.pc = self.fetch() | self.fetch() << 8
+        elif name == "JMP":
+            pointer = self.fetch() | self.fetch() << 8
+            if mode == IAX:
+                self.dummy(pointer)
+                pointer = (pointer + self.x) & 0xFFFF
+                self.pc = self.read(pointer) | self.read(pointer + 1) << 8
+            elif self.cmos:
+                self.dummy(pointer)
+                self.pc = self.read(pointer) | self.read(pointer + 1) << 8
+            else:
+                self.pc = self.read(pointer) | self.read((pointer & 0xFF00) | ((pointer + 1) & 0xFF)) << 8
+
+        elif name in STORES:
+            value = {"STA": self.a, "STX": self.x, "STY": self.y, "STZ": 0, "SAX": self.a & self.x}[name]
+            self.write(self.address(mode, "write"), value)
+        elif name in MODIFIES and mode == ACC:
+            self.a = self.modify(name, self.a)
+        elif name in MODIFIES:
+            # the 65C02 shifts by abs,X skip the extra cycle when no page gets crossed
+            shift = self.cmos and name in SHIFTS
+            address = self.address(mode, "read" if shift else "modify")
+            value = self.read(address)
+            if self.cmos:
+                self.read(address)
+            else:
+                self.write(address, value)
+            if name in ("RMB", "SMB"):
+                bit = 1 << (opcode >> 4 & 7)
+                result = (value | bit) if name == "SMB" else (value & ~bit & 0xFF)
+            else:
+                result = self.modify(name, value)
+            self.write(address, result)
+
+        elif name == "JAM":
+            self.pc = (self.pc - 1) & 0xFFFF
+        elif name in ("STP", "WAI"):
+            self.read(self.pc)
+            self.pc = (self.pc - 1) & 0xFFFF
+        elif name == "NOP8":
+            address = self.address(mode, "read")
+            for _ in range(5):
+                self.read(address)
+        elif name in ("NOP", "NOP1"):
+            if mode not in (IMP, ACC):
+                self.operand(mode)
+        elif name in ("CLC", "SEC", "CLI", "SEI", "CLV", "CLD", "SED"):
+            self.flag({"C": C, "I": I, "V": V, "D": D}[name[2]], name[0] == "S")
+        elif name in ("TAX", "TAY", "TXA", "TYA", "TSX"):
+            source = {"A": self.a, "X": self.x, "Y": self.y, "S": self.s}[name[1]]
+            setattr(self, name[2].lower(), self.nz(source))
+        elif name == "TXS":
+            self.s = self.x
+        elif name in ("INX", "INY", "DEX", "DEY"):
+            register = name[2].lower()
+            step = 1 if name[0] == "I" else -1
+            setattr(self, register, self.nz(getattr(self, register) + step))
+
+        else:
+            value = self.operand(mode)
+            if name == "LDA":
+                self.a = self.nz(value)
+            elif name == "LDX":
+                self.x = self.nz(value)
+            elif name == "LDY":
+                self.y = self.nz(value)
+            elif name == "LAX":
+                self.a = self.x = self.nz(value)
+            elif name == "ORA":
+                self.a = self.nz(self.a | value)
+            elif name == "AND":
+                self.a = self.nz(self.a & value)
+            elif name == "EOR":
+                self.a = self.nz(self.a ^ value)
+            elif name == "ADC":
+                self.adc(value)
+            elif name == "SBC":
+                self.sbc(value)
+            elif name in ("CMP", "CPX", "CPY"):
+                self.compare({"CMP": self.a, "CPX": self.x, "CPY": self.y}[name], value)
+            elif name == "BIT":
+                self.flag(Z, not self.a & value)
+                if mode != IMM:
+                    self.flag(N, value & 0x80)
+                    self.flag(V, value & 0x40)
+            elif name == "ANC":
+                self.a = self.nz(self.a & value)
+                self.flag(C, self.a & 0x80)
+            elif name == "ALR":
+                value &= self.a
+                self.flag(C, value & 1)
+                self.a = self.nz(value >> 1)
+            elif name == "ARR":
+                self.arr(value)
+            elif name == "AXS":
+                value = (self.a & self.x) - value
+                self.flag(C, value >= 0)
+                self.x = self.nz(value)
+            else:
+                raise ValueError(name)
+
+
+def case(cmos, rng, opcode):
+    model = Model(cmos, rng, opcode)
+    # the bytes after the opcode, for the name of the case
+    for offset in (1, 2):
+        model.touch((model.pc + offset) & 0xFFFF)
+    name = " ".join("%02x" % model.ram[(model.pc + offset) & 0xFFFF] for offset in range(3))
+
+    initial_registers = model.state({})
+    model.step()
+    initial = dict(initial_registers, ram=model.state(model.initial)["ram"])
+
+    return {"name": name, "initial": initial, "final": model.state(model.ram), "cycles": model.cycles}
+
+
+def generate(directory, cmos, count):
+    table = CMOS if cmos else UNDOCUMENTED
+    directory.mkdir(parents=True, exist_ok=True)
+
+    for opcode in sorted(table):
+        rng = random.Random("%s %02x" % (directory.name, opcode))
+        cases = [case(cmos, rng, opcode) for _ in range(count)]
+        lines = ",\n".join(json.dumps(c, separators=(",", ":")) for c in cases)
+        (directory / ("%02x.json" % opcode)).write_text("[\n%s\n]\n" % lines)
+
+
+def main():
+    count = int(sys.argv[1]) if len(sys.argv) > 1 else 10
+    root = Path(__file__).resolve().parent
+
+    generate(root / "model" / "6502", False, count)
+    generate(root / "model" / "wdc65c02", True, count)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,94 @@
+use cpu6502::loader::{error::LoadError, srec, Block};
+
+const PROGRAM: &str = "\
+S00600004844521B
+S1078000A20186004F
+S1058004EA028A
+S20600FFFC00807E
+S3060000020042B5
+S5030003F9
+S90380007C
+";
+
+#[test]
+fn parses_every_address_size() {
+    let program = srec::parse(PROGRAM).unwrap();
+
+    assert_eq!(
+        program.blocks,
+        [
+            Block {
+                address: 0x8000,
+                data: vec![0xA2, 0x01, 0x86, 0x00, 0xEA, 0x02]
+            },
+            Block {
+                address: 0xFFFC,
+                data: vec![0x00, 0x80]
+            },
+            Block {
+                address: 0x0200,
+                data: vec![0x42]
+            },
+        ]
+    );
+    assert_eq!(program.entry, Some(0x8000));
+}
+
+#[test]
+fn zero_termination_address_is_no_entry() {
+    let program = srec::parse("S1058004EA028A\nS9030000FC").unwrap();
+
+    assert_eq!(program.entry, None);
+}
+
+#[test]
+fn detection() {
+    assert!(srec::is_srec(PROGRAM.as_bytes()));
+    assert!(!srec::is_srec(b"Some text"));
+    assert!(!srec::is_srec(b":00000001FF"));
+}
+
+#[test]
+fn errors() {
+    assert!(matches!(
+        srec::parse("S00600004844521B\nS1078000A201860050"),
+        Err(LoadError::Checksum {
+            line: 2,
+            expected: 0x4F,
+            found: 0x50
+        })
+    ));
+    assert!(matches!(
+        srec::parse("S1088000A20186004F"),
+        Err(LoadError::InvalidRecord { line: 1, .. })
+    ));
+    assert!(matches!(
+        srec::parse("S4030000FC"),
+        Err(LoadError::UnknownRecordType {
+            line: 1,
+            record_type: 4
+        })
+    ));
+    assert!(matches!(
+        srec::parse("S1058004EA028A"),
+        Err(LoadError::MissingEndRecord)
+    ));
+    assert!(matches!(
+        srec::parse("S80401234592"),
+        Err(LoadError::AddressOutOfRange {
+            line: 1,
+            address: 0x12345
+        })
+    ));
+    assert!(matches!(
+        srec::parse("S307FFFFFFFF0102F9\nS9030000FC"),
+        Err(LoadError::AddressOutOfRange {
+            line: 1,
+            address: 0xFFFFFFFF
+        })
+    ));
+    assert!(matches!(
+        srec::parse("X1058004EA028A"),
+        Err(LoadError::InvalidRecord { line: 1, .. })
+    ));
+}
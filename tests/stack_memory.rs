@@ -0,0 +1,45 @@
+use cpu6502::{cpu::addressable_bus::DataBus, stack_memory::StackMemory};
+
+#[test]
+fn covers_the_whole_address_space() {
+    let mut memory = StackMemory::new();
+    memory.set_word(0xFFFE, 0xBEEF);
+
+    assert_eq!(memory.as_slice().len(), 0x10000);
+    assert_eq!(memory.peek(0xFFFF), 0xBE);
+    assert_eq!(memory.peek_word(0xFFFE), 0xBEEF);
+}
+
+#[test]
+fn loads_a_full_image() {
+    let image: Vec<u8> = (0..0x10000).map(|x| x as u8).collect();
+
+    let mut memory = StackMemory::new();
+    memory.load_data(0, &image);
+
+    assert_eq!(memory.as_slice(), &image[..]);
+}
+
+#[test]
+fn drops_data_past_the_address_space() {
+    let mut memory = StackMemory::new();
+    memory.load_data(0xFFFE, &[1, 2, 3]);
+
+    assert_eq!(memory.read_range(0xFFFE..=0xFFFF), &[1, 2]);
+    assert_eq!(memory.peek(0x0000), 0);
+}
+
+#[test]
+fn dumps_ranges() {
+    let mut memory = StackMemory::new();
+    memory.load_data(0x8000, b"Hello, 6502!\x00\x01\x02\x03\xFF");
+
+    let mut out = Vec::new();
+    memory.dump(0x8000..=0x8010, &mut out).unwrap();
+
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "8000: 48 65 6C 6C 6F 2C 20 36 35 30 32 21 00 01 02 03  |Hello, 6502!....|\n\
+         8010: FF                                               |.|\n"
+    );
+}
@@ -0,0 +1,142 @@
+use cpu6502::{
+    assembler::assemble,
+    cpu::Cpu,
+    debugger::{repl, watch::Watched, Debugger},
+    disassembler::disassemble,
+    stack_memory::StackMemory,
+    symbols::Symbols,
+    trace,
+};
+
+const PROGRAM: &str = "
+* = $8000
+count = $10
+reset   ldx #$03
+loop    dec count
+        dex
+        bne loop
+        .byt $22
+";
+
+fn program() -> (Cpu<StackMemory>, Symbols) {
+    let assembly = assemble(PROGRAM).unwrap();
+    let mut memory = StackMemory::new();
+    assembly.load_into(&mut memory);
+
+    let mut cpu = Cpu::load_memory(memory);
+    cpu.program_counter = assembly.origin;
+
+    (cpu, Symbols::from(&assembly))
+}
+
+#[test]
+fn parses_xa_label_listings() {
+    let symbols = Symbols::parse("reset, 0x8000, 1, 0x0000\nloop, 0x8002, 1, 0x0000\n").unwrap();
+
+    assert_eq!(symbols.get("loop"), Some(0x8002));
+    assert_eq!(symbols.label(0x8000), Some("reset"));
+
+    assert!(Symbols::parse("loop, 0xZZ, 1, 0x0000").is_err());
+}
+
+#[test]
+fn parses_vice_labels() {
+    let symbols = Symbols::parse("al C:8000 .reset\nal 008002 .loop\nbreak 8000\n").unwrap();
+
+    assert_eq!(symbols.get("reset"), Some(0x8000));
+    assert_eq!(symbols.label(0x8002), Some("loop"));
+}
+
+#[test]
+fn parses_ca65_debug_info() {
+    let dbg = "version\tmajor=2,minor=0
+sym\tid=0,name=\"loop\",addrsize=absolute,scope=0,def=3,val=0x8002,seg=0,type=lab
+sym\tid=1,name=\"count\",addrsize=zeropage,scope=0,def=1,val=0x10,type=equ
+sym\tid=2,name=\"chrout\",addrsize=absolute,scope=0,def=2,type=imp
+";
+    let symbols = Symbols::parse(dbg).unwrap();
+
+    assert_eq!(symbols.label(0x8002), Some("loop"));
+    // equates are known by name only
+    assert_eq!(symbols.get("count"), Some(0x10));
+    assert_eq!(symbols.label(0x10), None);
+    assert_eq!(symbols.get("chrout"), None);
+}
+
+#[test]
+fn describes_addresses_from_the_closest_label() {
+    let mut symbols = Symbols::new();
+    symbols.insert("loop", 0x8010);
+
+    assert_eq!(symbols.format(0x8010), "loop");
+    assert_eq!(symbols.format(0x8013), "loop+3");
+    assert_eq!(symbols.format(0x800F), "$800F");
+    assert_eq!(symbols.format(0x9000), "$9000");
+}
+
+#[test]
+fn names_operands_in_listings_and_traces() {
+    let (cpu, symbols) = program();
+
+    let listing: Vec<String> = disassemble(&cpu.bus, 0x8000, 0x8005)
+        .iter()
+        .map(|line| line.display_with(&symbols).to_string())
+        .collect();
+    assert_eq!(listing[1], "8002  C6 10     DEC $10");
+    assert_eq!(listing[3], "8005  D0 FB     BNE loop");
+
+    // definitions can be referred to, but don't name addresses
+    assert_eq!(symbols.get("count"), Some(0x10));
+    assert_eq!(symbols.label(0x10), None);
+
+    assert_eq!(
+        trace::line_with(&cpu, &symbols),
+        "8000  A2 03     LDX #$03                        A:00 X:00 Y:00 P:00 SP:00 CYC:0"
+    );
+}
+
+#[test]
+fn breakpoints_by_name() {
+    let (cpu, symbols) = program();
+    let mut debugger = Debugger::new(cpu.map_bus(Watched::new));
+    debugger.symbols = symbols;
+
+    let input = b"break loop+3\ncontinue\ncontinue\n";
+    let mut output = Vec::new();
+    repl::run(&mut debugger, &input[..], &mut output).unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("Breakpoint at loop+3\n8005  D0 FB     BNE loop"));
+    assert_eq!(debugger.cpu.x_register, 1);
+}
+
+#[test]
+fn disasm_subcommand_takes_symbols() {
+    let dir = std::env::temp_dir();
+    let binary = dir.join(format!("symbols-{}.bin", std::process::id()));
+    let labels = dir.join(format!("symbols-{}.lbl", std::process::id()));
+
+    std::fs::write(&binary, assemble(PROGRAM).unwrap().image).unwrap();
+    std::fs::write(
+        &labels,
+        "al C:8000 .reset\nal C:8002 .loop\nal C:0010 .count\n",
+    )
+    .unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_cpu6502"))
+        .arg("disasm")
+        .arg("--symbols")
+        .arg(&labels)
+        .arg(&binary)
+        .output()
+        .unwrap();
+
+    std::fs::remove_file(&binary).unwrap();
+    std::fs::remove_file(&labels).unwrap();
+
+    let listing = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success());
+    assert!(listing.contains("loop:\n"));
+    assert!(listing.contains("DEC count"));
+    assert!(listing.contains("BNE loop"));
+}
@@ -0,0 +1,74 @@
+use cpu6502::{
+    cpu::{addressable_bus::DataBus, Cpu},
+    stack_memory::StackMemory,
+    trace,
+};
+
+fn cpu_with(program: &[u8]) -> Cpu<StackMemory> {
+    let mut memory = StackMemory::new();
+    for (i, &byte) in program.iter().enumerate() {
+        memory.set(0xC000 + i as u16, byte);
+    }
+
+    let mut cpu = Cpu::load_memory(memory);
+    cpu.program_counter = 0xC000;
+    cpu.stack_pointer = 0xFD;
+    cpu.processor_status = 0x24.into();
+    cpu.cycles = 7;
+
+    cpu
+}
+
+#[test]
+fn nestest_columns() {
+    let cpu = cpu_with(&[0x4C, 0xF5, 0xC5]);
+
+    assert_eq!(
+        trace::line(&cpu),
+        "C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD CYC:7"
+    );
+}
+
+#[test]
+fn memory_operands_show_their_value() {
+    // LDA ($80,X)
+    let mut cpu = cpu_with(&[0xA1, 0x80]);
+    cpu.x_register = 0x02;
+    cpu.bus.set_word(0x82, 0x0200);
+    cpu.bus.set(0x0200, 0x5A);
+
+    assert_eq!(
+        trace::line(&cpu),
+        "C000  A1 80     LDA ($80,X) @ 82 = 0200 = 5A    A:00 X:02 Y:00 P:24 SP:FD CYC:7"
+    );
+
+    // STA $0300,Y
+    let mut cpu = cpu_with(&[0x99, 0x00, 0x03]);
+    cpu.y_register = 0x89;
+    cpu.bus.set(0x0389, 0x11);
+
+    assert!(trace::line(&cpu).starts_with("C000  99 00 03  STA $0300,Y @ 0389 = 11  "));
+}
+
+#[test]
+fn undocumented_opcodes_are_marked() {
+    // LAX ($89),Y
+    let mut cpu = cpu_with(&[0xB3, 0x89]);
+    cpu.undocumented_opcodes = true;
+    cpu.bus.set_word(0x89, 0x0300);
+    cpu.bus.set(0x0300, 0x89);
+
+    assert!(trace::line(&cpu).starts_with("C000  B3 89    *LAX ($89),Y = 0300 @ 0300 = 89  A:00"));
+}
+
+#[test]
+fn traces_state_before_each_instruction() {
+    // LDX #$05 INX
+    let mut cpu = cpu_with(&[0xA2, 0x05, 0xE8]);
+    cpu.tick().unwrap();
+
+    assert_eq!(
+        trace::line(&cpu),
+        "C002  E8        INX                             A:00 X:05 Y:00 P:24 SP:FD CYC:9"
+    );
+}
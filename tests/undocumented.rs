@@ -0,0 +1,102 @@
+use cpu6502::{
+    assembler::assemble_variant,
+    cpu::{addressable_bus::DataBus, error::CpuError, status::StatusFlag, variant::Variant, Cpu},
+    stack_memory::StackMemory,
+};
+
+/// Assembles `source` at $8000 and runs `count` of its instructions,
+/// with the undocumented opcodes turned on
+fn run(source: &str, count: usize) -> Cpu<StackMemory> {
+    let assembly = assemble_variant(source, Variant::Nmos6502, true).unwrap();
+    let mut memory = StackMemory::new();
+    assembly.load_into(&mut memory);
+
+    let mut cpu = Cpu::load_memory(memory);
+    cpu.undocumented_opcodes = true;
+    cpu.program_counter = assembly.origin;
+
+    for _ in 0..count {
+        cpu.tick().unwrap();
+    }
+
+    cpu
+}
+
+fn flags(cpu: &Cpu<StackMemory>) -> [bool; 4] {
+    [
+        StatusFlag::Negative,
+        StatusFlag::Overflow,
+        StatusFlag::Zero,
+        StatusFlag::Carry,
+    ]
+    .map(|flag| cpu.processor_status.get_flag(flag))
+}
+
+#[test]
+fn lax_loads_both_registers() {
+    let mut cpu = run("* = $8000\n lda #$90\n sta $20\n ldy #$01\n lax $1f,y", 3);
+    cpu.bus.set(0x20, 0x90);
+
+    // the operand is zero page, so this is the zp,Y form
+    assert_eq!(cpu.tick().unwrap().cycles, 4);
+    assert_eq!((cpu.accumulator, cpu.x_register), (0x90, 0x90));
+    assert_eq!(flags(&cpu), [true, false, false, false]);
+}
+
+#[test]
+fn dcp_decrements_and_compares() {
+    let cpu = run("* = $8000\n lda #$43\n sta $10\n lda #$42\n dcp $10", 4);
+
+    assert_eq!(cpu.bus.peek(0x10), 0x42);
+    assert_eq!(cpu.accumulator, 0x42);
+    assert_eq!(flags(&cpu), [false, false, true, true]);
+}
+
+#[test]
+fn slo_shifts_memory_and_ors_it_in() {
+    let cpu = run("* = $8000\n lda #$81\n sta $10\n lda #$01\n slo $10", 4);
+
+    assert_eq!(cpu.bus.peek(0x10), 0x02);
+    assert_eq!(cpu.accumulator, 0x03);
+    assert_eq!(flags(&cpu), [false, false, false, true]);
+}
+
+#[test]
+fn arr_takes_its_flags_from_the_adder() {
+    // binary: C and V come from bits 6 and 5 of the result
+    let cpu = run("* = $8000\n clc\n lda #$ff\n arr #$40", 3);
+    assert_eq!(cpu.accumulator, 0x20);
+    assert_eq!(flags(&cpu), [false, true, false, false]);
+
+    let cpu = run("* = $8000\n sec\n lda #$ff\n arr #$c0", 3);
+    assert_eq!(cpu.accumulator, 0xE0);
+    assert_eq!(flags(&cpu), [true, false, false, true]);
+
+    // decimal: both nibbles of $99 get fixed up after the rotation to $4C
+    let cpu = run("* = $8000\n sed\n clc\n lda #$99\n arr #$ff", 4);
+    assert_eq!(cpu.accumulator, 0xA2);
+    assert_eq!(flags(&cpu), [false, true, false, true]);
+}
+
+#[test]
+fn jam_locks_the_cpu_up() {
+    let mut cpu = run("* = $8000\n nop\n jam", 1);
+
+    for _ in 0..2 {
+        assert!(matches!(cpu.tick(), Err(CpuError::Jammed(0x02))));
+        assert_eq!(cpu.program_counter, 0x8001);
+    }
+
+    // only a reset gets it going again
+    cpu.bus.set_word(0xFFFC, 0x8000);
+    cpu.reset();
+    assert!(cpu.tick().is_ok());
+}
+
+#[test]
+fn undocumented_opcodes_are_unknown_unless_enabled() {
+    let mut cpu = run("* = $8000\n lax $10", 0);
+    cpu.undocumented_opcodes = false;
+
+    assert!(matches!(cpu.tick(), Err(CpuError::UnknownOpcode(0xA7))));
+}